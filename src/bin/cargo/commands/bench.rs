@@ -80,6 +80,12 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     let ops = TestOptions {
         no_run: args.is_present("no-run"),
         no_fail_fast: args.is_present("no-fail-fast"),
+        parallel_binaries: false,
+        junit: None,
+        partition: None,
+        list_partitions: false,
+        test_timeout: None,
+        report_slow: None,
         compile_opts,
     };
 
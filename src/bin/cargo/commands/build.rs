@@ -14,6 +14,7 @@ pub fn cli() -> App {
             "Exclude packages from the build",
         )
         .arg_jobs()
+        .arg_keep_going()
         .arg_targets_all(
             "Build only this package's library",
             "Build only the specified binary",
@@ -28,7 +29,7 @@ pub fn cli() -> App {
         )
         .arg_release("Build artifacts in release mode, with optimizations")
         .arg_features()
-        .arg_target_triple("Build for the target triple")
+        .arg_target_triples("Build for the target triple")
         .arg_target_dir()
         .arg(
             opt(
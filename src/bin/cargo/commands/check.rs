@@ -14,6 +14,7 @@ pub fn cli() -> App {
             "Exclude packages from the check",
         )
         .arg_jobs()
+        .arg_keep_going()
         .arg_targets_all(
             "Check only this package's library",
             "Check only the specified binary",
@@ -29,7 +30,7 @@ pub fn cli() -> App {
         .arg_release("Check artifacts in release mode, with optimizations")
         .arg(opt("profile", "Profile to build the selected target for").value_name("PROFILE"))
         .arg_features()
-        .arg_target_triple("Check for the target triple")
+        .arg_target_triples("Check for the target triple")
         .arg_target_dir()
         .arg_manifest_path()
         .arg_message_format()
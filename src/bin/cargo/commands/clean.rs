@@ -8,7 +8,7 @@ pub fn cli() -> App {
         .arg(opt("quiet", "No output printed to stdout").short("q"))
         .arg_package_spec_simple("Package to clean artifacts for")
         .arg_manifest_path()
-        .arg_target_triple("Target triple to clean output for")
+        .arg_target_triples("Target triple to clean output for")
         .arg_target_dir()
         .arg_release("Whether or not to clean release artifacts")
         .arg_doc("Whether or not to clean just the documentation directory")
@@ -27,7 +27,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     let opts = CleanOptions {
         config,
         spec: values(args, "package"),
-        target: args.target(),
+        targets: args.targets(),
         release: args.is_present("release"),
         doc: args.is_present("doc"),
     };
@@ -25,7 +25,7 @@ pub fn cli() -> App {
         )
         .arg_release("Build artifacts in release mode, with optimizations")
         .arg_features()
-        .arg_target_triple("Build for the target triple")
+        .arg_target_triples("Build for the target triple")
         .arg_target_dir()
         .arg_manifest_path()
         .arg_message_format()
@@ -8,7 +8,7 @@ pub fn cli() -> App {
         .about("Fetch dependencies of a package from the network")
         .arg(opt("quiet", "No output printed to stdout").short("q"))
         .arg_manifest_path()
-        .arg_target_triple("Fetch dependencies for the target triple")
+        .arg_target_triples("Fetch dependencies for the target triple")
         .after_help(
             "\
 If a lock file is available, this command will ensure that all of the Git
@@ -28,7 +28,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
 
     let opts = FetchOptions {
         config,
-        target: args.target(),
+        targets: args.targets(),
     };
     ops::fetch(&ws, &opts)?;
     Ok(())
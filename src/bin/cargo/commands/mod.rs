@@ -18,6 +18,7 @@ pub fn builtin() -> Vec<App> {
         login::cli(),
         metadata::cli(),
         new::cli(),
+        outdated::cli(),
         owner::cli(),
         package::cli(),
         pkgid::cli(),
@@ -54,6 +55,7 @@ pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches<'_>) -> Cli
         "login" => login::exec,
         "metadata" => metadata::exec,
         "new" => new::exec,
+        "outdated" => outdated::exec,
         "owner" => owner::exec,
         "package" => package::exec,
         "pkgid" => pkgid::exec,
@@ -90,6 +92,7 @@ pub mod locate_project;
 pub mod login;
 pub mod metadata;
 pub mod new;
+pub mod outdated;
 pub mod owner;
 pub mod package;
 pub mod pkgid;
@@ -0,0 +1,72 @@
+use crate::command_prelude::*;
+
+use cargo::ops::{self, OutdatedOptions};
+
+pub fn cli() -> App {
+    subcommand("outdated")
+        .about("Show dependencies which have newer versions available")
+        .arg(opt("quiet", "No output printed to stdout").short("q"))
+        .arg_package_spec_workspace(
+            "Package(s) to check the dependencies of",
+            "Check the dependencies of all packages in the workspace",
+            "Don't check the dependencies of specified packages",
+        )
+        .arg(
+            opt(
+                "depth",
+                "Only check dependencies up to DEPTH levels deep (1 for direct dependencies)",
+            )
+            .value_name("DEPTH"),
+        )
+        .arg(opt(
+            "exit-code",
+            "Exit with status 1 if any dependency is outdated or yanked",
+        ))
+        .arg(
+            opt("message-format", "Output format")
+                .value_name("FMT")
+                .case_insensitive(true)
+                .possible_values(&["human", "json"]),
+        )
+        .arg_manifest_path()
+        .after_help(
+            "\
+This command lists the dependencies from registries which have newer versions
+available than the ones in `Cargo.lock`, or whose locked version has been
+yanked. For each of them, it shows the locked version, the newest version
+allowed by every requirement on the dependency, which `cargo update` would
+update to, and the newest version overall.
+
+If the `--package` argument is given, then SPEC is a package ID specification
+which indicates whose dependencies should be checked. If it is not given, then
+the dependencies of the current package are checked. All packages in the
+workspace are checked if the `--workspace` flag is supplied. For more
+information on SPEC and its format, see the `cargo help pkgid` command.
+
+All dependencies, including indirect ones, are checked unless `--depth` is
+given.
+
+With `--message-format json`, a JSON object is printed for each dependency
+listed, and with `--exit-code` the command fails if any dependency is listed,
+which is useful to check that dependencies are kept up to date in CI.
+",
+        )
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    let ws = args.workspace(config)?;
+
+    let packages = args.packages_from_workspace_flags()?;
+
+    let opts = OutdatedOptions {
+        config,
+        packages,
+        depth: args.value_of_u32("depth")?,
+        message_format: args.message_format(),
+    };
+    let outdated = ops::outdated(&ws, &opts)?;
+    if args.is_present("exit-code") && outdated > 0 {
+        return Err(CliError::code(1));
+    }
+    Ok(())
+}
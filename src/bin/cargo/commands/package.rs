@@ -13,10 +13,21 @@ pub fn cli() -> App {
             )
             .short("l"),
         )
+        .arg(
+            opt("message-format", "Output format of --list")
+                .value_name("FMT")
+                .case_insensitive(true)
+                .possible_values(&["human", "json"])
+                .requires("list"),
+        )
         .arg(opt(
             "no-verify",
             "Don't verify the contents by building them",
         ))
+        .arg(opt(
+            "verify-reproducible",
+            "Package twice and check that both tarballs are identical",
+        ))
         .arg(opt(
             "no-metadata",
             "Ignore warnings about a lack of human-usable metadata",
@@ -25,20 +36,58 @@ pub fn cli() -> App {
             "allow-dirty",
             "Allow dirty working directories to be packaged",
         ))
+        .arg_package_spec_workspace(
+            "Package(s) to assemble",
+            "Assemble all packages in the workspace",
+            "Don't assemble specified packages",
+        )
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
         .arg_features()
         .arg_manifest_path()
         .arg_jobs()
+        .after_help(
+            "\
+If the `--package` argument is given, then SPEC is a package ID specification
+which indicates which package should be assembled. If it is not given, then the
+current package is assembled. For more information on SPEC and its format, see
+the `cargo help pkgid` command.
+
+All packages in the workspace are assembled if the `--workspace` flag is
+supplied. Note that `--exclude` has to be specified in conjunction with the
+`--workspace` flag.
+
+Every selected package is assembled before any of them is verified. Packages
+depending on other selected packages are verified against the freshly created
+tarballs rather than against the versions already published, if any.
+
+Tarballs are reproducible: their entries are sorted and have normalized
+ownership and permissions, and are all dated from the `SOURCE_DATE_EPOCH`
+environment variable, or from a fixed date if it is unset. The
+`--verify-reproducible` flag packages everything twice and fails if the two
+tarballs differ.
+
+With `--message-format json`, `--list` prints a JSON object per file, giving
+its path within the package, where it comes from (`vcs` for files listed from
+git, `walk` for files found in the package directory and `generated` for files
+created while packaging), its size and the `include` or `exclude` pattern which
+matched it. Files left out by an `exclude` pattern are listed as well, with
+`included` set to false.
+",
+        )
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     let ws = args.workspace(config)?;
+
+    let to_package = args.packages_from_workspace_flags()?;
+
     ops::package(
         &ws,
         &PackageOpts {
             config,
             verify: !args.is_present("no-verify"),
+            verify_reproducible: args.is_present("verify-reproducible"),
             list: args.is_present("list"),
             check_metadata: !args.is_present("no-metadata"),
             allow_dirty: args.is_present("allow-dirty"),
@@ -47,6 +96,8 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
             features: args._values_of("features"),
             all_features: args.is_present("all-features"),
             no_default_features: args.is_present("no-default-features"),
+            to_package,
+            message_format: args.message_format(),
         },
     )?;
     Ok(())
@@ -16,6 +16,11 @@ pub fn cli() -> App {
             "allow-dirty",
             "Allow dirty working directories to be packaged",
         ))
+        .arg_package_spec_workspace(
+            "Package(s) to publish",
+            "Publish all packages in the workspace",
+            "Exclude packages from publishing",
+        )
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
         .arg_manifest_path()
@@ -23,6 +28,24 @@ pub fn cli() -> App {
         .arg_jobs()
         .arg_dry_run("Perform all checks without uploading")
         .arg(opt("registry", "Registry to publish to").value_name("REGISTRY"))
+        .after_help(
+            "\
+If the `--package` argument is given, then SPEC is a package ID specification
+which indicates which package should be published. If it is not given, then the
+current package is published. For more information on SPEC and its format, see
+the `cargo help pkgid` command.
+
+All packages in the workspace are published if the `--workspace` flag is
+supplied. Note that `--exclude` has to be specified in conjunction with the
+`--workspace` flag. Members whose `publish` value doesn't allow the registry,
+such as ones with `publish = false`, are then skipped.
+
+When several packages are published, they are published in dependency order.
+Every package is packaged and verified before anything is uploaded, resolving
+dependencies on the other packages being published to the versions about to be
+uploaded. Publishing stops at the first failed upload.
+",
+        )
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
@@ -30,6 +53,8 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     let ws = args.workspace(config)?;
     let index = args.index(config)?;
 
+    let to_publish = args.packages_from_workspace_flags()?;
+
     ops::publish(
         &ws,
         &PublishOpts {
@@ -45,6 +70,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
             features: args._values_of("features"),
             all_features: args.is_present("all-features"),
             no_default_features: args.is_present("no-default-features"),
+            to_publish,
         },
     )?;
     Ok(())
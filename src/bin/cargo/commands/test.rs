@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use cargo::ops::{self, CompileFilter, FilterRule, LibRule};
 
 use crate::command_prelude::*;
@@ -40,12 +42,46 @@ pub fn cli() -> App {
         .arg(opt("doc", "Test only this library's documentation"))
         .arg(opt("no-run", "Compile, but don't run tests"))
         .arg(opt("no-fail-fast", "Run all tests regardless of failure"))
+        .arg(opt(
+            "parallel-binaries",
+            "Run up to `--jobs` test binaries at once, summarizing the failures at the end",
+        ))
+        .arg(opt("junit", "Write a JUnit XML report of the tests to PATH").value_name("PATH"))
+        .arg(
+            opt(
+                "partition",
+                "Only run the tests in partition N of M, assigned by `count` or `hash`",
+            )
+            .value_name("count:N/M|hash:N/M"),
+        )
+        .arg(
+            opt(
+                "list-partitions",
+                "Print the tests in each partition instead of running them",
+            )
+            .requires("partition"),
+        )
+        .arg(
+            opt(
+                "test-timeout",
+                "Kill test binaries running for longer than SECS, and fail",
+            )
+            .value_name("SECS"),
+        )
+        .arg(
+            opt(
+                "report-slow",
+                "Warn about test binaries running for longer than SECS",
+            )
+            .value_name("SECS"),
+        )
         .arg_package_spec(
             "Package to run tests for",
             "Test all packages in the workspace",
             "Exclude packages from the test",
         )
         .arg_jobs()
+        .arg_keep_going()
         .arg_release("Build artifacts in release mode, with optimizations")
         .arg_features()
         .arg_target_triple("Build for the target triple")
@@ -74,10 +110,11 @@ All packages in the workspace are tested if the `--all` flag is supplied. The
 Note that `--exclude` has to be specified in conjunction with the `--all` flag.
 
 The `--jobs` argument affects the building of the test executable but does
-not affect how many jobs are used when running the tests. The default value
-for the `--jobs` argument is the number of CPUs. If you want to control the
-number of simultaneous running test cases, pass the `--test-threads` option
-to the test binaries:
+not affect how many jobs are used when running the tests, unless
+`--parallel-binaries` is passed, in which case it also bounds the number of
+test binaries running at once. The default value for the `--jobs` argument is
+the number of CPUs. If you want to control the number of simultaneous running
+test cases, pass the `--test-threads` option to the test binaries:
 
     cargo test -- --test-threads=1
 
@@ -109,6 +146,12 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     let test_args = test_args.collect::<Vec<_>>();
 
     let no_run = args.is_present("no-run");
+    if compile_opts.build_config.keep_going && !no_run {
+        return Err(CliError::new(
+            failure::format_err!("--keep-going can only be used with --no-run"),
+            101,
+        ));
+    }
     let doc = args.is_present("doc");
     if doc {
         if let CompileFilter::Only { .. } = compile_opts.filter {
@@ -143,9 +186,24 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
         }
     }
 
+    let partition = match args.value_of("partition") {
+        Some(partition) => Some(ops::Partition::parse(partition)?),
+        None => None,
+    };
+
     let ops = ops::TestOptions {
         no_run,
         no_fail_fast: args.is_present("no-fail-fast"),
+        parallel_binaries: args.is_present("parallel-binaries"),
+        junit: args.value_of_path("junit", config),
+        partition,
+        list_partitions: args.is_present("list-partitions"),
+        test_timeout: args
+            .value_of_u32("test-timeout")?
+            .map(|secs| Duration::from_secs(u64::from(secs))),
+        report_slow: args
+            .value_of_u32("report-slow")?
+            .map(|secs| Duration::from_secs(u64::from(secs))),
         compile_opts,
     };
 
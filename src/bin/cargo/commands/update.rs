@@ -1,5 +1,6 @@
 use crate::command_prelude::*;
 
+use cargo::core::resolver::ResolveVersion;
 use cargo::ops::{self, UpdateOptions};
 
 pub fn cli() -> App {
@@ -11,9 +12,29 @@ pub fn cli() -> App {
             "aggressive",
             "Force updating all dependencies of <name> as well",
         ))
+        .arg(opt(
+            "breaking",
+            "Upgrade requirements in manifests to allow incompatible versions",
+        ))
         .arg_dry_run("Don't actually write the lockfile")
         .arg(opt("precise", "Update a single dependency to exactly PRECISE").value_name("PRECISE"))
+        .arg(
+            opt(
+                "lockfile-version",
+                "Write the lockfile in format VERSION (1 or 2)",
+            )
+            .value_name("VERSION")
+            .possible_values(&["1", "2"]),
+        )
+        .arg(
+            opt(
+                "explain",
+                "Explain why SPEC was selected, or why resolution failed",
+            )
+            .value_name("SPEC"),
+        )
         .arg_manifest_path()
+        .arg_message_format()
         .after_help(
             "\
 This command requires that a `Cargo.lock` already exists as generated by
@@ -34,6 +55,26 @@ be updated to.
 If SPEC is not given, then all dependencies will be re-resolved and
 updated.
 
+With `--breaking`, the requirements of the workspace members' registry
+dependencies are changed in their `Cargo.toml` to allow the newest version
+when they don't already allow it or a version compatible with it, and only
+those dependencies are updated. If SPEC is given, only the dependencies it
+names are upgraded.
+
+The lockfile keeps the format it was written in. `--lockfile-version 2`
+rewrites it in the newer format, which is less prone to merge conflicts, but
+can't be read by older versions of Cargo. Without SPEC, this doesn't update
+any dependency.
+
+With `--explain`, the requirements on the packages matching SPEC after the
+update are printed, traced back to the workspace members, along with why
+their versions were selected. If the dependencies can't be resolved, the steps
+which led to the conflict are printed instead.
+
+With `--message-format json`, a JSON message is printed to stdout for each
+package which is added, removed, upgraded, downgraded or moved to another
+source, which together with `--dry-run` shows what an update would do.
+
 For more information about package ID specifications, see `cargo help pkgid`.
 ",
         )
@@ -44,9 +85,17 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
 
     let update_opts = UpdateOptions {
         aggressive: args.is_present("aggressive"),
+        breaking: args.is_present("breaking"),
         precise: args.value_of("precise"),
         to_update: values(args, "package"),
         dry_run: args.is_present("dry-run"),
+        message_format: args.message_format(),
+        lockfile_version: match args.value_of("lockfile-version") {
+            Some("1") => Some(ResolveVersion::V1),
+            Some(_) => Some(ResolveVersion::V2),
+            None => None,
+        },
+        explain: args.value_of("explain"),
         config,
     };
     ops::update_lockfile(&ws, &update_opts)?;
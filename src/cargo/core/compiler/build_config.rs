@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::path::Path;
 
 use serde::ser;
@@ -9,9 +10,9 @@ use crate::util::{CargoResult, CargoResultExt, Config, RustfixDiagnosticServer};
 /// Configuration information for a rustc build.
 #[derive(Debug)]
 pub struct BuildConfig {
-    /// The target arch triple.
-    /// Default: host arch.
-    pub requested_target: Option<String>,
+    /// The target arch triples to compile for.
+    /// Default (empty): host arch.
+    pub requested_targets: Vec<String>,
     /// Number of rustc jobs to run in parallel.
     pub jobs: u32,
     /// `true` if we are building for release.
@@ -24,6 +25,9 @@ pub struct BuildConfig {
     pub force_rebuild: bool,
     /// Output a build plan to stdout instead of actually compiling.
     pub build_plan: bool,
+    /// Keep building the units which don't depend on a failed one, instead
+    /// of stopping at the first error.
+    pub keep_going: bool,
     /// An optional wrapper, if any, used to wrap rustc invocations
     pub rustc_wrapper: Option<ProcessBuilder>,
     pub rustfix_diagnostic_server: RefCell<Option<RustfixDiagnosticServer>>,
@@ -34,46 +38,56 @@ impl BuildConfig {
     /// configured options are:
     ///
     /// * `build.jobs`
-    /// * `build.target`
+    /// * `build.target` (a single target or a list of targets)
     /// * `target.$target.ar`
     /// * `target.$target.linker`
     /// * `target.$target.libfoo.metadata`
     pub fn new(
         config: &Config,
         jobs: Option<u32>,
-        requested_target: &Option<String>,
+        requested_targets: &[String],
         mode: CompileMode,
     ) -> CargoResult<BuildConfig> {
-        let requested_target = match requested_target {
-            &Some(ref target) if target.ends_with(".json") => {
+        let mut targets = Vec::new();
+        for target in requested_targets {
+            if target.trim().is_empty() {
+                failure::bail!("target was empty")
+            }
+            let target = if target.ends_with(".json") {
                 let path = Path::new(target).canonicalize().chain_err(|| {
                     failure::format_err!("Target path {:?} is not a valid file", target)
                 })?;
-                Some(
-                    path.into_os_string()
-                        .into_string()
-                        .map_err(|_| failure::format_err!("Target path is not valid unicode"))?,
-                )
-            }
-            other => other.clone(),
-        };
-        if let Some(ref s) = requested_target {
-            if s.trim().is_empty() {
-                failure::bail!("target was empty")
-            }
-        }
-        let cfg_target = match config.get_string("build.target")? {
-            Some(ref target) if target.val.ends_with(".json") => {
-                let path = target.definition.root(config).join(&target.val);
-                let path_string = path
-                    .into_os_string()
+                path.into_os_string()
                     .into_string()
-                    .map_err(|_| failure::format_err!("Target path is not valid unicode"));
-                Some(path_string?)
+                    .map_err(|_| failure::format_err!("Target path is not valid unicode"))?
+            } else {
+                target.clone()
+            };
+            targets.push(target);
+        }
+        if targets.is_empty() {
+            if let Some(cfg_targets) = config.get_string_or_list("build.target")? {
+                for target in cfg_targets.val {
+                    let target = if target.ends_with(".json") {
+                        let path = cfg_targets.definition.root(config).join(&target);
+                        path.into_os_string()
+                            .into_string()
+                            .map_err(|_| failure::format_err!("Target path is not valid unicode"))?
+                    } else {
+                        target
+                    };
+                    targets.push(target);
+                }
             }
-            other => other.map(|t| t.val),
-        };
-        let target = requested_target.or(cfg_target);
+        }
+        let mut seen = HashSet::new();
+        targets.retain(|target| seen.insert(target.clone()));
+        if targets.len() > 1 && !config.cli_unstable().multitarget {
+            failure::bail!(
+                "building for multiple targets is unstable, \
+                 pass `-Z multitarget` to enable it"
+            )
+        }
 
         if jobs == Some(0) {
             failure::bail!("jobs must be at least 1")
@@ -88,13 +102,14 @@ impl BuildConfig {
         let cfg_jobs: Option<u32> = config.get("build.jobs")?;
         let jobs = jobs.or(cfg_jobs).unwrap_or(::num_cpus::get() as u32);
         Ok(BuildConfig {
-            requested_target: target,
+            requested_targets: targets,
             jobs,
             release: false,
             mode,
             message_format: MessageFormat::Human,
             force_rebuild: false,
             build_plan: false,
+            keep_going: false,
             rustc_wrapper: None,
             rustfix_diagnostic_server: RefCell::new(None),
         })
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::str;
 
@@ -6,8 +6,9 @@ use log::debug;
 
 use crate::core::compiler::unit::UnitInterner;
 use crate::core::compiler::{BuildConfig, BuildOutput, Kind, Unit};
+use crate::core::interning::InternedString;
 use crate::core::profiles::Profiles;
-use crate::core::{Dependency, Workspace};
+use crate::core::{Dependency, Package, Workspace};
 use crate::core::{PackageId, PackageSet, Resolve};
 use crate::util::errors::CargoResult;
 use crate::util::{profile, Cfg, Config, Rustc};
@@ -23,6 +24,9 @@ pub struct BuildContext<'a, 'cfg: 'a> {
     pub config: &'cfg Config,
     /// The dependency graph for our build.
     pub resolve: &'a Resolve,
+    /// The dependency graph of the standard library, when it's built from
+    /// source with `-Z build-std`.
+    pub std_resolve: Option<&'a Resolve>,
     pub profiles: &'a Profiles,
     pub build_config: &'a BuildConfig,
     /// Extra compiler args for either `rustc` or `rustdoc`.
@@ -33,9 +37,9 @@ pub struct BuildContext<'a, 'cfg: 'a> {
     pub rustc: Rustc,
     /// Build information for the host arch.
     pub host_config: TargetConfig,
-    /// Build information for the target.
-    pub target_config: TargetConfig,
-    pub target_info: TargetInfo,
+    /// Build information for each target triple in use, keyed by triple.
+    pub target_config: HashMap<InternedString, TargetConfig>,
+    pub target_info: HashMap<InternedString, TargetInfo>,
     pub host_info: TargetInfo,
     pub units: &'a UnitInterner<'a>,
 }
@@ -44,7 +48,9 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
     pub fn new(
         ws: &'a Workspace<'cfg>,
         resolve: &'a Resolve,
+        std_resolve: Option<&'a Resolve>,
         packages: &'a PackageSet<'cfg>,
+        roots: &[&Package],
         config: &'cfg Config,
         build_config: &'a BuildConfig,
         profiles: &'a Profiles,
@@ -56,24 +62,61 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
             rustc.set_wrapper(wrapper.clone());
         }
 
+        // Every triple which may be compiled for: the requested targets, plus
+        // any selected by the packages being built or by artifact dependencies.
+        let mut triples = BTreeSet::new();
+        let root_kinds = roots
+            .iter()
+            .filter(|pkg| ws.is_member(pkg))
+            .flat_map(|pkg| root_kinds(build_config, pkg));
+        for kind in requested_kinds(build_config).into_iter().chain(root_kinds) {
+            if let Kind::Target(triple) = kind {
+                triples.insert(triple);
+            }
+        }
+        for id in resolve.iter() {
+            for (_, deps) in resolve.deps(id) {
+                let artifact_targets = deps
+                    .iter()
+                    .filter_map(|dep| dep.artifact().and_then(|a| a.target()));
+                triples.extend(artifact_targets);
+            }
+        }
+
         let host_config = TargetConfig::new(config, &rustc.host)?;
-        let target_config = match build_config.requested_target.as_ref() {
-            Some(triple) => TargetConfig::new(config, triple)?,
-            None => host_config.clone(),
-        };
+        let mut target_config = HashMap::new();
+        for &triple in triples.iter() {
+            target_config.insert(triple, TargetConfig::new(config, &triple)?);
+        }
         let (host_info, target_info) = {
             let _p = profile::start("BuildContext::probe_target_info");
             debug!("probe_target_info");
             let host_info =
-                TargetInfo::new(config, &build_config.requested_target, &rustc, Kind::Host)?;
-            let target_info =
-                TargetInfo::new(config, &build_config.requested_target, &rustc, Kind::Target)?;
+                TargetInfo::new(config, &build_config.requested_targets, &rustc, Kind::Host)?;
+            let mut target_info = HashMap::new();
+            for &triple in triples.iter() {
+                // Without `--target` the flags for the host and for a target
+                // of the same triple are the same, so rustc's answers are too.
+                let is_host = triple.as_str() == rustc.host;
+                let info = if is_host && build_config.requested_targets.is_empty() {
+                    host_info.clone()
+                } else {
+                    TargetInfo::new(
+                        config,
+                        &build_config.requested_targets,
+                        &rustc,
+                        Kind::Target(triple),
+                    )?
+                };
+                target_info.insert(triple, info);
+            }
             (host_info, target_info)
         };
 
         Ok(BuildContext {
             ws,
             resolve,
+            std_resolve,
             packages,
             config,
             rustc,
@@ -88,13 +131,34 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
         })
     }
 
+    /// Gets the dependency graph which the package of `unit` belongs to.
+    pub fn unit_resolve(&self, unit: &Unit<'_>) -> &'a Resolve {
+        if unit.is_std {
+            self.std_resolve
+                .expect("standard library units require `-Z build-std`")
+        } else {
+            self.resolve
+        }
+    }
+
     pub fn extern_crate_name(&self, unit: &Unit<'a>, dep: &Unit<'a>) -> CargoResult<String> {
-        self.resolve
-            .extern_crate_name(unit.pkg.package_id(), dep.pkg.package_id(), dep.target)
+        // The standard library isn't a dependency listed in the manifest, so
+        // it always goes by its own name.
+        if dep.is_std && !unit.is_std {
+            return Ok(dep.target.crate_name());
+        }
+        self.unit_resolve(unit).extern_crate_name(
+            unit.pkg.package_id(),
+            dep.pkg.package_id(),
+            dep.target,
+        )
     }
 
     pub fn is_public_dependency(&self, unit: &Unit<'a>, dep: &Unit<'a>) -> bool {
-        self.resolve
+        if dep.is_std && !unit.is_std {
+            return true;
+        }
+        self.unit_resolve(unit)
             .is_public_dep(unit.pkg.package_id(), dep.pkg.package_id())
     }
 
@@ -107,11 +171,7 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
             Some(p) => p,
             None => return true,
         };
-        let (name, info) = match kind {
-            Kind::Host => (self.host_triple(), &self.host_info),
-            Kind::Target => (self.target_triple(), &self.target_info),
-        };
-        platform.matches(name, info.cfg())
+        platform.matches(self.triple(kind), self.info(kind).cfg())
     }
 
     /// Gets the user-specified linker for a particular host or target.
@@ -126,11 +186,7 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
 
     /// Gets the list of `cfg`s printed out from the compiler for the specified kind.
     pub fn cfg(&self, kind: Kind) -> &[Cfg] {
-        let info = match kind {
-            Kind::Host => &self.host_info,
-            Kind::Target => &self.target_info,
-        };
-        info.cfg().unwrap_or(&[])
+        self.info(kind).cfg().unwrap_or(&[])
     }
 
     /// Gets the host architecture triple.
@@ -143,19 +199,53 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
         &self.rustc.host
     }
 
+    /// Gets the first requested target triple, or the host triple if no
+    /// target was requested.
     pub fn target_triple(&self) -> &str {
         self.build_config
-            .requested_target
-            .as_ref()
+            .requested_targets
+            .first()
             .map(|s| s.as_str())
             .unwrap_or_else(|| self.host_triple())
     }
 
+    /// Gets the architecture triple units of the given kind are compiled for.
+    pub fn triple(&self, kind: Kind) -> &str {
+        match kind {
+            Kind::Host => self.host_triple(),
+            Kind::Target(triple) => triple.as_str(),
+        }
+    }
+
+    /// Gets the kinds that the root units of `pkg` are compiled for.
+    ///
+    /// This is the package's `forced-target` if it has one, otherwise the
+    /// `--target` flags, and finally the package's `default-target`. Only
+    /// workspace members may choose their own target.
+    pub fn root_kinds(&self, pkg: &Package) -> Vec<Kind> {
+        if self.ws.is_member(pkg) {
+            root_kinds(self.build_config, pkg)
+        } else {
+            requested_kinds(self.build_config)
+        }
+    }
+
+    /// Gets the kinds of the targets requested for this build, which is just
+    /// the host if no `--target` flag was passed.
+    pub fn requested_kinds(&self) -> Vec<Kind> {
+        requested_kinds(self.build_config)
+    }
+
+    /// Gets the kind of the first target requested for this build.
+    pub fn default_kind(&self) -> Kind {
+        self.requested_kinds()[0]
+    }
+
     /// Gets the target configuration for a particular host or target.
-    fn target_config(&self, kind: Kind) -> &TargetConfig {
+    pub fn target_config(&self, kind: Kind) -> &TargetConfig {
         match kind {
             Kind::Host => &self.host_config,
-            Kind::Target => &self.target_config,
+            Kind::Target(triple) => &self.target_config[&triple],
         }
     }
 
@@ -176,10 +266,12 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
         pkg.source_id().is_path() || self.config.extra_verbose()
     }
 
-    fn info(&self, kind: Kind) -> &TargetInfo {
+    /// Gets the information learned from the compiler about a particular
+    /// host or target.
+    pub fn info(&self, kind: Kind) -> &TargetInfo {
         match kind {
             Kind::Host => &self.host_info,
-            Kind::Target => &self.target_info,
+            Kind::Target(triple) => &self.target_info[&triple],
         }
     }
 
@@ -188,6 +280,30 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
     }
 }
 
+fn root_kinds(build_config: &BuildConfig, pkg: &Package) -> Vec<Kind> {
+    let manifest = pkg.manifest();
+    if let Some(triple) = manifest.forced_target() {
+        return vec![Kind::Target(InternedString::new(triple))];
+    }
+    match manifest.default_target() {
+        Some(triple) if build_config.requested_targets.is_empty() => {
+            vec![Kind::Target(InternedString::new(triple))]
+        }
+        _ => requested_kinds(build_config),
+    }
+}
+
+fn requested_kinds(build_config: &BuildConfig) -> Vec<Kind> {
+    if build_config.requested_targets.is_empty() {
+        return vec![Kind::Host];
+    }
+    build_config
+        .requested_targets
+        .iter()
+        .map(|triple| Kind::Target(InternedString::new(triple)))
+        .collect()
+}
+
 /// Information required to build for a target.
 #[derive(Clone, Default)]
 pub struct TargetConfig {
@@ -15,6 +15,9 @@ pub struct TargetInfo {
     crate_types: RefCell<HashMap<String, Option<(String, String)>>>,
     cfg: Option<Vec<Cfg>>,
     pub sysroot_libdir: Option<PathBuf>,
+    /// Path to the libraries of the standard library for this target, which
+    /// for the host differs from `sysroot_libdir`.
+    pub sysroot_target_libdir: Option<PathBuf>,
     pub rustflags: Vec<String>,
     pub rustdocflags: Vec<String>,
 }
@@ -28,6 +31,9 @@ pub enum FileFlavor {
     Linkable,
     /// Piece of external debug information (e.g., `.dSYM`/`.pdb` file).
     DebugInfo,
+    /// The metadata of a library, emitted next to the library itself when
+    /// pipelining so that dependents can start compiling early.
+    Rmeta,
 }
 
 pub struct FileType {
@@ -56,13 +62,13 @@ impl FileType {
 impl TargetInfo {
     pub fn new(
         config: &Config,
-        requested_target: &Option<String>,
+        requested_targets: &[String],
         rustc: &Rustc,
         kind: Kind,
     ) -> CargoResult<TargetInfo> {
         let rustflags = env_args(
             config,
-            requested_target,
+            requested_targets,
             &rustc.host,
             None,
             kind,
@@ -77,12 +83,8 @@ impl TargetInfo {
             .args(&rustflags)
             .env_remove("RUST_LOG");
 
-        let target_triple = requested_target
-            .as_ref()
-            .map(|s| s.as_str())
-            .unwrap_or(&rustc.host);
-        if kind == Kind::Target {
-            process.arg("--target").arg(target_triple);
+        if let Kind::Target(target_triple) = kind {
+            process.arg("--target").arg(&*target_triple);
         }
 
         let crate_type_process = process.clone();
@@ -113,6 +115,7 @@ impl TargetInfo {
         }
 
         let mut sysroot_libdir = None;
+        let mut sysroot_target_libdir = None;
         if has_cfg_and_sysroot {
             let line = match lines.next() {
                 Some(line) => line,
@@ -121,21 +124,22 @@ impl TargetInfo {
                      target-specific information from rustc"
                 ),
             };
-            let mut rustlib = PathBuf::from(line);
-            if kind == Kind::Host {
-                if cfg!(windows) {
-                    rustlib.push("bin");
-                } else {
-                    rustlib.push("lib");
-                }
-                sysroot_libdir = Some(rustlib);
-            } else {
-                rustlib.push("lib");
-                rustlib.push("rustlib");
-                rustlib.push(target_triple);
-                rustlib.push("lib");
-                sysroot_libdir = Some(rustlib);
-            }
+            let sysroot = PathBuf::from(line);
+            let target_triple = match kind {
+                Kind::Host => rustc.host.as_str(),
+                Kind::Target(target_triple) => target_triple.as_str(),
+            };
+            let target_libdir = sysroot
+                .join("lib")
+                .join("rustlib")
+                .join(target_triple)
+                .join("lib");
+            sysroot_libdir = Some(match kind {
+                Kind::Host if cfg!(windows) => sysroot.join("bin"),
+                Kind::Host => sysroot.join("lib"),
+                Kind::Target(_) => target_libdir.clone(),
+            });
+            sysroot_target_libdir = Some(target_libdir);
         }
 
         let cfg = if has_cfg_and_sysroot {
@@ -148,11 +152,12 @@ impl TargetInfo {
             crate_type_process: Some(crate_type_process),
             crate_types: RefCell::new(map),
             sysroot_libdir,
+            sysroot_target_libdir,
             // recalculate `rustflags` from above now that we have `cfg`
             // information
             rustflags: env_args(
                 config,
-                requested_target,
+                requested_targets,
                 &rustc.host,
                 cfg.as_ref().map(|v| v.as_ref()),
                 kind,
@@ -160,7 +165,7 @@ impl TargetInfo {
             )?,
             rustdocflags: env_args(
                 config,
-                requested_target,
+                requested_targets,
                 &rustc.host,
                 cfg.as_ref().map(|v| v.as_ref()),
                 kind,
@@ -334,7 +339,7 @@ fn parse_crate_type(
 /// scripts, ...), even if it is the same as the target.
 fn env_args(
     config: &Config,
-    requested_target: &Option<String>,
+    requested_targets: &[String],
     host_triple: &str,
     target_cfg: Option<&[Cfg]>,
     kind: Kind,
@@ -354,13 +359,14 @@ fn env_args(
     //
     // 2) If --target *is* specified then we only apply RUSTFLAGS
     // to compilation units with the Target kind, which indicates
-    // it was chosen by the --target flag.
+    // it was chosen by the --target flag (or a package's
+    // `forced-target`).
     //
     // This means that, e.g., even if the specified --target is the
     // same as the host, build scripts in plugins won't get
     // RUSTFLAGS.
-    let compiling_with_target = requested_target.is_some();
-    let is_target_kind = kind == Kind::Target;
+    let compiling_with_target = !requested_targets.is_empty();
+    let is_target_kind = kind != Kind::Host;
 
     if compiling_with_target && !is_target_kind {
         // This is probably a build script or plugin and we're
@@ -386,10 +392,10 @@ fn env_args(
         .flat_map(|c| c.to_lowercase())
         .collect::<String>();
     // Then the target.*.rustflags value...
-    let target = requested_target
-        .as_ref()
-        .map(|s| s.as_str())
-        .unwrap_or(host_triple);
+    let target = match kind {
+        Kind::Host => host_triple,
+        Kind::Target(target) => target.as_str(),
+    };
     let key = format!("target.{}.{}", target, name);
     if let Some(args) = config.get_list_or_split_string(&key)? {
         let args = args.val.into_iter();
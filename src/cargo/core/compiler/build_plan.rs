@@ -13,6 +13,7 @@ use serde::Serialize;
 
 use super::context::OutputFile;
 use super::{CompileMode, Context, Kind, Unit};
+use crate::core::interning::InternedString;
 use crate::core::TargetKind;
 use crate::util::{internal, CargoResult, ProcessBuilder};
 
@@ -22,6 +23,9 @@ struct Invocation {
     package_version: semver::Version,
     target_kind: TargetKind,
     kind: Kind,
+    /// The triple a `Target` kind is compiled for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_triple: Option<InternedString>,
     compile_mode: CompileMode,
     deps: Vec<usize>,
     outputs: Vec<PathBuf>,
@@ -51,6 +55,10 @@ impl Invocation {
             package_name: id.name().to_string(),
             package_version: id.version().clone(),
             kind: unit.kind,
+            target_triple: match unit.kind {
+                Kind::Host => None,
+                Kind::Target(triple) => Some(triple),
+            },
             target_kind: unit.target.kind().clone(),
             compile_mode: unit.mode,
             deps,
@@ -5,7 +5,7 @@ use std::path::PathBuf;
 
 use semver::Version;
 
-use super::BuildContext;
+use super::{BuildContext, Kind};
 use crate::core::{Edition, Package, PackageId, Target};
 use crate::util::{self, join_paths, process, CargoResult, CfgExpr, Config, ProcessBuilder};
 
@@ -14,6 +14,8 @@ pub struct Doctest {
     pub package: Package,
     /// The target being tested (currently always the package's lib).
     pub target: Target,
+    /// The kind the target is compiled for.
+    pub kind: Kind,
     /// Extern dependencies needed by `rustdoc`. The path is the location of
     /// the compiled lib.
     pub deps: Vec<(String, PathBuf)>,
@@ -21,11 +23,13 @@ pub struct Doctest {
 
 /// A structure returning the result of a compilation.
 pub struct Compilation<'cfg> {
-    /// An array of all tests created during this compilation.
-    pub tests: Vec<(Package, Target, PathBuf)>,
+    /// An array of all tests created during this compilation, along with
+    /// the kind they are compiled for.
+    pub tests: Vec<(Package, Target, Kind, PathBuf)>,
 
-    /// An array of all binaries created.
-    pub binaries: Vec<PathBuf>,
+    /// An array of all binaries created, along with the kind they are
+    /// compiled for.
+    pub binaries: Vec<(Kind, PathBuf)>,
 
     /// All directories for the output of native build commands.
     ///
@@ -35,12 +39,12 @@ pub struct Compilation<'cfg> {
     /// The order should be deterministic.
     pub native_dirs: BTreeSet<PathBuf>,
 
-    /// Root output directory (for the local package's artifacts)
-    pub root_output: PathBuf,
+    /// Root output directory (for the local package's artifacts) of each
+    /// kind.
+    pub root_output: HashMap<Kind, PathBuf>,
 
-    /// Output directory for rust dependencies.
-    /// May be for the host or for a specific target.
-    pub deps_output: PathBuf,
+    /// Output directory for rust dependencies of each kind.
+    pub deps_output: HashMap<Kind, PathBuf>,
 
     /// Output directory for the rust host dependencies.
     pub host_deps_output: PathBuf,
@@ -48,8 +52,8 @@ pub struct Compilation<'cfg> {
     /// The path to rustc's own libstd
     pub host_dylib_path: Option<PathBuf>,
 
-    /// The path to libstd for the target
-    pub target_dylib_path: Option<PathBuf>,
+    /// The path to libstd for each kind
+    pub target_dylib_path: HashMap<Kind, PathBuf>,
 
     /// Extra environment variables that were passed to compilations and should
     /// be passed to future invocations of programs.
@@ -65,12 +69,12 @@ pub struct Compilation<'cfg> {
     pub rustdocflags: HashMap<PackageId, Vec<String>>,
 
     pub host: String,
-    pub target: String,
 
     config: &'cfg Config,
     rustc_process: ProcessBuilder,
 
-    target_runner: Option<(PathBuf, Vec<String>)>,
+    /// The runner of each kind, from `target.<triple>.runner`.
+    target_runners: HashMap<Kind, (PathBuf, Vec<String>)>,
 }
 
 impl<'cfg> Compilation<'cfg> {
@@ -84,14 +88,27 @@ impl<'cfg> Compilation<'cfg> {
         if let Some(server) = &*srv {
             server.configure(&mut rustc);
         }
+        let kinds = Some(Kind::Host)
+            .into_iter()
+            .chain(bcx.target_info.keys().map(|&triple| Kind::Target(triple)));
+        let mut target_dylib_path = HashMap::new();
+        let mut target_runners = HashMap::new();
+        for kind in kinds {
+            if let Some(path) = &bcx.info(kind).sysroot_target_libdir {
+                target_dylib_path.insert(kind, path.clone());
+            }
+            if let Some(runner) = target_runner(bcx, kind)? {
+                target_runners.insert(kind, runner);
+            }
+        }
         Ok(Compilation {
             // TODO: deprecated; remove.
             native_dirs: BTreeSet::new(),
-            root_output: PathBuf::from("/"),
-            deps_output: PathBuf::from("/"),
+            root_output: HashMap::new(),
+            deps_output: HashMap::new(),
             host_deps_output: PathBuf::from("/"),
             host_dylib_path: bcx.host_info.sysroot_libdir.clone(),
-            target_dylib_path: bcx.target_info.sysroot_libdir.clone(),
+            target_dylib_path,
             tests: Vec::new(),
             binaries: Vec::new(),
             extra_env: HashMap::new(),
@@ -101,14 +118,21 @@ impl<'cfg> Compilation<'cfg> {
             config: bcx.config,
             rustc_process: rustc,
             host: bcx.host_triple().to_string(),
-            target: bcx.target_triple().to_string(),
-            target_runner: target_runner(bcx)?,
+            target_runners,
         })
     }
 
+    /// Gets the architecture triple units of the given kind are compiled for.
+    pub fn triple(&self, kind: Kind) -> &str {
+        match kind {
+            Kind::Host => &self.host,
+            Kind::Target(ref triple) => triple.as_str(),
+        }
+    }
+
     /// See `process`.
     pub fn rustc_process(&self, pkg: &Package, target: &Target) -> CargoResult<ProcessBuilder> {
-        let mut p = self.fill_env(self.rustc_process.clone(), pkg, true)?;
+        let mut p = self.fill_env(self.rustc_process.clone(), pkg, None)?;
         if target.edition() != Edition::Edition2015 {
             p.arg(format!("--edition={}", target.edition()));
         }
@@ -116,8 +140,13 @@ impl<'cfg> Compilation<'cfg> {
     }
 
     /// See `process`.
-    pub fn rustdoc_process(&self, pkg: &Package, target: &Target) -> CargoResult<ProcessBuilder> {
-        let mut p = self.fill_env(process(&*self.config.rustdoc()?), pkg, false)?;
+    pub fn rustdoc_process(
+        &self,
+        pkg: &Package,
+        target: &Target,
+        kind: Kind,
+    ) -> CargoResult<ProcessBuilder> {
+        let mut p = self.fill_env(process(&*self.config.rustdoc()?), pkg, Some(kind))?;
         if target.edition() != Edition::Edition2015 {
             p.arg(format!("--edition={}", target.edition()));
         }
@@ -130,20 +159,20 @@ impl<'cfg> Compilation<'cfg> {
         cmd: T,
         pkg: &Package,
     ) -> CargoResult<ProcessBuilder> {
-        self.fill_env(process(cmd), pkg, true)
-    }
-
-    fn target_runner(&self) -> &Option<(PathBuf, Vec<String>)> {
-        &self.target_runner
+        self.fill_env(process(cmd), pkg, None)
     }
 
     /// See `process`.
+    ///
+    /// The process runs an artifact compiled for `kind`, with the runner
+    /// configured for that kind, if any.
     pub fn target_process<T: AsRef<OsStr>>(
         &self,
         cmd: T,
         pkg: &Package,
+        kind: Kind,
     ) -> CargoResult<ProcessBuilder> {
-        let builder = if let Some((ref runner, ref args)) = *self.target_runner() {
+        let builder = if let Some((runner, args)) = self.target_runners.get(&kind) {
             let mut builder = process(runner);
             builder.args(args);
             builder.arg(cmd);
@@ -151,31 +180,37 @@ impl<'cfg> Compilation<'cfg> {
         } else {
             process(cmd)
         };
-        self.fill_env(builder, pkg, false)
+        self.fill_env(builder, pkg, Some(kind))
     }
 
     /// Prepares a new process with an appropriate environment to run against
     /// the artifacts produced by the build process.
     ///
     /// The package argument is also used to configure environment variables as
-    /// well as the working directory of the child process.
+    /// well as the working directory of the child process. `kind` is the kind
+    /// of the artifacts the process runs against, or `None` for processes
+    /// running build tools on the host, such as `rustc`.
     fn fill_env(
         &self,
         mut cmd: ProcessBuilder,
         pkg: &Package,
-        is_host: bool,
+        kind: Option<Kind>,
     ) -> CargoResult<ProcessBuilder> {
-        let mut search_path = if is_host {
-            let mut search_path = vec![self.host_deps_output.clone()];
-            search_path.extend(self.host_dylib_path.clone());
-            search_path
-        } else {
-            let mut search_path =
-                super::filter_dynamic_search_path(self.native_dirs.iter(), &self.root_output);
-            search_path.push(self.deps_output.clone());
-            search_path.push(self.root_output.clone());
-            search_path.extend(self.target_dylib_path.clone());
-            search_path
+        let mut search_path = match kind {
+            None => {
+                let mut search_path = vec![self.host_deps_output.clone()];
+                search_path.extend(self.host_dylib_path.clone());
+                search_path
+            }
+            Some(kind) => {
+                let root_output = &self.root_output[&kind];
+                let mut search_path =
+                    super::filter_dynamic_search_path(self.native_dirs.iter(), root_output);
+                search_path.push(self.deps_output[&kind].clone());
+                search_path.push(root_output.clone());
+                search_path.extend(self.target_dylib_path.get(&kind).cloned());
+                search_path
+            }
         };
 
         let dylib_path = util::dylib_path();
@@ -254,8 +289,11 @@ fn pre_version_component(v: &Version) -> String {
     ret
 }
 
-fn target_runner(bcx: &BuildContext<'_, '_>) -> CargoResult<Option<(PathBuf, Vec<String>)>> {
-    let target = bcx.target_triple();
+fn target_runner(
+    bcx: &BuildContext<'_, '_>,
+    kind: Kind,
+) -> CargoResult<Option<(PathBuf, Vec<String>)>> {
+    let target = bcx.triple(kind);
 
     // try target.{}.runner
     let key = format!("target.{}.runner", target);
@@ -264,7 +302,8 @@ fn target_runner(bcx: &BuildContext<'_, '_>) -> CargoResult<Option<(PathBuf, Vec
     }
 
     // try target.'cfg(...)'.runner
-    if let Some(target_cfg) = bcx.target_info.cfg() {
+    let info = bcx.info(kind);
+    if let Some(target_cfg) = info.cfg() {
         if let Some(table) = bcx.config.get_table("target")? {
             let mut matching_runner = None;
 
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fmt;
 use std::hash::{Hash, Hasher, SipHasher};
@@ -10,6 +10,7 @@ use log::info;
 
 use super::{BuildContext, Context, FileFlavor, Kind, Layout};
 use crate::core::compiler::Unit;
+use crate::core::interning::InternedString;
 use crate::core::{TargetKind, Workspace};
 use crate::util::{self, CargoResult};
 
@@ -53,8 +54,8 @@ impl fmt::Display for Metadata {
 pub struct CompilationFiles<'a, 'cfg: 'a> {
     /// The target directory layout for the host (and target if it is the same as host).
     pub(super) host: Layout,
-    /// The target directory layout for the target (if different from then host).
-    pub(super) target: Option<Layout>,
+    /// The target directory layouts for each target triple compiled for.
+    pub(super) target: HashMap<InternedString, Layout>,
     /// Additional directory to include a copy of the outputs.
     export_dir: Option<PathBuf>,
     /// The root targets requested by the user on the command line (does not
@@ -64,6 +65,8 @@ pub struct CompilationFiles<'a, 'cfg: 'a> {
     metas: HashMap<Unit<'a>, Option<Metadata>>,
     /// For each Unit, a list all files produced.
     outputs: HashMap<Unit<'a>, LazyCell<Arc<Vec<OutputFile>>>>,
+    /// The units which also emit their metadata separately when built.
+    rmeta_required: HashSet<Unit<'a>>,
 }
 
 #[derive(Debug)]
@@ -93,7 +96,7 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
     pub(super) fn new(
         roots: &[Unit<'a>],
         host: Layout,
-        target: Option<Layout>,
+        target: HashMap<InternedString, Layout>,
         export_dir: Option<PathBuf>,
         ws: &'a Workspace<'cfg>,
         cx: &Context<'a, 'cfg>,
@@ -107,6 +110,11 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
             .cloned()
             .map(|unit| (unit, LazyCell::new()))
             .collect();
+        let rmeta_required = metas
+            .keys()
+            .filter(|unit| cx.rmeta_required(unit))
+            .cloned()
+            .collect();
         CompilationFiles {
             ws,
             host,
@@ -115,6 +123,7 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
             roots: roots.to_vec(),
             metas,
             outputs,
+            rmeta_required,
         }
     }
 
@@ -122,7 +131,7 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
     pub fn layout(&self, kind: Kind) -> &Layout {
         match kind {
             Kind::Host => &self.host,
-            Kind::Target => self.target.as_ref().unwrap_or(&self.host),
+            Kind::Target(triple) => &self.target[&triple],
         }
     }
 
@@ -167,11 +176,6 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         }
     }
 
-    /// Returns the root of the build output tree for the target
-    pub fn target_root(&self) -> &Path {
-        self.target.as_ref().unwrap_or(&self.host).dest()
-    }
-
     /// Returns the root of the build output tree for the host
     pub fn host_root(&self) -> &Path {
         self.host.dest()
@@ -192,6 +196,12 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         self.layout(unit.kind).fingerprint().join(dir)
     }
 
+    /// Returns the file where the messages of the compiler are cached with
+    /// `-Z cache-messages`, to be replayed when the unit is fresh.
+    pub fn message_cache_path(&self, unit: &Unit<'a>) -> PathBuf {
+        self.fingerprint_dir(unit).join("output")
+    }
+
     /// Returns the directory where a compiled build script is stored.
     /// `/path/to/target/{debug,release}/build/PKG-HASH`
     pub fn build_script_dir(&self, unit: &Unit<'a>) -> PathBuf {
@@ -261,8 +271,9 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         // it was compiled into something like `example/` or `doc/` then
         // we don't want to link it up.
         if out_dir.ends_with("deps") {
-            // Don't lift up library dependencies.
-            if unit.target.is_bin() || self.roots.contains(unit) {
+            // Don't lift up library dependencies, nor the binaries of
+            // artifact dependencies outside of the workspace.
+            if (unit.target.is_bin() && self.ws.is_member(unit.pkg)) || self.roots.contains(unit) {
                 Some((
                     out_dir.parent().unwrap().to_owned(),
                     if unit.mode.is_any_test() {
@@ -291,11 +302,7 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         let out_dir = self.out_dir(unit);
         let file_stem = self.file_stem(unit);
         let link_stem = self.link_stem(unit);
-        let info = if unit.kind == Kind::Host {
-            &bcx.host_info
-        } else {
-            &bcx.target_info
-        };
+        let info = bcx.info(unit.kind);
 
         let mut ret = Vec::new();
         let mut unsupported = Vec::new();
@@ -321,7 +328,7 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
                         crate_type,
                         flavor,
                         unit.target.kind(),
-                        bcx.target_triple(),
+                        bcx.triple(unit.kind),
                     )?;
 
                     match file_types {
@@ -380,6 +387,15 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
                         }
                     }
                 }
+                if self.rmeta_required.contains(unit) {
+                    let path = out_dir.join(format!("lib{}.rmeta", file_stem));
+                    ret.push(OutputFile {
+                        path,
+                        hardlink: None,
+                        export_path: None,
+                        flavor: FileFlavor::Rmeta,
+                    });
+                }
             }
         }
         if ret.is_empty() {
@@ -389,14 +405,14 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
                      does not support these crate types",
                     unsupported.join(", "),
                     unit.pkg,
-                    bcx.target_triple()
+                    bcx.triple(unit.kind)
                 )
             }
             failure::bail!(
                 "cannot compile `{}` as the target `{}` does not \
                  support any of the output crate types",
                 unit.pkg,
-                bcx.target_triple()
+                bcx.triple(unit.kind)
             );
         }
         info!("Target filenames: {:?}", ret);
@@ -450,7 +466,7 @@ fn compute_metadata<'a, 'cfg>(
     if !(unit.mode.is_any_test() || unit.mode.is_check())
         && (unit.target.is_dylib()
             || unit.target.is_cdylib()
-            || (unit.target.is_executable() && bcx.target_triple().starts_with("wasm32-")))
+            || (unit.target.is_executable() && bcx.triple(unit.kind).starts_with("wasm32-")))
         && unit.pkg.package_id().source_id().is_path()
         && __cargo_default_lib_metadata.is_err()
     {
@@ -477,10 +493,16 @@ fn compute_metadata<'a, 'cfg>(
 
     // Also mix in enabled features to our metadata. This'll ensure that
     // when changing feature sets each lib is separately cached.
-    bcx.resolve
+    bcx.unit_resolve(unit)
         .features_sorted(unit.pkg.package_id())
         .hash(&mut hasher);
 
+    // The standard library's crates may share a package with the user's
+    // dependencies, so keep their outputs apart.
+    if unit.is_std {
+        unit.is_std.hash(&mut hasher);
+    }
+
     // Mix in the target-metadata of all the dependencies of this target.
     {
         let mut deps_metadata = cx
@@ -1,5 +1,5 @@
 #![allow(deprecated)]
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fmt::Write;
 use std::path::PathBuf;
@@ -21,6 +21,7 @@ use super::layout::Layout;
 use super::{BuildContext, Compilation, CompileMode, Executor, FileFlavor, Kind};
 
 mod unit_dependencies;
+pub use self::unit_dependencies::active_deps;
 use self::unit_dependencies::build_unit_dependencies;
 
 mod compilation_files;
@@ -42,6 +43,15 @@ pub struct Context<'a, 'cfg: 'a> {
     unit_dependencies: HashMap<Unit<'a>, Vec<Unit<'a>>>,
     files: Option<CompilationFiles<'a, 'cfg>>,
     package_cache: HashMap<PackageId, &'a Package>,
+
+    /// Whether dependents which only need the metadata of a library may start
+    /// compiling as soon as rustc has emitted it, rather than waiting for the
+    /// whole library to be built.
+    pipelining: bool,
+
+    /// The units whose metadata some dependent needs as soon as possible,
+    /// so rustc emits it separately for them when pipelining.
+    rmeta_required: HashSet<Unit<'a>>,
 }
 
 impl<'a, 'cfg> Context<'a, 'cfg> {
@@ -60,6 +70,16 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                 .chain_err(|| "failed to create jobserver")?,
         };
 
+        // Pipelining is opt-in for now, it relies on rustc emitting artifact
+        // notifications, which older compilers don't support. A build plan
+        // is run by other tools, which don't know how to start dependents
+        // early, so it is never pipelined.
+        let pipelining = config
+            .get_bool("build.pipelining")?
+            .map(|t| t.val)
+            .unwrap_or(false)
+            && !bcx.build_config.build_plan;
+
         Ok(Self {
             bcx,
             compilation: Compilation::new(bcx)?,
@@ -76,6 +96,8 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             unit_dependencies: HashMap::new(),
             files: None,
             package_cache: HashMap::new(),
+            pipelining,
+            rmeta_required: HashSet::new(),
         })
     }
 
@@ -125,10 +147,11 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                     self.compilation.tests.push((
                         unit.pkg.clone(),
                         unit.target.clone(),
+                        unit.kind,
                         output.path.clone(),
                     ));
                 } else if unit.target.is_executable() {
-                    self.compilation.binaries.push(bindst.clone());
+                    self.compilation.binaries.push((unit.kind, bindst.clone()));
                 }
             }
 
@@ -179,11 +202,12 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                 self.compilation.to_doc_test.push(compilation::Doctest {
                     package: unit.pkg.clone(),
                     target: unit.target.clone(),
+                    kind: unit.kind,
                     deps: doctest_deps,
                 });
             }
 
-            let feats = self.bcx.resolve.features(unit.pkg.package_id());
+            let feats = self.bcx.unit_resolve(unit).features(unit.pkg.package_id());
             if !feats.is_empty() {
                 self.compilation
                     .cfgs
@@ -253,11 +277,6 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         } else {
             "debug"
         };
-        let host_layout = Layout::new(self.bcx.ws, None, dest)?;
-        let target_layout = match self.bcx.build_config.requested_target.as_ref() {
-            Some(target) => Some(Layout::new(self.bcx.ws, Some(target), dest)?),
-            None => None,
-        };
         self.primary_packages
             .extend(units.iter().map(|u| u.pkg.package_id()));
 
@@ -267,10 +286,32 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             &mut self.unit_dependencies,
             &mut self.package_cache,
         )?;
+        self.record_units_requiring_metadata();
+
+        // Each target triple gets its own directory, but only those which
+        // are actually compiled for are created.
+        let host_layout = Layout::new(self.bcx.ws, None, dest)?;
+        let mut triples = self
+            .unit_dependencies
+            .keys()
+            .chain(units)
+            .filter_map(|unit| match unit.kind {
+                Kind::Host => None,
+                Kind::Target(triple) => Some(triple),
+            })
+            .collect::<BTreeSet<_>>();
+        if let Kind::Target(triple) = self.bcx.default_kind() {
+            triples.insert(triple);
+        }
+        let mut target_layouts = HashMap::new();
+        for triple in triples {
+            target_layouts.insert(triple, Layout::new(self.bcx.ws, Some(&triple), dest)?);
+        }
+
         let files = CompilationFiles::new(
             units,
             host_layout,
-            target_layout,
+            target_layouts,
             export_dir,
             self.bcx.ws,
             self,
@@ -288,7 +329,7 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             .host
             .prepare()
             .chain_err(|| internal("couldn't prepare build directories"))?;
-        if let Some(ref mut target) = self.files.as_mut().unwrap().target {
+        for target in self.files_mut().target.values_mut() {
             target
                 .prepare()
                 .chain_err(|| internal("couldn't prepare build directories"))?;
@@ -297,9 +338,18 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         self.compilation.host_deps_output = self.files_mut().host.deps().to_path_buf();
 
         let files = self.files.as_ref().unwrap();
-        let layout = files.target.as_ref().unwrap_or(&files.host);
-        self.compilation.root_output = layout.dest().to_path_buf();
-        self.compilation.deps_output = layout.deps().to_path_buf();
+        let kinds = Some(Kind::Host)
+            .into_iter()
+            .chain(files.target.keys().map(|&triple| Kind::Target(triple)));
+        for kind in kinds {
+            let layout = files.layout(kind);
+            self.compilation
+                .root_output
+                .insert(kind, layout.dest().to_path_buf());
+            self.compilation
+                .deps_output
+                .insert(kind, layout.deps().to_path_buf());
+        }
         Ok(())
     }
 
@@ -340,6 +390,34 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         self.unit_dependencies[unit].clone()
     }
 
+    /// Records the units whose metadata is needed by a dependent before the
+    /// units are fully built.
+    fn record_units_requiring_metadata(&mut self) {
+        for (key, deps) in self.unit_dependencies.iter() {
+            for dep in deps {
+                if self.only_requires_rmeta(key, dep) {
+                    self.rmeta_required.insert(*dep);
+                }
+            }
+        }
+    }
+
+    /// Returns whether `parent` can be compiled against just the metadata of
+    /// `dep`, in which case it may start before `dep` is fully built.
+    pub fn only_requires_rmeta(&self, parent: &Unit<'a>, dep: &Unit<'a>) -> bool {
+        self.pipelining
+            && parent.mode == CompileMode::Build
+            && !parent.requires_upstream_objects()
+            && dep.mode == CompileMode::Build
+            && !dep.requires_upstream_objects()
+    }
+
+    /// Returns whether rustc should emit the metadata of `unit` separately,
+    /// and tell Cargo as soon as it is available.
+    pub fn rmeta_required(&self, unit: &Unit<'a>) -> bool {
+        self.rmeta_required.contains(unit)
+    }
+
     pub fn is_primary_package(&self, unit: &Unit<'a>) -> bool {
         self.primary_packages.contains(&unit.pkg.package_id())
     }
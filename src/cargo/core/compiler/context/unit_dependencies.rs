@@ -25,7 +25,7 @@ use crate::core::compiler::Unit;
 use crate::core::dependency::Kind as DepKind;
 use crate::core::package::Downloads;
 use crate::core::profiles::UnitFor;
-use crate::core::{Package, PackageId, Target};
+use crate::core::{Dependency, Package, PackageId, Target};
 use crate::CargoResult;
 
 struct State<'a: 'tmp, 'cfg: 'a, 'tmp> {
@@ -139,50 +139,24 @@ fn compute_deps<'a, 'cfg, 'tmp>(
 
     let bcx = state.bcx;
     let id = unit.pkg.package_id();
-    let deps = bcx.resolve.deps(id).filter(|&(_id, deps)| {
-        assert!(!deps.is_empty());
-        deps.iter().any(|dep| {
-            // If this target is a build command, then we only want build
-            // dependencies, otherwise we want everything *other than* build
-            // dependencies.
-            if unit.target.is_custom_build() != dep.is_build() {
-                return false;
-            }
-
-            // If this dependency is **not** a transitive dependency, then it
-            // only applies to test/example targets.
-            if !dep.is_transitive()
-                && !unit.target.is_test()
-                && !unit.target.is_example()
-                && !unit.mode.is_any_test()
-            {
-                return false;
-            }
-
-            // If this dependency is only available for certain platforms,
-            // make sure we're only enabling it for that platform.
-            if !bcx.dep_platform_activated(dep, unit.kind) {
-                return false;
-            }
-
-            // If the dependency is optional, then we're only activating it
-            // if the corresponding feature was activated
-            if dep.is_optional() && !bcx.resolve.features(id).contains(&*dep.name_in_toml()) {
-                return false;
-            }
-
-            // If we've gotten past all that, then this dependency is
-            // actually used!
-            true
-        })
-    });
+    let deps = bcx
+        .unit_resolve(unit)
+        .deps(id)
+        .map(|(dep_id, _)| (dep_id, active_deps(bcx, unit, dep_id)))
+        .filter(|(_, deps)| !deps.is_empty())
+        .collect::<Vec<_>>();
 
     let mut ret = Vec::new();
-    for (id, _) in deps {
+    for (id, deps) in deps {
         let pkg = match state.get(id)? {
             Some(pkg) => pkg,
             None => continue,
         };
+        ret.extend(artifact_units(unit, bcx, pkg, &deps, unit_for)?);
+        // Artifact dependencies don't link the library unless asked to.
+        if !deps.iter().any(|dep| dep.links_lib()) {
+            continue;
+        }
         let lib = match pkg.targets().iter().find(|t| t.is_lib()) {
             Some(t) => t,
             None => continue,
@@ -190,20 +164,28 @@ fn compute_deps<'a, 'cfg, 'tmp>(
         let mode = check_or_build_mode(unit.mode, lib);
         let dep_unit_for = unit_for.with_for_host(lib.for_host());
 
-        if bcx.config.cli_unstable().dual_proc_macros
-            && lib.proc_macro()
-            && unit.kind == Kind::Target
+        if bcx.config.cli_unstable().dual_proc_macros && lib.proc_macro() && unit.kind != Kind::Host
         {
-            let unit = new_unit(bcx, pkg, lib, dep_unit_for, Kind::Target, mode);
+            let unit = new_unit(bcx, pkg, lib, dep_unit_for, unit.kind, mode, unit.is_std);
             ret.push((unit, dep_unit_for));
-            let unit = new_unit(bcx, pkg, lib, dep_unit_for, Kind::Host, mode);
+            let unit = new_unit(bcx, pkg, lib, dep_unit_for, Kind::Host, mode, unit.is_std);
             ret.push((unit, dep_unit_for));
         } else {
-            let unit = new_unit(bcx, pkg, lib, dep_unit_for, unit.kind.for_target(lib), mode);
+            let unit = new_unit(
+                bcx,
+                pkg,
+                lib,
+                dep_unit_for,
+                unit.kind.for_target(lib),
+                mode,
+                unit.is_std,
+            );
             ret.push((unit, dep_unit_for));
         }
     }
 
+    ret.extend(std_deps(unit, state)?);
+
     // If this target is a build script, then what we've collected so far is
     // all we need. If this isn't a build script, then it depends on the
     // build script if there is one.
@@ -237,7 +219,7 @@ fn compute_deps<'a, 'cfg, 'tmp>(
                     t.is_bin() &&
                         // Skip binaries with required features that have not been selected.
                         t.required_features().unwrap_or(&no_required_features).iter().all(|f| {
-                            bcx.resolve.features(id).contains(f)
+                            bcx.unit_resolve(unit).features(id).contains(f)
                         })
                 })
                 .map(|t| {
@@ -249,6 +231,7 @@ fn compute_deps<'a, 'cfg, 'tmp>(
                             UnitFor::new_normal(),
                             unit.kind.for_target(t),
                             CompileMode::Build,
+                            unit.is_std,
                         ),
                         UnitFor::new_normal(),
                     )
@@ -259,6 +242,116 @@ fn compute_deps<'a, 'cfg, 'tmp>(
     Ok(ret)
 }
 
+/// Returns the dependencies declared by the package of `unit` on the package
+/// `dep_id` which are actually used when compiling `unit`.
+pub fn active_deps<'b>(
+    bcx: &'b BuildContext<'_, '_>,
+    unit: &Unit<'_>,
+    dep_id: PackageId,
+) -> Vec<&'b Dependency> {
+    let id = unit.pkg.package_id();
+    let deps = match bcx
+        .unit_resolve(unit)
+        .deps(id)
+        .find(|&(other, _)| other == dep_id)
+    {
+        Some((_, deps)) => deps,
+        None => return Vec::new(),
+    };
+    assert!(!deps.is_empty());
+    deps.iter()
+        .filter(|dep| {
+            // If this target is a build command, then we only want build
+            // dependencies, otherwise we want everything *other than* build
+            // dependencies.
+            if unit.target.is_custom_build() != dep.is_build() {
+                return false;
+            }
+
+            // If this dependency is **not** a transitive dependency, then it
+            // only applies to test/example targets.
+            if !dep.is_transitive()
+                && !unit.target.is_test()
+                && !unit.target.is_example()
+                && !unit.mode.is_any_test()
+            {
+                return false;
+            }
+
+            // If this dependency is only available for certain platforms,
+            // make sure we're only enabling it for that platform.
+            if !bcx.dep_platform_activated(dep, unit.kind) {
+                return false;
+            }
+
+            // If the dependency is optional, then we're only activating it
+            // if the corresponding feature was activated
+            if dep.is_optional()
+                && !bcx
+                    .unit_resolve(unit)
+                    .features(id)
+                    .contains(&*dep.name_in_toml())
+            {
+                return false;
+            }
+
+            // If we've gotten past all that, then this dependency is
+            // actually used!
+            true
+        })
+        .collect()
+}
+
+/// Returns the units building the artifacts that `deps`, all declared on the
+/// package `dep`, request.
+///
+/// Artifacts are always fully built, even when `unit` is only checked or
+/// documented, since the crate may need the artifact's file to compile.
+fn artifact_units<'a>(
+    unit: &Unit<'a>,
+    bcx: &BuildContext<'a, '_>,
+    dep: &'a Package,
+    deps: &[&Dependency],
+    unit_for: UnitFor,
+) -> CargoResult<Vec<(Unit<'a>, UnitFor)>> {
+    let mut ret = Vec::new();
+    for artifact in deps.iter().filter_map(|d| d.artifact()) {
+        let kind = match artifact.target() {
+            Some(triple) => Kind::Target(triple),
+            None => unit.kind,
+        };
+        let dep_unit_for = unit_for.with_for_host(false);
+        let mut targets = dep
+            .targets()
+            .iter()
+            .filter(|t| artifact.kind().matches(t))
+            .peekable();
+        if targets.peek().is_none() {
+            failure::bail!(
+                "package `{}` depends on `{}` with `artifact = \"{}\"`, \
+                 but `{}` has no such target",
+                unit.pkg,
+                dep.name(),
+                artifact.kind().as_str(),
+                dep.name()
+            );
+        }
+        for target in targets {
+            let unit = new_unit(
+                bcx,
+                dep,
+                target,
+                dep_unit_for,
+                kind,
+                CompileMode::Build,
+                unit.is_std,
+            );
+            ret.push((unit, dep_unit_for));
+        }
+    }
+    Ok(ret)
+}
+
 /// Returns the dependencies needed to run a build script.
 ///
 /// The `unit` provided must represent an execution of a build script, and
@@ -284,6 +377,7 @@ fn compute_deps_custom_build<'a, 'cfg>(
         // Build scripts always compiled for the host.
         Kind::Host,
         CompileMode::Build,
+        unit.is_std,
     );
     // All dependencies of this unit should use profiles for custom
     // builds.
@@ -297,24 +391,40 @@ fn compute_deps_doc<'a, 'cfg, 'tmp>(
 ) -> CargoResult<Vec<(Unit<'a>, UnitFor)>> {
     let bcx = state.bcx;
     let deps = bcx
-        .resolve
+        .unit_resolve(unit)
         .deps(unit.pkg.package_id())
-        .filter(|&(_id, deps)| {
-            deps.iter().any(|dep| match dep.kind() {
-                DepKind::Normal => bcx.dep_platform_activated(dep, unit.kind),
-                _ => false,
-            })
-        });
+        .map(|(id, deps)| {
+            let deps = deps
+                .iter()
+                .filter(|dep| match dep.kind() {
+                    DepKind::Normal => bcx.dep_platform_activated(dep, unit.kind),
+                    _ => false,
+                })
+                .collect::<Vec<_>>();
+            (id, deps)
+        })
+        .filter(|(_, deps)| !deps.is_empty())
+        .collect::<Vec<_>>();
 
     // To document a library, we depend on dependencies actually being
     // built. If we're documenting *all* libraries, then we also depend on
     // the documentation of the library being built.
     let mut ret = Vec::new();
-    for (id, _deps) in deps {
+    for (id, deps) in deps {
         let dep = match state.get(id)? {
             Some(dep) => dep,
             None => continue,
         };
+        ret.extend(artifact_units(
+            unit,
+            bcx,
+            dep,
+            &deps,
+            UnitFor::new_normal(),
+        )?);
+        if !deps.iter().any(|dep| dep.links_lib()) {
+            continue;
+        }
         let lib = match dep.targets().iter().find(|t| t.is_lib()) {
             Some(lib) => lib,
             None => continue,
@@ -323,7 +433,15 @@ fn compute_deps_doc<'a, 'cfg, 'tmp>(
         // However, for plugins/proc macros, deps should be built like normal.
         let mode = check_or_build_mode(unit.mode, lib);
         let dep_unit_for = UnitFor::new_normal().with_for_host(lib.for_host());
-        let lib_unit = new_unit(bcx, dep, lib, dep_unit_for, unit.kind.for_target(lib), mode);
+        let lib_unit = new_unit(
+            bcx,
+            dep,
+            lib,
+            dep_unit_for,
+            unit.kind.for_target(lib),
+            mode,
+            unit.is_std,
+        );
         ret.push((lib_unit, dep_unit_for));
         if let CompileMode::Doc { deps: true } = unit.mode {
             // Document this lib as well.
@@ -334,11 +452,14 @@ fn compute_deps_doc<'a, 'cfg, 'tmp>(
                 dep_unit_for,
                 unit.kind.for_target(lib),
                 unit.mode,
+                unit.is_std,
             );
             ret.push((doc_unit, dep_unit_for));
         }
     }
 
+    ret.extend(std_deps(unit, state)?);
+
     // Be sure to build/run the build script for documented libraries.
     ret.extend(dep_build_script(unit, bcx));
 
@@ -349,6 +470,53 @@ fn compute_deps_doc<'a, 'cfg, 'tmp>(
     Ok(ret)
 }
 
+/// Returns the units of the standard library which `unit` links against
+/// when the standard library is built from source with `-Z build-std`.
+///
+/// Only units compiled for a target get these, as host units use the
+/// prebuilt standard library which ships with the compiler.
+fn std_deps<'a, 'cfg, 'tmp>(
+    unit: &Unit<'a>,
+    state: &mut State<'a, 'cfg, 'tmp>,
+) -> CargoResult<Vec<(Unit<'a>, UnitFor)>> {
+    let bcx = state.bcx;
+    let std_resolve = match bcx.std_resolve {
+        Some(resolve) => resolve,
+        None => return Ok(Vec::new()),
+    };
+    if unit.is_std || unit.kind == Kind::Host || unit.mode == CompileMode::Doctest {
+        return Ok(Vec::new());
+    }
+    let mut crates = bcx
+        .config
+        .cli_unstable()
+        .build_std
+        .clone()
+        .unwrap_or_default();
+    // The test harness needs `test`, even if it wasn't asked for.
+    if unit.mode.is_any_test() && unit.target.harness() && !crates.iter().any(|c| c == "test") {
+        crates.push("test".to_string());
+    }
+
+    let mut ret = Vec::new();
+    for name in crates {
+        let id = std_resolve.query(&name)?;
+        let pkg = match state.get(id)? {
+            Some(pkg) => pkg,
+            None => continue,
+        };
+        let lib = match pkg.targets().iter().find(|t| t.is_lib()) {
+            Some(t) => t,
+            None => continue,
+        };
+        let unit_for = UnitFor::new_normal();
+        let mode = check_or_build_mode(unit.mode, lib);
+        let unit = new_unit(bcx, pkg, lib, unit_for, unit.kind, mode, true);
+        ret.push((unit, unit_for));
+    }
+    Ok(ret)
+}
+
 fn maybe_lib<'a>(
     unit: &Unit<'a>,
     bcx: &BuildContext<'a, '_>,
@@ -356,7 +524,15 @@ fn maybe_lib<'a>(
 ) -> Option<(Unit<'a>, UnitFor)> {
     unit.pkg.targets().iter().find(|t| t.linkable()).map(|t| {
         let mode = check_or_build_mode(unit.mode, t);
-        let unit = new_unit(bcx, unit.pkg, t, unit_for, unit.kind.for_target(t), mode);
+        let unit = new_unit(
+            bcx,
+            unit.pkg,
+            t,
+            unit_for,
+            unit.kind.for_target(t),
+            mode,
+            unit.is_std,
+        );
         (unit, unit_for)
     })
 }
@@ -385,6 +561,7 @@ fn dep_build_script<'a>(
                 bcx.profiles.get_profile_run_custom_build(&unit.profile),
                 unit.kind,
                 CompileMode::RunCustomBuild,
+                unit.is_std,
             );
 
             (unit, UnitFor::new_build())
@@ -416,6 +593,7 @@ fn new_unit<'a>(
     unit_for: UnitFor,
     kind: Kind,
     mode: CompileMode,
+    is_std: bool,
 ) -> Unit<'a> {
     let profile = bcx.profiles.get_profile(
         pkg.package_id(),
@@ -425,7 +603,7 @@ fn new_unit<'a>(
         bcx.build_config.release,
     );
 
-    bcx.units.intern(pkg, target, profile, kind, mode)
+    bcx.units.intern(pkg, target, profile, kind, mode, is_std)
 }
 
 /// Fill in missing dependencies for units of the `RunCustomBuild`
@@ -5,6 +5,7 @@ use std::path::{Path, PathBuf};
 use std::str;
 use std::sync::{Arc, Mutex};
 
+use crate::core::interning::InternedString;
 use crate::core::PackageId;
 use crate::util::errors::{CargoResult, CargoResultExt};
 use crate::util::machine_message;
@@ -146,13 +147,7 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoRes
     cmd.env("OUT_DIR", &script_out_dir)
         .env("CARGO_MANIFEST_DIR", unit.pkg.root())
         .env("NUM_JOBS", &bcx.jobs().to_string())
-        .env(
-            "TARGET",
-            &match unit.kind {
-                Kind::Host => bcx.host_triple(),
-                Kind::Target => bcx.target_triple(),
-            },
-        )
+        .env("TARGET", bcx.triple(unit.kind))
         .env("DEBUG", debug.to_string())
         .env("OPT_LEVEL", &unit.profile.opt_level.to_string())
         .env(
@@ -168,7 +163,7 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoRes
         .env("RUSTDOC", &*bcx.config.rustdoc()?)
         .inherit_jobserver(&cx.jobserver);
 
-    if let Some(ref linker) = bcx.target_config.linker {
+    if let Some(linker) = bcx.linker(unit.kind) {
         cmd.env("RUSTC_LINKER", linker);
     }
 
@@ -176,9 +171,19 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoRes
         cmd.env("CARGO_MANIFEST_LINKS", links);
     }
 
+    // Point the build script at the artifacts of its artifact dependencies,
+    // which are dependencies of compiling the script itself.
+    for dep in cx.dep_targets(build_script_unit) {
+        super::add_artifact_env(&mut cmd, cx, build_script_unit, &dep)?;
+    }
+
     // Be sure to pass along all enabled features for this package, this is the
     // last piece of statically known information that we have.
-    for feat in bcx.resolve.features(unit.pkg.package_id()).iter() {
+    for feat in bcx
+        .unit_resolve(unit)
+        .features(unit.pkg.package_id())
+        .iter()
+    {
         cmd.env(&format!("CARGO_FEATURE_{}", super::envify(feat)), "1");
     }
 
@@ -378,10 +383,18 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoRes
 }
 
 impl BuildState {
-    pub fn new(host_config: &TargetConfig, target_config: &TargetConfig) -> BuildState {
+    pub fn new(
+        host_config: &TargetConfig,
+        target_config: &HashMap<InternedString, TargetConfig>,
+    ) -> BuildState {
         let mut overrides = HashMap::new();
         let i1 = host_config.overrides.iter().map(|p| (p, Kind::Host));
-        let i2 = target_config.overrides.iter().map(|p| (p, Kind::Target));
+        let i2 = target_config.iter().flat_map(|(&triple, config)| {
+            config
+                .overrides
+                .iter()
+                .map(move |p| (p, Kind::Target(triple)))
+        });
         for ((name, output), kind) in i1.chain(i2) {
             overrides.insert((name.clone(), kind), output.clone());
         }
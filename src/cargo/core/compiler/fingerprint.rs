@@ -60,6 +60,7 @@
 //! -C incremental=… flag                      | ✓           |
 //! mtime of sources                           | ✓[^3]       |
 //! RUSTFLAGS/RUSTDOCFLAGS                     | ✓           |
+//! Short messages, with `-Z cache-messages`   | ✓           |
 //!
 //! [^1]: Build script and bin dependencies are not included.
 //!
@@ -88,6 +89,9 @@
 //! - An `invoked.timestamp` file whose filesystem mtime is updated every time
 //!   the Unit is built. This is an experimental feature used for cleaning
 //!   unused artifacts.
+//! - An `output` file with the messages of the last successful compilation,
+//!   which are replayed when the Unit is fresh. This is only written with
+//!   `-Z cache-messages`.
 //!
 //! Note that some units are a little different. A Unit for *running* a build
 //! script or for `rustdoc` does not have a dep-info file (it's not
@@ -212,7 +216,7 @@ use super::job::{
     Freshness::{Dirty, Fresh},
     Job, Work,
 };
-use super::{BuildContext, Context, FileFlavor, Kind, Unit};
+use super::{BuildContext, Context, FileFlavor, MessageFormat, Unit};
 
 /// Determines if a `unit` is up-to-date, and if not prepares necessary work to
 /// update the persisted fingerprint.
@@ -1004,13 +1008,14 @@ fn calculate_normal<'a, 'cfg>(
 ) -> CargoResult<Fingerprint> {
     // Recursively calculate the fingerprint for all of our dependencies.
     //
-    // Skip fingerprints of binaries because they don't actually induce a
-    // recompile, they're just dependencies in the sense that they need to be
-    // built.
+    // Skip fingerprints of binaries of the same package because they don't
+    // actually induce a recompile, they're just dependencies in the sense that
+    // they need to be built. Binaries of artifact dependencies are kept as
+    // the crate may embed them.
     let mut deps = cx
         .dep_targets(unit)
         .iter()
-        .filter(|u| !u.target.is_bin())
+        .filter(|u| !u.target.is_bin() || u.pkg != unit.pkg)
         .map(|dep| DepFingerprint::new(cx, unit, dep))
         .collect::<CargoResult<Vec<_>>>()?;
     deps.sort_by(|a, b| a.pkg_id.cmp(&b.pkg_id));
@@ -1054,7 +1059,16 @@ fn calculate_normal<'a, 'cfg>(
     } else {
         cx.bcx.rustflags_args(unit)
     };
-    let profile_hash = util::hash_u64((&unit.profile, unit.mode, cx.bcx.extra_args_for(unit)));
+    // Cached messages are replayed as they were rendered, so short messages
+    // require the unit to be rebuilt to be rendered in full, and vice versa.
+    let short_messages = cx.bcx.config.cli_unstable().cache_messages
+        && cx.bcx.build_config.message_format == MessageFormat::Short;
+    let profile_hash = util::hash_u64((
+        &unit.profile,
+        unit.mode,
+        cx.bcx.extra_args_for(unit),
+        short_messages,
+    ));
     // Include metadata since it is exposed as environment variables.
     let m = unit.pkg.manifest().metadata();
     let metadata = util::hash_u64((&m.authors, &m.description, &m.homepage, &m.repository));
@@ -1067,7 +1081,9 @@ fn calculate_normal<'a, 'cfg>(
         path: util::hash_u64(super::path_args(cx.bcx, unit).0),
         features: format!(
             "{:?}",
-            cx.bcx.resolve.features_sorted(unit.pkg.package_id())
+            cx.bcx
+                .unit_resolve(unit)
+                .features_sorted(unit.pkg.package_id())
         ),
         deps,
         local: Mutex::new(local),
@@ -1326,10 +1342,8 @@ pub fn dep_info_loc<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> Pa
 fn target_root<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> PathBuf {
     if unit.mode.is_run_custom_build() {
         cx.files().build_script_run_dir(unit)
-    } else if unit.kind == Kind::Host {
-        cx.files().host_root().to_path_buf()
     } else {
-        cx.files().target_root().to_path_buf()
+        cx.files().layout(unit.kind).dest().to_path_buf()
     }
 }
 
@@ -1,4 +1,5 @@
-use std::collections::{HashMap, HashSet};
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io;
 use std::marker;
 use std::process::Output;
@@ -29,7 +30,7 @@ use crate::util::{Progress, ProgressStyle};
 /// actual compilation step of each package. Packages enqueue units of work and
 /// then later on the entire graph is processed and compiled.
 pub struct JobQueue<'a, 'cfg> {
-    queue: DependencyQueue<Unit<'a>, Job>,
+    queue: DependencyQueue<Unit<'a>, Artifact, Job>,
     tx: Sender<Message>,
     rx: Receiver<Message>,
     active: HashMap<u32, Unit<'a>>,
@@ -37,17 +38,33 @@ pub struct JobQueue<'a, 'cfg> {
     documented: HashSet<PackageId>,
     counts: HashMap<PackageId, usize>,
     is_release: bool,
+    keep_going: bool,
     progress: Progress<'cfg>,
     next_id: u32,
 }
 
 pub struct JobState<'a> {
+    /// The ID of the job which is running.
+    id: u32,
     tx: Sender<Message>,
+    /// Whether some dependent needs the metadata of this job's unit before
+    /// it's fully built, and it hasn't been reported as produced yet.
+    rmeta_required: Cell<bool>,
     // Historical versions of Cargo made use of the `'a` argument here, so to
     // leave the door open to future refactorings keep it here.
     _marker: marker::PhantomData<&'a ()>,
 }
 
+/// The piece of a unit's output which a dependent waits for.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+enum Artifact {
+    /// All of the output, once the unit has fully finished.
+    All,
+    /// Only the metadata of a library, which rustc may emit well before
+    /// it's done generating code.
+    Metadata,
+}
+
 enum Message {
     Run(String),
     BuildPlanMsg(String, ProcessBuilder, Arc<Vec<OutputFile>>),
@@ -55,7 +72,7 @@ enum Message {
     Stderr(String),
     FixDiagnostic(diagnostic_server::Message),
     Token(io::Result<Acquired>),
-    Finish(u32, CargoResult<()>),
+    Finish(u32, Artifact, CargoResult<()>),
 }
 
 impl<'a> JobState<'a> {
@@ -74,6 +91,23 @@ impl<'a> JobState<'a> {
             .send(Message::BuildPlanMsg(module_name, cmd, filenames));
     }
 
+    pub fn stderr(&self, err: String) {
+        let _ = self.tx.send(Message::Stderr(err));
+    }
+
+    /// Reports that the metadata of this job's unit has been written, which
+    /// allows dependents which only need the metadata to start.
+    pub fn rmeta_produced(&self) {
+        // Dependents are released on the first notification only, a rustc
+        // or wrapper reporting the metadata twice is otherwise harmless.
+        if !self.rmeta_required.replace(false) {
+            return;
+        }
+        let _ = self
+            .tx
+            .send(Message::Finish(self.id, Artifact::Metadata, Ok(())));
+    }
+
     pub fn capture_output(
         &self,
         cmd: &ProcessBuilder,
@@ -108,6 +142,7 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
             documented: HashSet::new(),
             counts: HashMap::new(),
             is_release: bcx.build_config.release,
+            keep_going: bcx.build_config.keep_going,
             progress,
             next_id: 0,
         }
@@ -127,7 +162,16 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
                 // them, so we don't include this dependency edge in the job graph.
                 !unit.target.is_test() || !unit.target.is_bin()
             })
-            .cloned()
+            .map(|dep| {
+                // Wait for just the metadata of dependencies if that's all we
+                // need to get started.
+                let artifact = if cx.only_requires_rmeta(unit, dep) {
+                    Artifact::Metadata
+                } else {
+                    Artifact::All
+                };
+                (*dep, artifact)
+            })
             .collect::<Vec<_>>();
         self.queue.queue(unit, job, &dependencies);
         *self.counts.entry(unit.pkg.package_id()).or_insert(0) += 1;
@@ -139,7 +183,7 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
     /// This function will spawn off `config.jobs()` workers to build all of the
     /// necessary dependencies, in order. Freshness is propagated as far as
     /// possible along each dependency chain.
-    pub fn execute(&mut self, cx: &mut Context<'_, '_>, plan: &mut BuildPlan) -> CargoResult<()> {
+    pub fn execute(&mut self, cx: &mut Context<'a, '_>, plan: &mut BuildPlan) -> CargoResult<()> {
         let _p = profile::start("executing the job graph");
         self.queue.queue_finished();
 
@@ -176,7 +220,7 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
 
     fn drain_the_queue(
         &mut self,
-        cx: &mut Context<'_, '_>,
+        cx: &mut Context<'a, '_>,
         plan: &mut BuildPlan,
         scope: &Scope<'a>,
         jobserver_helper: &HelperThread,
@@ -195,8 +239,11 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
         //
         // After a job has finished we update our internal state if it was
         // successful and otherwise wait for pending work to finish if it failed
-        // and then immediately return.
+        // and then immediately return. With `--keep-going` a failure only
+        // holds back the units which depend on the failed one instead, and
+        // the failures are reported once nothing is left to build.
         let mut error = None;
+        let mut failed = BTreeSet::new();
         let total = self.queue.len();
         loop {
             // Dequeue as much work as we can, learning about everything
@@ -264,21 +311,36 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
                     Message::FixDiagnostic(msg) => {
                         print.print(&msg)?;
                     }
-                    Message::Finish(id, result) => {
-                        let unit = self.active.remove(&id).unwrap();
-                        info!("end: {:?}", unit);
+                    Message::Finish(id, artifact, result) => {
+                        let unit = match artifact {
+                            // The job is done, so it no longer holds a token.
+                            Artifact::All => {
+                                let unit = self.active.remove(&id).unwrap();
+                                info!("end: {:?}", unit);
 
-                        if !self.active.is_empty() {
-                            assert!(!tokens.is_empty());
-                            drop(tokens.pop());
-                        }
+                                if !self.active.is_empty() {
+                                    assert!(!tokens.is_empty());
+                                    drop(tokens.pop());
+                                }
+                                unit
+                            }
+                            // The job is still running, and will send another
+                            // message once it's done.
+                            Artifact::Metadata => {
+                                info!("end (meta): {:?}", self.active[&id]);
+                                self.active[&id]
+                            }
+                        };
                         match result {
-                            Ok(()) => self.finish(&unit, cx)?,
+                            Ok(()) => self.finish(&unit, artifact, cx)?,
                             Err(e) => {
                                 let msg = "The following warnings were emitted during compilation:";
                                 self.emit_warnings(Some(msg), &unit, cx)?;
 
-                                if !self.active.is_empty() {
+                                if self.keep_going {
+                                    handle_error(&e, &mut *cx.bcx.config.shell());
+                                    failed.insert(unit.pkg.name());
+                                } else if !self.active.is_empty() {
                                     error = Some(failure::format_err!("build failed"));
                                     handle_error(&e, &mut *cx.bcx.config.shell());
                                     cx.bcx.config.shell().warn(
@@ -321,7 +383,18 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
 
         let time_elapsed = util::elapsed(cx.bcx.config.creation_time().elapsed());
 
-        if self.queue.is_empty() {
+        if let Some(e) = error {
+            Err(e)
+        } else if !failed.is_empty() {
+            let names = failed
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>();
+            Err(failure::format_err!(
+                "build failed, could not compile {}",
+                names.join(", ")
+            ))
+        } else if self.queue.is_empty() {
             let message = format!(
                 "{} [{}] target(s) in {}",
                 build_type, opt_type, time_elapsed
@@ -330,8 +403,6 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
                 cx.bcx.config.shell().status("Finished", message)?;
             }
             Ok(())
-        } else if let Some(e) = error {
-            Err(e)
         } else {
             debug!("queue: {:#?}", self.queue);
             Err(internal("finished with jobs still left in the queue"))
@@ -376,7 +447,7 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
         &mut self,
         unit: &Unit<'a>,
         job: Job,
-        cx: &Context<'_, '_>,
+        cx: &Context<'a, '_>,
         scope: &Scope<'a>,
     ) -> CargoResult<()> {
         info!("start: {:?}", unit);
@@ -388,12 +459,26 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
 
         let my_tx = self.tx.clone();
         let fresh = job.freshness();
+        let rmeta_required = cx.rmeta_required(unit);
         let doit = move || {
-            let res = job.run(&JobState {
+            let state = JobState {
+                id,
                 tx: my_tx.clone(),
+                rmeta_required: Cell::new(rmeta_required),
                 _marker: marker::PhantomData,
-            });
-            my_tx.send(Message::Finish(id, res)).unwrap();
+            };
+            let res = job.run(&state);
+
+            // If the metadata was needed but never reported, then either the
+            // job was fresh and didn't run rustc, or it failed. Dependents
+            // must be released in the former case, while in the latter the
+            // build is aborted anyway.
+            if state.rmeta_required.get() && res.is_ok() {
+                my_tx
+                    .send(Message::Finish(id, Artifact::Metadata, Ok(())))
+                    .unwrap();
+            }
+            my_tx.send(Message::Finish(id, Artifact::All, res)).unwrap();
         };
 
         if !cx.bcx.build_config.build_plan {
@@ -439,11 +524,20 @@ impl<'a, 'cfg> JobQueue<'a, 'cfg> {
         Ok(())
     }
 
-    fn finish(&mut self, unit: &Unit<'a>, cx: &mut Context<'_, '_>) -> CargoResult<()> {
-        if unit.mode.is_run_custom_build() && cx.bcx.show_warnings(unit.pkg.package_id()) {
+    fn finish(
+        &mut self,
+        unit: &Unit<'a>,
+        artifact: Artifact,
+        cx: &mut Context<'_, '_>,
+    ) -> CargoResult<()> {
+        if artifact == Artifact::All
+            && unit.mode.is_run_custom_build()
+            && !unit.is_std
+            && cx.bcx.show_warnings(unit.pkg.package_id())
+        {
             self.emit_warnings(None, unit, cx)?;
         }
-        self.queue.finish(unit);
+        self.queue.finish(unit, &artifact);
         Ok(())
     }
 
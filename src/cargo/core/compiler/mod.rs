@@ -9,32 +9,38 @@ mod job;
 mod job_queue;
 mod layout;
 mod output_depinfo;
+pub mod standard_lib;
 mod unit;
 
 use std::env;
 use std::ffi::{OsStr, OsString};
-use std::fs;
-use std::io::{self, Write};
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::{self, Path, PathBuf};
 use std::sync::Arc;
 
 use failure::Error;
+use lazycell::LazyCell;
 use log::debug;
 use same_file::is_same_file;
-use serde::Serialize;
+use serde::{ser, Deserialize, Serialize};
+use serde_json::value::RawValue;
 
 pub use self::build_config::{BuildConfig, CompileMode, MessageFormat};
 pub use self::build_context::{BuildContext, FileFlavor, TargetConfig, TargetInfo};
 use self::build_plan::BuildPlan;
 pub use self::compilation::{Compilation, Doctest};
+use self::context::active_deps;
 pub use self::context::Context;
 pub use self::custom_build::{BuildMap, BuildOutput, BuildScripts};
 pub use self::job::Freshness;
 use self::job::{Job, Work};
-use self::job_queue::JobQueue;
+use self::job_queue::{JobQueue, JobState};
 pub use self::layout::is_bad_artifact_name;
 use self::output_depinfo::output_depinfo;
 pub use crate::core::compiler::unit::{Unit, UnitInterner};
+use crate::core::dependency::ArtifactKind;
+use crate::core::interning::InternedString;
 use crate::core::manifest::TargetSourcePath;
 use crate::core::profiles::{Lto, PanicStrategy, Profile};
 use crate::core::Feature;
@@ -44,13 +50,29 @@ use crate::util::paths;
 use crate::util::{self, machine_message, process, ProcessBuilder};
 use crate::util::{internal, join_paths, profile};
 
-/// Indicates whether an object is for the host architcture or the target architecture.
+/// Indicates whether an object is for the host architcture or a target architecture.
 ///
-/// These will be the same unless cross-compiling.
-#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord, Serialize)]
+/// These will be the same unless cross-compiling. A single build may involve
+/// several target architectures, for example when a package specifies a
+/// `forced-target`, so the `Target` variant carries the triple it is for.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord)]
 pub enum Kind {
     Host,
-    Target,
+    Target(InternedString),
+}
+
+impl Serialize for Kind {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        // Serialized like a plain enum, as it was before it carried a triple.
+        match self {
+            Kind::Host => "Host",
+            Kind::Target(_) => "Target",
+        }
+        .serialize(s)
+    }
 }
 
 /// A glorified callback for executing calls to rustc. Rather than calling rustc
@@ -143,7 +165,7 @@ fn compile<'a, 'cfg: 'a>(
     // we've got everything constructed.
     let p = profile::start(format!("preparing: {}/{}", unit.pkg, unit.target.name()));
     fingerprint::prepare_init(cx, unit)?;
-    cx.links.validate(bcx.resolve, unit)?;
+    cx.links.validate(bcx.unit_resolve(unit), unit)?;
 
     let job = if unit.mode.is_run_custom_build() {
         custom_build::prepare(cx, unit)?
@@ -162,8 +184,10 @@ fn compile<'a, 'cfg: 'a>(
                 rustc(cx, unit, exec)?
             };
             work.then(link_targets(cx, unit, false)?)
-        } else {
+        } else if bcx.config.cli_unstable().cache_messages && !unit.mode.is_doc() {
             // Need to link targets on both the dirty and fresh.
+            replay_output_cache(cx, unit).then(link_targets(cx, unit, true)?)
+        } else {
             link_targets(cx, unit, true)?
         });
 
@@ -227,6 +251,9 @@ fn rustc<'a, 'cfg>(
 
     rustc.args(cx.bcx.rustflags_args(unit));
     let json_messages = cx.bcx.build_config.json_messages();
+    let pipelined = cx.rmeta_required(unit);
+    let cache_messages = cx.bcx.config.cli_unstable().cache_messages;
+    let mut output_options = OutputOptions::new(cx, unit);
     let package_id = unit.pkg.package_id();
     let target = unit.target.clone();
     let mode = unit.mode;
@@ -270,7 +297,9 @@ fn rustc<'a, 'cfg>(
             // If there is both an rmeta and rlib, rustc will prefer to use the
             // rlib, even if it is older. Therefore, we must delete the rlib to
             // force using the new rmeta.
-            if output.path.extension() == Some(OsStr::new("rmeta")) {
+            if output.flavor == FileFlavor::Linkable
+                && output.path.extension() == Some(OsStr::new("rmeta"))
+            {
                 let dst = root.join(&output.path).with_extension("rlib");
                 if dst.exists() {
                     paths::remove_file(&dst)?;
@@ -293,17 +322,36 @@ fn rustc<'a, 'cfg>(
 
         state.running(&rustc);
         let timestamp = paths::set_invocation_time(&fingerprint_dir)?;
-        if json_messages {
-            exec.exec_json(
-                rustc,
-                package_id,
-                &target,
-                mode,
-                &mut assert_is_empty,
-                &mut |line| json_stderr(line, package_id, &target),
-            )
-            .map_err(internal_if_simple_exit_code)
-            .chain_err(|| format!("Could not compile `{}`.", name))?;
+        if json_messages || pipelined || (cache_messages && !build_plan) {
+            // Messages of a previous compilation must not be replayed, even
+            // if this one doesn't emit any.
+            if let Some((path, _)) = &output_options.cache_cell {
+                if path.exists() {
+                    paths::remove_file(path)?;
+                }
+            }
+            let result = exec
+                .exec_json(
+                    rustc,
+                    package_id,
+                    &target,
+                    mode,
+                    &mut assert_is_empty,
+                    &mut |line| {
+                        on_stderr_line(state, line, package_id, &target, &mut output_options)
+                    },
+                )
+                .map_err(internal_if_simple_exit_code)
+                .chain_err(|| format!("Could not compile `{}`.", name));
+            // Only the messages of successful compilations are replayed.
+            if result.is_err() {
+                if let Some((path, _)) = &output_options.cache_cell {
+                    if path.exists() {
+                        paths::remove_file(path)?;
+                    }
+                }
+            }
+            result?;
         } else if build_plan {
             state.build_plan(buildkey, rustc.clone(), outputs.clone());
         } else {
@@ -413,12 +461,13 @@ fn link_targets<'a, 'cfg>(
     let profile = unit.profile;
     let unit_mode = unit.mode;
     let features = bcx
-        .resolve
+        .unit_resolve(unit)
         .features_sorted(package_id)
         .into_iter()
         .map(|s| s.to_owned())
         .collect();
     let json_messages = bcx.build_config.json_messages();
+    let target_triple = bcx.triple(unit.kind).to_string();
     let executable = cx.get_executable(unit)?;
     let mut target = unit.target.clone();
     if let TargetSourcePath::Metabuild = target.src_path() {
@@ -471,6 +520,7 @@ fn link_targets<'a, 'cfg>(
             machine_message::emit(&machine_message::Artifact {
                 package_id,
                 target: &target,
+                target_triple: &target_triple,
                 profile: art_profile,
                 features,
                 filenames: destinations,
@@ -602,12 +652,20 @@ fn prepare_rustc<'a, 'cfg>(
     base.inherit_jobserver(&cx.jobserver);
     build_base_args(cx, &mut base, unit, crate_types)?;
     build_deps_args(&mut base, cx, unit)?;
+    if unit.is_std {
+        // The standard library is full of unstable features, and everything
+        // it doesn't mark as stable itself must stay unstable for users.
+        base.arg("-Z").arg("force-unstable-if-unmarked");
+        base.env("RUSTC_BOOTSTRAP", "1");
+    }
     Ok(base)
 }
 
 fn rustdoc<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoResult<Work> {
     let bcx = cx.bcx;
-    let mut rustdoc = cx.compilation.rustdoc_process(unit.pkg, unit.target)?;
+    let mut rustdoc = cx
+        .compilation
+        .rustdoc_process(unit.pkg, unit.target, unit.kind)?;
     rustdoc.inherit_jobserver(&cx.jobserver);
     rustdoc.arg("--crate-name").arg(&unit.target.crate_name());
     add_path_args(bcx, unit, &mut rustdoc);
@@ -619,10 +677,8 @@ fn rustdoc<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoResult
         add_color(bcx, &mut rustdoc);
     }
 
-    if unit.kind != Kind::Host {
-        if let Some(ref target) = bcx.build_config.requested_target {
-            rustdoc.arg("--target").arg(target);
-        }
+    if let Kind::Target(target) = unit.kind {
+        rustdoc.arg("--target").arg(&*target);
     }
 
     let doc_dir = cx.files().out_dir(unit);
@@ -634,11 +690,14 @@ fn rustdoc<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoResult
 
     rustdoc.arg("-o").arg(doc_dir);
 
-    for feat in bcx.resolve.features_sorted(unit.pkg.package_id()) {
+    for feat in bcx
+        .unit_resolve(unit)
+        .features_sorted(unit.pkg.package_id())
+    {
         rustdoc.arg("--cfg").arg(&format!("feature=\"{}\"", feat));
     }
 
-    add_error_format(bcx, &mut rustdoc);
+    add_error_format(bcx, &mut rustdoc, false, false);
 
     if let Some(args) = bcx.extra_args_for(unit) {
         rustdoc.args(args);
@@ -654,6 +713,12 @@ fn rustdoc<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoResult
     let json_messages = bcx.build_config.json_messages();
     let package_id = unit.pkg.package_id();
     let target = unit.target.clone();
+    let mut output_options = OutputOptions {
+        format: bcx.build_config.message_format,
+        look_for_metadata_directive: false,
+        color: false,
+        cache_cell: None,
+    };
 
     Ok(Work::new(move |state| {
         if let Some(output) = build_state.outputs.lock().unwrap().get(&key) {
@@ -670,7 +735,9 @@ fn rustdoc<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>) -> CargoResult
             rustdoc
                 .exec_with_streaming(
                     &mut assert_is_empty,
-                    &mut |line| json_stderr(line, package_id, &target),
+                    &mut |line| {
+                        on_stderr_line(state, line, package_id, &target, &mut output_options)
+                    },
                     false,
                 )
                 .map(drop)
@@ -720,7 +787,7 @@ fn add_path_args(bcx: &BuildContext<'_, '_>, unit: &Unit<'_>, cmd: &mut ProcessB
 fn add_cap_lints(bcx: &BuildContext<'_, '_>, unit: &Unit<'_>, cmd: &mut ProcessBuilder) {
     // If this is an upstream dep we don't want warnings from, turn off all
     // lints.
-    if !bcx.show_warnings(unit.pkg.package_id()) {
+    if unit.is_std || !bcx.show_warnings(unit.pkg.package_id()) {
         cmd.arg("--cap-lints").arg("allow");
 
     // If this is an upstream dep but we *do* want warnings, make sure that they
@@ -740,7 +807,43 @@ fn add_color(bcx: &BuildContext<'_, '_>, cmd: &mut ProcessBuilder) {
     cmd.args(&["--color", color]);
 }
 
-fn add_error_format(bcx: &BuildContext<'_, '_>, cmd: &mut ProcessBuilder) {
+/// Adds the flags selecting the format of diagnostics. Pipelined units always
+/// use JSON, as that's how rustc reports that their metadata is ready, and so
+/// do units whose messages are cached, so that they can be replayed in any
+/// format. The diagnostics are rendered as requested on their way to the user.
+fn add_error_format(
+    bcx: &BuildContext<'_, '_>,
+    cmd: &mut ProcessBuilder,
+    pipelined: bool,
+    cache_messages: bool,
+) {
+    if pipelined || cache_messages {
+        cmd.arg("--error-format=json");
+        let mut json = if pipelined {
+            String::from("--json=artifacts")
+        } else {
+            String::new()
+        };
+        let mut add = |flag: &str| {
+            json.push_str(if json.is_empty() { "--json=" } else { "," });
+            json.push_str(flag);
+        };
+        if bcx.build_config.message_format == MessageFormat::Short {
+            add("diagnostic-short");
+        }
+        // Cached messages keep their colors, which are removed when they are
+        // replayed if the terminal doesn't support them.
+        if cache_messages
+            || (bcx.build_config.message_format == MessageFormat::Human
+                && bcx.config.shell().supports_color())
+        {
+            add("diagnostic-rendered-ansi");
+        }
+        if !json.is_empty() {
+            cmd.arg(json);
+        }
+        return;
+    }
     match bcx.build_config.message_format {
         MessageFormat::Human => (),
         MessageFormat::Json => {
@@ -778,8 +881,14 @@ fn build_base_args<'a, 'cfg>(
     cmd.arg("--crate-name").arg(&unit.target.crate_name());
 
     add_path_args(bcx, unit, cmd);
-    add_color(bcx, cmd);
-    add_error_format(bcx, cmd);
+    // rustc doesn't accept `--color` together with `--json`, in which case
+    // the color is chosen through the latter.
+    let pipelined = cx.rmeta_required(unit);
+    let cache_messages = bcx.config.cli_unstable().cache_messages;
+    if !pipelined && !cache_messages {
+        add_color(bcx, cmd);
+    }
+    add_error_format(bcx, cmd, pipelined, cache_messages);
 
     if !test {
         for crate_type in crate_types.iter() {
@@ -789,6 +898,8 @@ fn build_base_args<'a, 'cfg>(
 
     if unit.mode.is_check() {
         cmd.arg("--emit=dep-info,metadata");
+    } else if pipelined {
+        cmd.arg("--emit=dep-info,metadata,link");
     } else {
         cmd.arg("--emit=dep-info,link");
     }
@@ -865,7 +976,10 @@ fn build_base_args<'a, 'cfg>(
     // We ideally want deterministic invocations of rustc to ensure that
     // rustc-caching strategies like sccache are able to cache more, so sort the
     // feature list here.
-    for feat in bcx.resolve.features_sorted(unit.pkg.package_id()) {
+    for feat in bcx
+        .unit_resolve(unit)
+        .features_sorted(unit.pkg.package_id())
+    {
         cmd.arg("--cfg").arg(&format!("feature=\"{}\"", feat));
     }
 
@@ -894,16 +1008,8 @@ fn build_base_args<'a, 'cfg>(
         }
     }
 
-    if unit.kind == Kind::Target {
-        opt(
-            cmd,
-            "--target",
-            "",
-            bcx.build_config
-                .requested_target
-                .as_ref()
-                .map(|s| s.as_ref()),
-        );
+    if let Kind::Target(target) = unit.kind {
+        opt(cmd, "--target", "", Some(target.as_ref()));
     }
 
     opt(cmd, "-C", "ar=", bcx.ar(unit.kind).map(|s| s.as_ref()));
@@ -934,7 +1040,7 @@ fn build_deps_args<'a, 'cfg>(
 
     // Be sure that the host path is also listed. This'll ensure that proc macro
     // dependencies are correctly found (for reexported macros).
-    if let Kind::Target = unit.kind {
+    if let Kind::Target(_) = unit.kind {
         cmd.arg("-L").arg(&{
             let mut deps = OsString::from("dependency=");
             deps.push(cx.files().host_deps());
@@ -953,7 +1059,7 @@ fn build_deps_args<'a, 'cfg>(
     {
         if let Some(u) = dep_targets
             .iter()
-            .find(|u| !u.mode.is_doc() && u.target.is_lib())
+            .find(|u| !u.mode.is_doc() && u.target.is_lib() && links_lib(bcx, unit, u))
         {
             bcx.config.shell().warn(format!(
                 "The package `{}` \
@@ -973,9 +1079,10 @@ fn build_deps_args<'a, 'cfg>(
         if dep.mode.is_run_custom_build() {
             cmd.env("OUT_DIR", &cx.files().build_script_out_dir(&dep));
         }
-        if dep.target.linkable() && !dep.mode.is_doc() {
+        if dep.target.linkable() && !dep.mode.is_doc() && links_lib(bcx, unit, &dep) {
             link_to(cmd, cx, unit, &dep, &mut unstable_opts)?;
         }
+        add_artifact_env(cmd, cx, unit, &dep)?;
     }
 
     // This will only be set if we're already usign a feature
@@ -994,18 +1101,28 @@ fn build_deps_args<'a, 'cfg>(
         need_unstable_opts: &mut bool,
     ) -> CargoResult<()> {
         let bcx = cx.bcx;
+        // Crates which only need the metadata of `dep` are compiled against
+        // it, so that they don't have to wait for `dep` to be fully built.
+        let flavor = if cx.only_requires_rmeta(current, dep) {
+            FileFlavor::Rmeta
+        } else {
+            FileFlavor::Linkable
+        };
         for output in cx.outputs(dep)?.iter() {
-            if output.flavor != FileFlavor::Linkable {
+            if output.flavor != flavor {
                 continue;
             }
             let mut v = OsString::new();
             let name = bcx.extern_crate_name(current, dep)?;
-            v.push(name);
-            v.push("=");
-            v.push(cx.files().out_dir(dep));
-            v.push(&path::MAIN_SEPARATOR.to_string());
-            v.push(&output.path.file_name().unwrap());
-
+            let mut opts = Vec::new();
+            // The standard library built from source must not be injected
+            // into the prelude, as that's up to `#![no_std]` and friends.
+            if dep.is_std && !current.is_std {
+                opts.push("noprelude");
+            }
+            // Private dependencies are checked by the
+            // `exported_private_dependencies` lint to not show up in the
+            // public API of the crate.
             if current
                 .pkg
                 .manifest()
@@ -1014,18 +1131,87 @@ fn build_deps_args<'a, 'cfg>(
                 .is_ok()
                 && !bcx.is_public_dependency(current, dep)
             {
-                cmd.arg("--extern-private");
+                opts.push("priv");
+            }
+            if !opts.is_empty() {
+                v.push(opts.join(","));
+                v.push(":");
                 *need_unstable_opts = true;
-            } else {
-                cmd.arg("--extern");
             }
+            v.push(name);
+            v.push("=");
+            v.push(cx.files().out_dir(dep));
+            v.push(&path::MAIN_SEPARATOR.to_string());
+            v.push(&output.path.file_name().unwrap());
 
-            cmd.arg(&v);
+            cmd.arg("--extern").arg(&v);
         }
         Ok(())
     }
 }
 
+/// Returns `true` if the library of `dep` is linked into `unit`, which is
+/// not the case when `unit` only asks for `dep` as an artifact dependency.
+fn links_lib(bcx: &BuildContext<'_, '_>, unit: &Unit<'_>, dep: &Unit<'_>) -> bool {
+    dep.pkg == unit.pkg
+        || (dep.is_std && !unit.is_std)
+        || active_deps(bcx, unit, dep.pkg.package_id())
+            .iter()
+            .any(|d| d.links_lib())
+}
+
+/// Sets the `CARGO_<KIND>_FILE_<DEP>` environment variables pointing `cmd`,
+/// which builds `unit` or runs its build script, at the files that `dep`
+/// produces, if `dep` is an artifact dependency of `unit`.
+fn add_artifact_env<'a>(
+    cmd: &mut ProcessBuilder,
+    cx: &Context<'a, '_>,
+    unit: &Unit<'a>,
+    dep: &Unit<'a>,
+) -> CargoResult<()> {
+    let bcx = cx.bcx;
+    if dep.pkg == unit.pkg || dep.mode != CompileMode::Build {
+        return Ok(());
+    }
+    for d in active_deps(bcx, unit, dep.pkg.package_id()) {
+        let artifact = match d.artifact() {
+            Some(artifact) => artifact,
+            None => continue,
+        };
+        let kind = artifact.target().map(Kind::Target).unwrap_or(unit.kind);
+        if kind != dep.kind || !artifact.kind().matches(dep.target) {
+            continue;
+        }
+        let name = envify(&d.name_in_toml());
+        let var = match artifact.kind() {
+            ArtifactKind::Bin => format!("CARGO_BIN_FILE_{}_{}", name, dep.target.name()),
+            ArtifactKind::Cdylib => format!("CARGO_CDYLIB_FILE_{}", name),
+            ArtifactKind::Staticlib => format!("CARGO_STATICLIB_FILE_{}", name),
+        };
+        let file_types = bcx.info(dep.kind).file_types(
+            artifact.kind().as_str(),
+            FileFlavor::Normal,
+            dep.target.kind(),
+            bcx.triple(dep.kind),
+        )?;
+        let file_type = match file_types.as_ref().and_then(|types| types.first()) {
+            Some(file_type) => file_type,
+            None => failure::bail!(
+                "cannot produce a `{}` artifact of `{}` for `{}`",
+                artifact.kind().as_str(),
+                dep.pkg,
+                bcx.triple(dep.kind)
+            ),
+        };
+        let path = cx
+            .files()
+            .out_dir(dep)
+            .join(file_type.filename(&cx.files().file_stem(dep)));
+        cmd.env(&var, path);
+    }
+    Ok(())
+}
+
 fn envify(s: &str) -> String {
     s.chars()
         .flat_map(|c| c.to_uppercase())
@@ -1040,8 +1226,8 @@ impl Kind {
         // that needs to be on the host we lift ourselves up to `Host`.
         match self {
             Kind::Host => Kind::Host,
-            Kind::Target if target.for_host() => Kind::Host,
-            Kind::Target => Kind::Target,
+            Kind::Target(_) if target.for_host() => Kind::Host,
+            kind @ Kind::Target(_) => kind,
         }
     }
 }
@@ -1057,21 +1243,167 @@ fn assert_is_empty(line: &str) -> CargoResult<()> {
     }
 }
 
-fn json_stderr(line: &str, package_id: PackageId, target: &Target) -> CargoResult<()> {
+/// Describes how the stderr of a rustc invocation is handled.
+struct OutputOptions {
+    /// The format of the messages Cargo itself emits.
+    format: MessageFormat,
+    /// Whether rustc reports its artifacts, so that dependents can start as
+    /// soon as the metadata has been written.
+    look_for_metadata_directive: bool,
+    /// Whether rendered diagnostics may keep their colors.
+    color: bool,
+    /// Where the messages are cached with `-Z cache-messages`, along with the
+    /// file, which is only created once there's something to write to it.
+    cache_cell: Option<(PathBuf, LazyCell<File>)>,
+}
+
+impl OutputOptions {
+    fn new<'a>(cx: &Context<'a, '_>, unit: &Unit<'a>) -> OutputOptions {
+        let cache_cell = if cx.bcx.config.cli_unstable().cache_messages {
+            Some((cx.files().message_cache_path(unit), LazyCell::new()))
+        } else {
+            None
+        };
+        OutputOptions {
+            format: cx.bcx.build_config.message_format,
+            look_for_metadata_directive: cx.rmeta_required(unit),
+            color: cx.bcx.config.shell().supports_color(),
+            cache_cell,
+        }
+    }
+}
+
+/// Handles a line of stderr from rustc, which is in JSON if the user asked
+/// for JSON messages, if the unit is pipelined, or if its messages are
+/// cached.
+fn on_stderr_line(
+    state: &JobState<'_>,
+    line: &str,
+    package_id: PackageId,
+    target: &Target,
+    options: &mut OutputOptions,
+) -> CargoResult<()> {
+    if let Some((path, cell)) = &mut options.cache_cell {
+        let file = cell.try_borrow_mut_with(|| File::create(path))?;
+        debug_assert!(!line.contains('\n'));
+        file.write_all(line.as_bytes())?;
+        file.write_all(b"\n")?;
+    }
+
     // Stderr from rustc/rustdoc can have a mix of JSON and non-JSON output.
-    if line.starts_with('{') {
-        // Handle JSON lines.
-        let compiler_message = serde_json::from_str(line)
-            .map_err(|_| internal(&format!("compiler produced invalid json: `{}`", line)))?;
-
-        machine_message::emit(&machine_message::FromCompiler {
-            package_id,
-            target,
-            message: compiler_message,
-        });
-    } else {
+    if !line.starts_with('{') {
         // Forward non-JSON to stderr.
-        writeln!(io::stderr(), "{}", line)?;
+        state.stderr(line.to_string());
+        return Ok(());
     }
+
+    let mut compiler_message: Box<RawValue> = serde_json::from_str(line)
+        .map_err(|_| internal(&format!("compiler produced invalid json: `{}`", line)))?;
+
+    // Pipelined units are told to report their artifacts, so that
+    // dependents can start as soon as the metadata has been written.
+    if options.look_for_metadata_directive {
+        #[derive(Deserialize)]
+        struct ArtifactNotification {
+            artifact: String,
+        }
+        if let Ok(notification) =
+            serde_json::from_str::<ArtifactNotification>(compiler_message.get())
+        {
+            if notification.artifact.ends_with(".rmeta") {
+                debug!("metadata of {} is ready", package_id);
+                state.rmeta_produced();
+            }
+            return Ok(());
+        }
+    }
+
+    // If the user didn't ask for JSON, then the unit is only in JSON mode
+    // because it's pipelined or cached, so print diagnostics as rustc would
+    // have.
+    if options.format != MessageFormat::Json {
+        #[derive(Deserialize)]
+        struct CompilerMessage {
+            rendered: String,
+        }
+        if let Ok(mut message) = serde_json::from_str::<CompilerMessage>(compiler_message.get()) {
+            // Each message is printed on its own line already.
+            if message.rendered.ends_with('\n') {
+                message.rendered.pop();
+            }
+            if !options.color {
+                message.rendered = strip_ansi_escapes(&message.rendered);
+            }
+            state.stderr(message.rendered);
+        }
+        return Ok(());
+    }
+
+    // Colors are only requested from rustc for the messages to be cached, and
+    // have never been part of the JSON messages.
+    if compiler_message.get().contains("\\u001b") {
+        let mut message: serde_json::Value = serde_json::from_str(compiler_message.get())?;
+        if let Some(rendered) = message.get_mut("rendered") {
+            if let Some(s) = rendered.as_str() {
+                *rendered = serde_json::Value::String(strip_ansi_escapes(s));
+            }
+        }
+        compiler_message = serde_json::value::to_raw_value(&message)?;
+    }
+
+    machine_message::emit(&machine_message::FromCompiler {
+        package_id,
+        target,
+        message: compiler_message,
+    });
     Ok(())
 }
+
+/// Removes the ANSI escape sequences which rustc uses to color its
+/// diagnostics.
+fn strip_ansi_escapes(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            ret.push(c);
+            continue;
+        }
+        // A control sequence is `ESC [`, followed by parameters, and ends
+        // with a letter or one of a few symbols.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// Creates the work which replays the messages cached by the last successful
+/// compilation of a fresh unit.
+fn replay_output_cache<'a>(cx: &Context<'a, '_>, unit: &Unit<'a>) -> Work {
+    let package_id = unit.pkg.package_id();
+    let target = unit.target.clone();
+    let path = cx.files().message_cache_path(unit);
+    let mut options = OutputOptions {
+        // Whether the metadata is ready doesn't matter anymore.
+        look_for_metadata_directive: false,
+        cache_cell: None,
+        ..OutputOptions::new(cx, unit)
+    };
+    Work::new(move |state| {
+        if !path.exists() {
+            // No messages, or the unit was last built without
+            // `-Z cache-messages`.
+            return Ok(());
+        }
+        let contents = paths::read(&path)?;
+        for line in contents.lines() {
+            on_stderr_line(state, line, package_id, &target, &mut options)?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,119 @@
+//! Code for building the standard library.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::core::profiles::Profiles;
+use crate::core::resolver::Method;
+use crate::core::{Dependency, Features, PackageIdSpec, PackageSet, Resolve, SourceId};
+use crate::core::{VirtualManifest, Workspace, WorkspaceConfig, WorkspaceRootConfig};
+use crate::ops;
+use crate::util::errors::CargoResult;
+
+/// Parses the value of the `-Z build-std` flag into the list of crates to
+/// build, adding the crates which the requested ones can't work without.
+pub fn parse_unstable_flag(value: Option<&str>) -> Vec<String> {
+    let value = value.unwrap_or("std");
+    let mut crates: HashSet<&str> = value.split(',').collect();
+    if crates.contains("std") {
+        crates.insert("core");
+        crates.insert("alloc");
+        crates.insert("panic_unwind");
+        crates.insert("compiler_builtins");
+    } else if crates.contains("core") {
+        crates.insert("compiler_builtins");
+    }
+    let mut crates: Vec<String> = crates.into_iter().map(|s| s.to_string()).collect();
+    crates.sort();
+    crates
+}
+
+/// Resolves the standard library's workspace, as found in the `rust-src`
+/// component of the compiler's sysroot.
+///
+/// The result is kept separate from the user's own `Resolve`, so that the
+/// standard library's dependencies never unify with the user's.
+pub fn resolve_std<'cfg>(
+    ws: &Workspace<'cfg>,
+    crates: &[String],
+) -> CargoResult<(PackageSet<'cfg>, Resolve)> {
+    let src_path = detect_sysroot_src_path(ws)?;
+
+    // The standard library depends on crates.io packages which in turn depend
+    // back on `core`, `alloc` and `std` through these shim packages.
+    let mut patches = Vec::new();
+    for name in &[
+        "rustc-std-workspace-core",
+        "rustc-std-workspace-alloc",
+        "rustc-std-workspace-std",
+    ] {
+        let path = src_path.join("src").join("tools").join(name);
+        let source_id = SourceId::for_path(&path)?;
+        patches.push(Dependency::parse_no_deprecated(name, None, source_id)?);
+    }
+    let mut patch = HashMap::new();
+    patch.insert(SourceId::crates_io(ws.config())?.url().clone(), patches);
+
+    let members = vec![
+        String::from("src/libstd"),
+        String::from("src/libcore"),
+        String::from("src/liballoc"),
+        String::from("src/libtest"),
+    ];
+    let ws_config = WorkspaceConfig::Root(WorkspaceRootConfig::new(
+        &src_path,
+        &Some(members),
+        /*default_members*/ &None,
+        /*exclude*/ &None,
+    ));
+    let features = Features::default();
+    let profiles = Profiles::new(None, ws.config(), &features, &mut Vec::new())?;
+    let virtual_manifest = VirtualManifest::new(Vec::new(), patch, ws_config, profiles, features);
+
+    // Features given on the command line only apply to the current member of
+    // a workspace, so `libtest`, which depends on every other crate and
+    // forwards these features to `std`, is made the current one.
+    let current_manifest = src_path.join("src/libtest/Cargo.toml");
+    let std_ws = Workspace::new_virtual(src_path, current_manifest, virtual_manifest, ws.config())?;
+
+    // `test` is always resolved so it can be built for harnessed tests, even
+    // if it wasn't asked for.
+    let mut spec_names: Vec<&str> = crates.iter().map(|s| s.as_str()).collect();
+    if !spec_names.contains(&"test") {
+        spec_names.push("test");
+    }
+    let specs = spec_names
+        .iter()
+        .map(|name| PackageIdSpec::parse(name))
+        .collect::<CargoResult<Vec<_>>>()?;
+    let features = vec!["panic-unwind".to_string(), "backtrace".to_string()];
+    let method = Method::Required {
+        dev_deps: false,
+        features: Rc::new(Method::split_features(&features)),
+        all_features: false,
+        uses_default_features: true,
+    };
+    ops::resolve_ws_with_method(&std_ws, method, &specs)
+}
+
+/// Finds the standard library's source code in the `rust-src` component.
+fn detect_sysroot_src_path(ws: &Workspace<'_>) -> CargoResult<PathBuf> {
+    let src_path = match env::var_os("__CARGO_TESTS_ONLY_SRC_ROOT") {
+        Some(s) => PathBuf::from(s),
+        None => {
+            let rustc = ws.config().load_global_rustc(Some(ws))?;
+            rustc.sysroot()?.join("lib/rustlib/src/rust")
+        }
+    };
+    let lock = src_path.join("src/libstd/Cargo.toml");
+    if !lock.exists() {
+        failure::bail!(
+            "{:?} does not exist, unable to build with the standard \
+             library, try:\n        rustup component add rust-src",
+            lock
+        );
+    }
+    Ok(src_path)
+}
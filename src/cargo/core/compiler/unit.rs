@@ -48,12 +48,21 @@ pub struct UnitInner<'a> {
     pub kind: Kind,
     /// The "mode" this unit is being compiled for. See [`CompileMode`] for more details.
     pub mode: CompileMode,
+    /// Whether this unit is part of the standard library built with
+    /// `-Z build-std`, and so belongs to the standard library's `Resolve`.
+    pub is_std: bool,
 }
 
 impl<'a> Unit<'a> {
     pub fn buildkey(&self) -> String {
         format!("{}-{}", self.pkg.name(), short_hash(self))
     }
+
+    /// Returns whether compiling this unit needs the object code of its
+    /// dependencies, or whether their metadata is enough.
+    pub fn requires_upstream_objects(&self) -> bool {
+        self.mode.is_any_test() || self.target.kind().requires_upstream_objects()
+    }
 }
 
 // Just hash the pointer for fast hashing
@@ -88,6 +97,7 @@ impl<'a> fmt::Debug for Unit<'a> {
             .field("profile", &self.profile)
             .field("kind", &self.kind)
             .field("mode", &self.mode)
+            .field("is_std", &self.is_std)
             .finish()
     }
 }
@@ -127,6 +137,7 @@ impl<'a> UnitInterner<'a> {
         profile: Profile,
         kind: Kind,
         mode: CompileMode,
+        is_std: bool,
     ) -> Unit<'a> {
         let inner = self.intern_inner(&UnitInner {
             pkg,
@@ -134,6 +145,7 @@ impl<'a> UnitInterner<'a> {
             profile,
             kind,
             mode,
+            is_std,
         });
         Unit { inner }
     }
@@ -10,7 +10,7 @@ use serde::Serialize;
 use url::Url;
 
 use crate::core::interning::InternedString;
-use crate::core::{PackageId, SourceId, Summary};
+use crate::core::{PackageId, SourceId, Summary, Target};
 use crate::util::errors::{CargoResult, CargoResultExt};
 use crate::util::{Cfg, CfgExpr, Config};
 
@@ -47,6 +47,10 @@ struct Inner {
     // This dependency should be used only for this platform.
     // `None` means *all platforms*.
     platform: Option<Platform>,
+
+    // The compiled artifact this dependency asks for, if any. `None` means
+    // the dependency's library is linked like usual.
+    artifact: Option<Artifact>,
 }
 
 #[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Clone, Debug)]
@@ -55,6 +59,27 @@ pub enum Platform {
     Cfg(CfgExpr),
 }
 
+/// A compiled artifact of a dependency, requested with the `artifact` key.
+#[derive(PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Debug, Serialize)]
+pub struct Artifact {
+    kind: ArtifactKind,
+    /// The target triple to build the artifact for. `None` means the artifact
+    /// is built for the same target as the crate depending on it.
+    target: Option<InternedString>,
+    /// Whether the dependency's library is also linked.
+    lib: bool,
+}
+
+#[derive(PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy, Debug)]
+pub enum ArtifactKind {
+    /// All of the dependency's binaries.
+    Bin,
+    /// The dependency's library, built as a `cdylib`.
+    Cdylib,
+    /// The dependency's library, built as a `staticlib`.
+    Staticlib,
+}
+
 #[derive(Serialize)]
 struct SerializedDependency<'a> {
     name: &'a str,
@@ -71,6 +96,8 @@ struct SerializedDependency<'a> {
     /// If None, then it comes from the default registry (crates.io).
     #[serde(with = "url_serde")]
     registry: Option<Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    artifact: Option<&'a Artifact>,
 }
 
 impl ser::Serialize for Dependency {
@@ -89,6 +116,7 @@ impl ser::Serialize for Dependency {
             target: self.platform(),
             rename: self.explicit_name_in_toml().map(|s| s.as_str()),
             registry: self.registry_id().map(|sid| sid.url().clone()),
+            artifact: self.artifact(),
         }
         .serialize(s)
     }
@@ -224,6 +252,7 @@ impl Dependency {
                 specified_req: false,
                 platform: None,
                 explicit_name_in_toml: None,
+                artifact: None,
             }),
         }
     }
@@ -373,6 +402,22 @@ impl Dependency {
         self
     }
 
+    /// The compiled artifact requested by this dependency, if any.
+    pub fn artifact(&self) -> Option<&Artifact> {
+        self.inner.artifact.as_ref()
+    }
+
+    pub fn set_artifact(&mut self, artifact: Option<Artifact>) -> &mut Dependency {
+        Rc::make_mut(&mut self.inner).artifact = artifact;
+        self
+    }
+
+    /// Returns `true` if the dependency's library should be linked into the
+    /// crates depending on it.
+    pub fn links_lib(&self) -> bool {
+        self.inner.artifact.as_ref().map_or(true, |a| a.lib)
+    }
+
     pub fn set_explicit_name_in_toml(&mut self, name: &str) -> &mut Dependency {
         Rc::make_mut(&mut self.inner).explicit_name_in_toml = Some(InternedString::new(name));
         self
@@ -455,6 +500,77 @@ impl Dependency {
     }
 }
 
+impl Artifact {
+    pub fn new(kind: ArtifactKind, target: Option<&str>, lib: bool) -> Artifact {
+        Artifact {
+            kind,
+            target: target.map(InternedString::new),
+            lib,
+        }
+    }
+
+    pub fn kind(&self) -> ArtifactKind {
+        self.kind
+    }
+
+    pub fn target(&self) -> Option<InternedString> {
+        self.target
+    }
+
+    pub fn lib(&self) -> bool {
+        self.lib
+    }
+}
+
+impl ArtifactKind {
+    /// Returns `true` if `target` of the dependency produces this kind of
+    /// artifact.
+    pub fn matches(self, target: &Target) -> bool {
+        match self {
+            ArtifactKind::Bin => target.is_bin(),
+            ArtifactKind::Cdylib => {
+                target.is_lib() && target.rustc_crate_types().contains(&"cdylib")
+            }
+            ArtifactKind::Staticlib => {
+                target.is_lib() && target.rustc_crate_types().contains(&"staticlib")
+            }
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArtifactKind::Bin => "bin",
+            ArtifactKind::Cdylib => "cdylib",
+            ArtifactKind::Staticlib => "staticlib",
+        }
+    }
+}
+
+impl ser::Serialize for ArtifactKind {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        self.as_str().serialize(s)
+    }
+}
+
+impl FromStr for ArtifactKind {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> CargoResult<ArtifactKind> {
+        match s {
+            "bin" => Ok(ArtifactKind::Bin),
+            "cdylib" => Ok(ArtifactKind::Cdylib),
+            "staticlib" => Ok(ArtifactKind::Staticlib),
+            _ => failure::bail!(
+                "unknown artifact kind `{}`, expected one of `bin`, `cdylib` or `staticlib`",
+                s
+            ),
+        }
+    }
+}
+
 impl Platform {
     pub fn matches(&self, name: &str, cfg: Option<&[Cfg]>) -> bool {
         match *self {
@@ -202,6 +202,18 @@ features! {
 
         // Specifying the 'public' attribute on dependencies
         [unstable] public_dependency: bool,
+
+        // "forced-target" and "default-target" manifest options
+        [unstable] per_package_target: bool,
+
+        // The "artifact" key on dependencies
+        [unstable] artifact_dependencies: bool,
+
+        // The "timeout" key on targets
+        [unstable] test_timeout: bool,
+
+        // The "submodules" key on git dependencies
+        [unstable] git_submodules: bool,
     }
 }
 
@@ -326,12 +338,16 @@ pub struct CliUnstable {
     pub no_index_update: bool,
     pub avoid_dev_deps: bool,
     pub minimal_versions: bool,
+    pub direct_minimal_versions: bool,
     pub package_features: bool,
     pub advanced_env: bool,
     pub config_profile: bool,
     pub dual_proc_macros: bool,
     pub mtime_on_use: bool,
     pub install_upgrade: bool,
+    pub multitarget: bool,
+    pub build_std: Option<Vec<String>>,
+    pub cache_messages: bool,
 }
 
 impl CliUnstable {
@@ -348,6 +364,12 @@ impl CliUnstable {
         for flag in flags {
             self.add(flag)?;
         }
+        if self.minimal_versions && self.direct_minimal_versions {
+            failure::bail!(
+                "`-Z minimal-versions` and `-Z direct-minimal-versions` \
+                 can't be used together"
+            );
+        }
         Ok(())
     }
 
@@ -371,12 +393,18 @@ impl CliUnstable {
             "no-index-update" => self.no_index_update = true,
             "avoid-dev-deps" => self.avoid_dev_deps = true,
             "minimal-versions" => self.minimal_versions = true,
+            "direct-minimal-versions" => self.direct_minimal_versions = true,
             "package-features" => self.package_features = true,
             "advanced-env" => self.advanced_env = true,
             "config-profile" => self.config_profile = true,
             "dual-proc-macros" => self.dual_proc_macros = true,
             "mtime-on-use" => self.mtime_on_use = true,
             "install-upgrade" => self.install_upgrade = true,
+            "multitarget" => self.multitarget = true,
+            "build-std" => {
+                self.build_std = Some(crate::core::compiler::standard_lib::parse_unstable_flag(v))
+            }
+            "cache-messages" => self.cache_messages = true,
             _ => failure::bail!("unknown `-Z` flag specified: {}", k),
         }
 
@@ -44,6 +44,8 @@ pub struct Manifest {
     edition: Edition,
     im_a_teapot: Option<bool>,
     default_run: Option<String>,
+    forced_target: Option<String>,
+    default_target: Option<String>,
     metabuild: Option<Vec<String>>,
 }
 
@@ -120,6 +122,15 @@ impl LibKind {
             LibKind::Other(..) => false,
         }
     }
+
+    /// Returns whether producing this crate type needs the object code of
+    /// upstream crates, rather than just their metadata.
+    pub fn requires_upstream_objects(&self) -> bool {
+        match *self {
+            LibKind::Lib | LibKind::Rlib => false,
+            LibKind::Dylib | LibKind::ProcMacro | LibKind::Other(..) => true,
+        }
+    }
 }
 
 impl fmt::Debug for LibKind {
@@ -193,6 +204,17 @@ impl TargetKind {
             TargetKind::CustomBuild => "build-script",
         }
     }
+
+    /// Returns whether producing this target needs the object code of
+    /// upstream crates, rather than just their metadata.
+    pub fn requires_upstream_objects(&self) -> bool {
+        match self {
+            TargetKind::Lib(kinds) | TargetKind::ExampleLib(kinds) => {
+                kinds.iter().any(|k| k.requires_upstream_objects())
+            }
+            _ => true,
+        }
+    }
 }
 
 /// Information about a binary, a library, an example, etc. that is part of the
@@ -215,6 +237,8 @@ pub struct Target {
     for_host: bool,
     proc_macro: bool,
     edition: Edition,
+    /// How long the tests of the target may run for, in seconds.
+    test_timeout: Option<u64>,
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -356,6 +380,7 @@ compact_debug! {
                 for_host
                 proc_macro
                 edition
+                test_timeout
             )]
         }
     }
@@ -380,6 +405,8 @@ impl Manifest {
         edition: Edition,
         im_a_teapot: Option<bool>,
         default_run: Option<String>,
+        forced_target: Option<String>,
+        default_target: Option<String>,
         original: Rc<TomlManifest>,
         metabuild: Option<Vec<String>>,
     ) -> Manifest {
@@ -402,6 +429,8 @@ impl Manifest {
             original,
             im_a_teapot,
             default_run,
+            forced_target,
+            default_target,
             publish_lockfile,
             metabuild,
         }
@@ -498,6 +527,16 @@ impl Manifest {
                 .chain_err(|| failure::format_err!("the `default-run` manifest key is unstable"))?;
         }
 
+        if self.forced_target.is_some() || self.default_target.is_some() {
+            self.features
+                .require(Feature::per_package_target())
+                .chain_err(|| {
+                    failure::format_err!(
+                        "the `forced-target` and `default-target` manifest keys are unstable"
+                    )
+                })?;
+        }
+
         Ok(())
     }
 
@@ -522,6 +561,17 @@ impl Manifest {
         self.default_run.as_ref().map(|s| &s[..])
     }
 
+    /// The target this package must always be built for, if any, except for
+    /// its build script and procedural macros which are built for the host.
+    pub fn forced_target(&self) -> Option<&str> {
+        self.forced_target.as_ref().map(|s| &s[..])
+    }
+
+    /// The target this package is built for when no `--target` is given.
+    pub fn default_target(&self) -> Option<&str> {
+        self.default_target.as_ref().map(|s| &s[..])
+    }
+
     pub fn metabuild(&self) -> Option<&Vec<String>> {
         self.metabuild.as_ref()
     }
@@ -597,6 +647,7 @@ impl Target {
             edition,
             tested: true,
             benched: true,
+            test_timeout: None,
         }
     }
 
@@ -750,6 +801,9 @@ impl Target {
     pub fn edition(&self) -> Edition {
         self.edition
     }
+    pub fn test_timeout(&self) -> Option<u64> {
+        self.test_timeout
+    }
     pub fn benched(&self) -> bool {
         self.benched
     }
@@ -891,6 +945,10 @@ impl Target {
         self.harness = harness;
         self
     }
+    pub fn set_test_timeout(&mut self, test_timeout: Option<u64>) -> &mut Target {
+        self.test_timeout = test_timeout;
+        self
+    }
     pub fn set_doc(&mut self, doc: bool) -> &mut Target {
         self.doc = doc;
         self
@@ -457,6 +457,20 @@ impl<'cfg> PackageSet<'cfg> {
         Ok(pkgs)
     }
 
+    /// Merges the packages and sources of another set into this one.
+    pub fn add_set(&mut self, set: PackageSet<'cfg>) {
+        assert!(
+            !self.downloading.get(),
+            "cannot add to a package set which is downloading"
+        );
+        for (id, pkg) in set.packages {
+            self.packages.entry(id).or_insert(pkg);
+        }
+        self.sources
+            .borrow_mut()
+            .add_source_map(set.sources.into_inner());
+    }
+
     pub fn sources(&self) -> Ref<'_, SourceMap<'cfg>> {
         self.sources.borrow()
     }
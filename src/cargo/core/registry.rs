@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use log::{debug, trace};
 use semver::VersionReq;
@@ -7,8 +8,9 @@ use url::Url;
 use crate::core::PackageSet;
 use crate::core::{Dependency, PackageId, Source, SourceId, SourceMap, Summary};
 use crate::sources::config::SourceConfigMap;
+use crate::sources::{OverlaySource, RegistrySource};
 use crate::util::errors::{CargoResult, CargoResultExt};
-use crate::util::{profile, Config};
+use crate::util::{profile, Config, Filesystem};
 
 /// Source of information about a group of packages.
 ///
@@ -77,6 +79,10 @@ pub struct PackageRegistry<'cfg> {
     patches: HashMap<Url, Vec<Summary>>,
     patches_locked: bool,
     patches_available: HashMap<Url, Vec<PackageId>>,
+
+    // Local registries, keyed by the source they are layered on top of, see
+    // `add_local_overlay`.
+    local_overlays: HashMap<SourceId, PathBuf>,
 }
 
 type LockedMap = HashMap<SourceId, HashMap<String, Vec<(PackageId, Vec<PackageId>)>>>;
@@ -102,6 +108,7 @@ impl<'cfg> PackageRegistry<'cfg> {
             patches: HashMap::new(),
             patches_locked: false,
             patches_available: HashMap::new(),
+            local_overlays: HashMap::new(),
         })
     }
 
@@ -168,6 +175,17 @@ impl<'cfg> PackageRegistry<'cfg> {
         self.add_source(source, Kind::Override);
     }
 
+    /// Layers the local registry at `path` on top of the source `source_id`.
+    ///
+    /// Whenever `source_id` is loaded it is wrapped in an `OverlaySource`, so
+    /// packages from the local registry are available as if they were part of
+    /// `source_id`. Those packages are unpacked into the `src` directory of the
+    /// local registry rather than into the shared source cache. This must be
+    /// called before `source_id` is first loaded.
+    pub fn add_local_overlay(&mut self, source_id: SourceId, path: &Path) {
+        self.local_overlays.insert(source_id, path.to_path_buf());
+    }
+
     pub fn add_to_yanked_whitelist(&mut self, iter: impl Iterator<Item = PackageId>) {
         let pkgs = iter.collect::<Vec<_>>();
         for (_, source) in self.sources.sources_mut() {
@@ -311,9 +329,16 @@ impl<'cfg> PackageRegistry<'cfg> {
     fn load(&mut self, source_id: SourceId, kind: Kind) -> CargoResult<()> {
         (|| {
             debug!("loading source {}", source_id);
-            let source = self.source_config.load(source_id, &self.yanked_whitelist)?;
+            let mut source = self.source_config.load(source_id, &self.yanked_whitelist)?;
             assert_eq!(source.source_id(), source_id);
 
+            if let Some(path) = self.local_overlays.get(&source_id) {
+                let local_id = SourceId::for_local_registry(path)?;
+                let mut local = RegistrySource::local(local_id, path, &HashSet::new(), self.config);
+                local.set_src_path(Filesystem::new(path.join("src")));
+                source = Box::new(OverlaySource::new(Box::new(local), source));
+            }
+
             if kind == Kind::Override {
                 self.overrides.push(source_id);
             }
@@ -611,8 +636,14 @@ fn lock(locked: &LockedMap, patches: &HashMap<Url, Vec<PackageId>>, summary: Sum
         //
         // Cases 1/2 are handled by `matches_id` and case 3 is handled by
         // falling through to the logic below.
+        //
+        // A lock file with merge conflicts may have a dependency locked to a
+        // different version on each side, in which case the newest is used.
         if let Some(&(_, ref locked_deps)) = pair {
-            let locked = locked_deps.iter().find(|&&id| dep.matches_id(id));
+            let locked = locked_deps
+                .iter()
+                .filter(|&&id| dep.matches_id(id))
+                .max_by_key(|id| id.version());
             if let Some(&locked) = locked {
                 trace!("\tfirst hit on {}", locked);
                 let mut dep = dep;
@@ -30,8 +30,15 @@ pub struct RegistryQueryer<'a> {
     /// versions first. That allows `cargo update -Z minimal-versions` which will
     /// specify minimum dependency versions to be used.
     minimal_versions: bool,
-    /// a cache of `Candidate`s that fulfil a `Dependency`
-    registry_cache: HashMap<Dependency, Rc<Vec<Candidate>>>,
+    /// The packages whose dependencies have their candidates sorted by
+    /// minimal versions first even if `minimal_versions` isn't set. With
+    /// `-Z direct-minimal-versions` these are the workspace members, so that
+    /// their lower bounds can be checked without relying on those of
+    /// dependencies further down the graph.
+    direct_minimal_versions: HashSet<PackageId>,
+    /// a cache of `Candidate`s that fulfil a `Dependency`, sorted by minimal
+    /// versions first or not
+    registry_cache: HashMap<(Dependency, bool), Rc<Vec<Candidate>>>,
     /// a cache of `Dependency`s that are required for a `Summary`
     summary_cache: HashMap<
         (Option<PackageId>, Summary, Method),
@@ -47,12 +54,14 @@ impl<'a> RegistryQueryer<'a> {
         replacements: &'a [(PackageIdSpec, Dependency)],
         try_to_use: &'a HashSet<PackageId>,
         minimal_versions: bool,
+        direct_minimal_versions: HashSet<PackageId>,
     ) -> Self {
         RegistryQueryer {
             registry,
             replacements,
             try_to_use,
             minimal_versions,
+            direct_minimal_versions,
             registry_cache: HashMap::new(),
             summary_cache: HashMap::new(),
             used_replacements: HashMap::new(),
@@ -63,14 +72,25 @@ impl<'a> RegistryQueryer<'a> {
         self.used_replacements.get(&p).map(|&r| (p, r))
     }
 
+    /// Whether the candidates for the dependencies of `parent` are tried from
+    /// the minimal version up.
+    pub fn first_minimal_version(&self, parent: PackageId) -> bool {
+        self.minimal_versions || self.direct_minimal_versions.contains(&parent)
+    }
+
     /// Queries the `registry` to return a list of candidates for `dep`.
     ///
     /// This method is the location where overrides are taken into account. If
     /// any candidates are returned which match an override then the override is
     /// applied by performing a second query for what the override should
     /// return.
-    pub fn query(&mut self, dep: &Dependency) -> CargoResult<Rc<Vec<Candidate>>> {
-        if let Some(out) = self.registry_cache.get(dep).cloned() {
+    pub fn query(
+        &mut self,
+        dep: &Dependency,
+        first_minimal_version: bool,
+    ) -> CargoResult<Rc<Vec<Candidate>>> {
+        let key = (dep.clone(), first_minimal_version);
+        if let Some(out) = self.registry_cache.get(&key).cloned() {
             return Ok(out);
         }
 
@@ -176,7 +196,7 @@ impl<'a> RegistryQueryer<'a> {
             match previous_cmp {
                 Ordering::Equal => {
                     let cmp = a.summary.version().cmp(b.summary.version());
-                    if self.minimal_versions {
+                    if first_minimal_version {
                         // Lower version ordered first.
                         cmp
                     } else {
@@ -190,7 +210,7 @@ impl<'a> RegistryQueryer<'a> {
 
         let out = Rc::new(ret);
 
-        self.registry_cache.insert(dep.clone(), out.clone());
+        self.registry_cache.insert(key, out.clone());
 
         Ok(out)
     }
@@ -221,10 +241,11 @@ impl<'a> RegistryQueryer<'a> {
 
         // Next, transform all dependencies into a list of possible candidates
         // which can satisfy that dependency.
+        let first_minimal_version = self.first_minimal_version(candidate.package_id());
         let mut deps = deps
             .into_iter()
             .map(|(dep, features)| {
-                let candidates = self.query(&dep)?;
+                let candidates = self.query(&dep, first_minimal_version)?;
                 Ok((dep, candidates, features))
             })
             .collect::<CargoResult<Vec<DepInfo>>>()?;
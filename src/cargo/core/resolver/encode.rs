@@ -11,10 +11,12 @@ use crate::core::{Dependency, Package, PackageId, SourceId, Workspace};
 use crate::util::errors::{CargoResult, CargoResultExt};
 use crate::util::{internal, Graph};
 
-use super::Resolve;
+use super::{Resolve, ResolveVersion};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EncodableResolve {
+    /// The version of the format, which is absent in the original format.
+    version: Option<u32>,
     package: Option<Vec<EncodableDependency>>,
     /// `root` is optional to allow backward compatibility.
     root: Option<EncodableDependency>,
@@ -34,6 +36,16 @@ pub type Metadata = BTreeMap<String, String>;
 impl EncodableResolve {
     pub fn into_resolve(self, ws: &Workspace<'_>) -> CargoResult<Resolve> {
         let path_deps = build_path_deps(ws);
+        let version = match self.version {
+            None => ResolveVersion::V1,
+            Some(2) => ResolveVersion::V2,
+            Some(v) => failure::bail!(
+                "lock file version `{}` was found, but this version of Cargo \
+                 does not understand this lock file, perhaps Cargo needs \
+                 to be updated?",
+                v
+            ),
+        };
 
         let packages = {
             let mut packages = self.package.unwrap_or_default();
@@ -51,7 +63,7 @@ impl EncodableResolve {
             for pkg in packages.iter() {
                 let enc_id = EncodablePackageId {
                     name: pkg.name.clone(),
-                    version: pkg.version.clone(),
+                    version: Some(pkg.version.clone()),
                     source: pkg.source,
                 };
 
@@ -73,8 +85,46 @@ impl EncodableResolve {
             live_pkgs
         };
 
+        let mut by_name = HashMap::new();
+        for (enc_id, &(id, _)) in live_pkgs.iter() {
+            by_name
+                .entry(enc_id.name.as_str())
+                .or_insert_with(Vec::new)
+                .push((enc_id, id));
+        }
+
         let lookup_id = |enc_id: &EncodablePackageId| -> Option<PackageId> {
-            live_pkgs.get(enc_id).map(|&(id, _)| id)
+            if let Some(&(id, _)) = live_pkgs.get(enc_id) {
+                return Some(id);
+            }
+            if version == ResolveVersion::V1 {
+                return None;
+            }
+            // The version and the source of a package are left out when no
+            // other package has the same name, or name and version.
+            let candidates = by_name
+                .get(enc_id.name.as_str())?
+                .iter()
+                .filter(|(candidate, _)| {
+                    enc_id.version.is_none() || candidate.version == enc_id.version
+                })
+                .filter(|(candidate, _)| {
+                    enc_id.source.is_none() || candidate.source == enc_id.source
+                })
+                .collect::<Vec<_>>();
+            match candidates[..] {
+                [&(_, id)] => Some(id),
+                // Path dependencies have no source, and so are never
+                // ambiguous.
+                _ if enc_id.source.is_none() => {
+                    let mut paths = candidates.iter().filter(|(c, _)| c.source.is_none());
+                    match (paths.next(), paths.next()) {
+                        (Some(&&(_, id)), None) => Some(id),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
         };
 
         let g = {
@@ -155,6 +205,16 @@ impl EncodableResolve {
             metadata.remove(&k);
         }
 
+        // Lock files since `version = 2` list the checksums on the packages,
+        // and a package from a source without checksums has none.
+        if version == ResolveVersion::V2 {
+            for &(id, pkg) in live_pkgs.values() {
+                if !id.source_id().is_path() {
+                    checksums.insert(id, pkg.checksum.clone());
+                }
+            }
+        }
+
         let mut unused_patches = Vec::new();
         for pkg in self.patch.unused {
             let id = match pkg.source.as_ref().or_else(|| path_deps.get(&pkg.name)) {
@@ -171,6 +231,7 @@ impl EncodableResolve {
             checksums,
             metadata,
             unused_patches,
+            version,
         ))
     }
 }
@@ -254,6 +315,7 @@ pub struct EncodableDependency {
     name: String,
     version: String,
     source: Option<SourceId>,
+    checksum: Option<String>,
     dependencies: Option<Vec<EncodablePackageId>>,
     replace: Option<EncodablePackageId>,
 }
@@ -261,13 +323,16 @@ pub struct EncodableDependency {
 #[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Clone)]
 pub struct EncodablePackageId {
     name: String,
-    version: String,
+    version: Option<String>,
     source: Option<SourceId>,
 }
 
 impl fmt::Display for EncodablePackageId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {}", self.name, self.version)?;
+        write!(f, "{}", self.name)?;
+        if let Some(ref v) = self.version {
+            write!(f, " {}", v)?;
+        }
         if let Some(ref s) = self.source {
             write!(f, " ({})", s.to_url())?;
         }
@@ -281,9 +346,7 @@ impl FromStr for EncodablePackageId {
     fn from_str(s: &str) -> CargoResult<EncodablePackageId> {
         let mut s = s.splitn(3, ' ');
         let name = s.next().unwrap();
-        let version = s
-            .next()
-            .ok_or_else(|| internal("invalid serialized PackageId"))?;
+        let version = s.next();
         let source_id = match s.next() {
             Some(s) => {
                 if s.starts_with('(') && s.ends_with(')') {
@@ -297,7 +360,7 @@ impl FromStr for EncodablePackageId {
 
         Ok(EncodablePackageId {
             name: name.to_string(),
-            version: version.to_string(),
+            version: version.map(|v| v.to_string()),
             source: source_id,
         })
     }
@@ -338,14 +401,20 @@ impl<'a, 'cfg> ser::Serialize for WorkspaceResolve<'a, 'cfg> {
         let mut ids: Vec<_> = self.resolve.iter().collect();
         ids.sort();
 
+        let state = EncodeState::new(self.resolve);
+
         let encodable = ids
             .iter()
-            .map(|&id| encodable_resolve_node(id, self.resolve))
+            .map(|&id| encodable_resolve_node(id, self.resolve, &state))
             .collect::<Vec<_>>();
 
         let mut metadata = self.resolve.metadata().clone();
 
-        for &id in ids.iter().filter(|id| !id.source_id().is_path()) {
+        let v1_checksums = match self.resolve.version() {
+            ResolveVersion::V1 => &ids[..],
+            ResolveVersion::V2 => &[],
+        };
+        for &id in v1_checksums.iter().filter(|id| !id.source_id().is_path()) {
             let checksum = match self.resolve.checksums()[&id] {
                 Some(ref s) => &s[..],
                 None => "<none>",
@@ -369,12 +438,18 @@ impl<'a, 'cfg> ser::Serialize for WorkspaceResolve<'a, 'cfg> {
                     name: id.name().to_string(),
                     version: id.version().to_string(),
                     source: encode_source(id.source_id()),
+                    checksum: None,
                     dependencies: None,
                     replace: None,
                 })
                 .collect(),
         };
+        let version = match self.resolve.version() {
+            ResolveVersion::V1 => None,
+            ResolveVersion::V2 => Some(2),
+        };
         EncodableResolve {
+            version,
             package: Some(encodable),
             root: None,
             metadata,
@@ -384,23 +459,77 @@ impl<'a, 'cfg> ser::Serialize for WorkspaceResolve<'a, 'cfg> {
     }
 }
 
-fn encodable_resolve_node(id: PackageId, resolve: &Resolve) -> EncodableDependency {
+/// What's needed to refer to packages as briefly as possible in lock files
+/// since `version = 2`.
+struct EncodeState<'a> {
+    /// The number of packages of each version of each name, if references to
+    /// packages are shortened.
+    counts: Option<HashMap<&'a str, HashMap<&'a semver::Version, usize>>>,
+}
+
+impl<'a> EncodeState<'a> {
+    fn new(resolve: &'a Resolve) -> EncodeState<'a> {
+        let counts = match resolve.version() {
+            ResolveVersion::V1 => None,
+            ResolveVersion::V2 => {
+                let mut counts = HashMap::new();
+                for id in resolve.iter() {
+                    let by_version = counts
+                        .entry(id.name().as_str())
+                        .or_insert_with(HashMap::new);
+                    *by_version.entry(id.version()).or_insert(0) += 1;
+                }
+                Some(counts)
+            }
+        };
+        EncodeState { counts }
+    }
+
+    /// Refers to a package, leaving out its source if no other package has
+    /// the same name and version, and its version if no other package has
+    /// the same name.
+    fn package_id(&self, id: PackageId) -> EncodablePackageId {
+        let mut enc_id = encodable_package_id(id);
+        if let Some(counts) = &self.counts {
+            let by_version = &counts[id.name().as_str()];
+            if by_version[id.version()] == 1 {
+                enc_id.source = None;
+                if by_version.len() == 1 {
+                    enc_id.version = None;
+                }
+            }
+        }
+        enc_id
+    }
+}
+
+fn encodable_resolve_node(
+    id: PackageId,
+    resolve: &Resolve,
+    state: &EncodeState<'_>,
+) -> EncodableDependency {
     let (replace, deps) = match resolve.replacement(id) {
-        Some(id) => (Some(encodable_package_id(id)), None),
+        Some(id) => (Some(state.package_id(id)), None),
         None => {
             let mut deps = resolve
                 .deps_not_replaced(id)
-                .map(encodable_package_id)
+                .map(|id| state.package_id(id))
                 .collect::<Vec<_>>();
             deps.sort();
             (None, Some(deps))
         }
     };
 
+    let checksum = match resolve.version() {
+        ResolveVersion::V1 => None,
+        ResolveVersion::V2 => resolve.checksums().get(&id).and_then(|s| s.clone()),
+    };
+
     EncodableDependency {
         name: id.name().to_string(),
         version: id.version().to_string(),
         source: encode_source(id.source_id()),
+        checksum,
         dependencies: deps,
         replace,
     }
@@ -409,7 +538,7 @@ fn encodable_resolve_node(id: PackageId, resolve: &Resolve) -> EncodableDependen
 pub fn encodable_package_id(id: PackageId) -> EncodablePackageId {
     EncodablePackageId {
         name: id.name().to_string(),
-        version: id.version().to_string(),
+        version: Some(id.version().to_string()),
         source: encode_source(id.source_id()).map(|s| s.with_precise(None)),
     }
 }
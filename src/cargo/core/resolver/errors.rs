@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 use crate::core::{Dependency, PackageId, Registry, Summary};
@@ -6,6 +7,7 @@ use crate::util::Config;
 use failure::{Error, Fail};
 use semver;
 
+use super::conflict_cache::ConflictCache;
 use super::context::Context;
 use super::types::{Candidate, ConflictMap, ConflictReason};
 
@@ -13,6 +15,7 @@ use super::types::{Candidate, ConflictMap, ConflictReason};
 pub struct ResolveError {
     cause: Error,
     package_path: Vec<PackageId>,
+    derivation: Option<String>,
 }
 
 impl ResolveError {
@@ -20,6 +23,7 @@ impl ResolveError {
         Self {
             cause: cause.into(),
             package_path,
+            derivation: None,
         }
     }
 
@@ -28,6 +32,12 @@ impl ResolveError {
     pub fn package_path(&self) -> &[PackageId] {
         &self.package_path
     }
+
+    /// Returns the steps which led to the conflict, from the requirements of
+    /// the packages involved, if the error is a conflict.
+    pub fn derivation(&self) -> Option<&str> {
+        self.derivation.as_ref().map(|s| s.as_str())
+    }
 }
 
 impl Fail for ResolveError {
@@ -75,6 +85,7 @@ pub(super) fn activation_error(
     dep: &Dependency,
     conflicting_activations: &ConflictMap,
     candidates: &[Candidate],
+    past_conflicting_activations: &ConflictCache,
     config: Option<&Config>,
 ) -> ResolveError {
     let to_resolve_err = |err| {
@@ -88,6 +99,18 @@ pub(super) fn activation_error(
         )
     };
 
+    let derivation = Derivation::new(cx).explain(
+        parent,
+        dep,
+        conflicting_activations,
+        candidates,
+        past_conflicting_activations,
+    );
+    let to_conflict_err = |err| ResolveError {
+        derivation: Some(derivation.clone()),
+        ..to_resolve_err(err)
+    };
+
     if !candidates.is_empty() {
         let mut msg = format!("failed to select a version for `{}`.", dep.package_name());
         msg.push_str("\n    ... required by ");
@@ -146,9 +169,10 @@ pub(super) fn activation_error(
             // p == parent so the full path is redundant.
         }
 
-        let (required_dependency_as_features_errors, other_errors): (Vec<_>, Vec<_>) = other_errors
-            .drain(..)
-            .partition(|&(_, r)| r.is_required_dependency_as_features());
+        let (required_dependency_as_features_errors, mut other_errors): (Vec<_>, Vec<_>) =
+            other_errors
+                .drain(..)
+                .partition(|&(_, r)| r.is_required_dependency_as_features());
 
         for &(p, r) in required_dependency_as_features_errors.iter() {
             if let ConflictReason::RequiredDependencyAsFeatures(ref features) = *r {
@@ -169,6 +193,38 @@ pub(super) fn activation_error(
             // p == parent so the full path is redundant.
         }
 
+        let (public_dependency_errors, other_errors): (Vec<_>, Vec<_>) = other_errors
+            .drain(..)
+            .partition(|&(_, r)| *r == ConflictReason::PublicDependency);
+
+        for &(p, _) in public_dependency_errors.iter() {
+            msg.push_str("\n\nthe package `");
+            msg.push_str(&*p.name());
+            msg.push_str(
+                "` can only see one version of each crate through its public dependencies, ",
+            );
+            let seen = cx
+                .public_dependency
+                .as_ref()
+                .and_then(|public| public.get(p))
+                .and_then(|names| names.get(&dep.package_name()));
+            match seen {
+                Some(&(seen, _)) => {
+                    msg.push_str("but it already sees `");
+                    msg.push_str(&seen.to_string());
+                    msg.push_str("`, which conflicts with the versions of `");
+                    msg.push_str(&*dep.package_name());
+                    msg.push_str("` that meet the requirements:\n");
+                }
+                None => {
+                    msg.push_str("which conflicts with the versions of `");
+                    msg.push_str(&*dep.package_name());
+                    msg.push_str("` that meet the requirements:\n");
+                }
+            }
+            msg.push_str(&describe_path(&cx.parents.path_to_bottom(p)));
+        }
+
         if !other_errors.is_empty() {
             msg.push_str(
                 "\n\nall possible versions conflict with \
@@ -185,7 +241,7 @@ pub(super) fn activation_error(
         msg.push_str(&*dep.package_name());
         msg.push_str("` which could resolve this conflict");
 
-        return to_resolve_err(failure::format_err!("{}", msg));
+        return to_conflict_err(failure::format_err!("{}", msg));
     }
 
     // We didn't actually find any candidates, so we need to
@@ -309,7 +365,7 @@ pub(super) fn activation_error(
         }
     }
 
-    to_resolve_err(failure::format_err!("{}", msg))
+    to_conflict_err(failure::format_err!("{}", msg))
 }
 
 /// Returns String representation of dependency chain for a particular `pkgid`.
@@ -321,3 +377,259 @@ pub(super) fn describe_path(path: &[&PackageId]) -> String {
     }
     dep_path_desc
 }
+
+/// The steps leading to a conflict, where each one follows from the
+/// requirements of the packages involved and from the earlier steps it
+/// refers to, in the style of the derivations of PubGrub.
+struct Derivation<'a> {
+    cx: &'a Context,
+    steps: Vec<String>,
+    /// The step explaining why each package was selected.
+    selected: HashMap<PackageId, usize>,
+    /// The packages whose selection is being explained, to not go around in
+    /// circles through dev-dependencies.
+    explaining: HashSet<PackageId>,
+}
+
+impl<'a> Derivation<'a> {
+    fn new(cx: &'a Context) -> Derivation<'a> {
+        Derivation {
+            cx,
+            steps: Vec::new(),
+            selected: HashMap::new(),
+            explaining: HashSet::new(),
+        }
+    }
+
+    /// Explains why no candidate for `dep` of `parent` could be selected.
+    fn explain(
+        mut self,
+        parent: &Summary,
+        dep: &Dependency,
+        conflicting_activations: &ConflictMap,
+        candidates: &[Candidate],
+        past_conflicting_activations: &ConflictCache,
+    ) -> String {
+        let candidates = candidates
+            .iter()
+            .map(|c| &c.summary)
+            .filter(|s| dep.matches_id(s.package_id()))
+            .collect::<Vec<_>>();
+
+        let parent_step = self.selected(parent.package_id());
+        let matching = if candidates.is_empty() {
+            "no version".to_string()
+        } else {
+            candidates
+                .iter()
+                .map(|s| format!("`{}`", s.package_id()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let requirement = self.push(
+            &[parent_step],
+            format!(
+                "`{}` depends on {}, which matches {}.",
+                parent.package_id(),
+                describe_dep(dep),
+                matching
+            ),
+        );
+
+        let mut steps = vec![requirement];
+        for candidate in candidates {
+            steps.push(self.rejected(
+                parent,
+                candidate,
+                conflicting_activations,
+                past_conflicting_activations,
+            ));
+        }
+        self.push(
+            &steps,
+            format!(
+                "no version of `{}` can be selected for `{}`, so resolution failed.",
+                dep.package_name(),
+                parent.package_id()
+            ),
+        );
+
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| format!("({}) {}", i + 1, step))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Adds a step which follows from the `because` steps, returning its
+    /// index.
+    fn push(&mut self, because: &[usize], conclusion: String) -> usize {
+        let mut because = because.to_vec();
+        because.sort();
+        because.dedup();
+        let step = match because.split_last() {
+            None => conclusion,
+            Some((last, [])) => format!("Because of ({}), {}", last + 1, conclusion),
+            Some((last, rest)) => format!(
+                "Because of {} and ({}), {}",
+                rest.iter()
+                    .map(|i| format!("({})", i + 1))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                last + 1,
+                conclusion
+            ),
+        };
+        self.steps.push(step);
+        self.steps.len() - 1
+    }
+
+    /// Explains why `id` was selected, from the requirements of one of the
+    /// packages depending on it.
+    fn selected(&mut self, id: PackageId) -> usize {
+        if let Some(&step) = self.selected.get(&id) {
+            return step;
+        }
+        self.explaining.insert(id);
+        let cx = self.cx;
+        let parent = cx
+            .parents
+            .edges(&id)
+            .find(|(p, _)| !self.explaining.contains(p));
+        let step = match parent {
+            Some((p, deps)) => {
+                let p_step = self.selected(*p);
+                let mut reqs = deps.iter().map(describe_dep).collect::<Vec<_>>();
+                reqs.dedup();
+                self.push(
+                    &[p_step],
+                    format!(
+                        "`{}` is selected, as `{}` depends on {}.",
+                        id,
+                        p,
+                        reqs.join(" and ")
+                    ),
+                )
+            }
+            None => self.push(&[], format!("`{}` is being resolved.", id)),
+        };
+        self.explaining.remove(&id);
+        self.selected.insert(id, step);
+        step
+    }
+
+    /// Explains why `candidate` couldn't be selected for a dependency of
+    /// `parent`.
+    fn rejected(
+        &mut self,
+        parent: &Summary,
+        candidate: &Summary,
+        conflicting_activations: &ConflictMap,
+        past_conflicting_activations: &ConflictCache,
+    ) -> usize {
+        let cx = self.cx;
+        let id = candidate.package_id();
+
+        if let Some((other, _)) = cx.activations.get(&id.as_activations_key()) {
+            if other.package_id() != id {
+                let other_step = self.selected(other.package_id());
+                return self.push(
+                    &[other_step],
+                    format!(
+                        "`{}` cannot be selected, as only one semver compatible version \
+                         of `{}` can be selected.",
+                        id,
+                        id.name()
+                    ),
+                );
+            }
+        }
+
+        if let Some(link) = candidate.links() {
+            if let Some(&other) = cx.links.get(&link) {
+                if other != id {
+                    let other_step = self.selected(other);
+                    return self.push(
+                        &[other_step],
+                        format!(
+                            "`{}` cannot be selected, as it links to the native library \
+                             `{}` as well, and only one package may link to it.",
+                            id, link
+                        ),
+                    );
+                }
+            }
+        }
+
+        // Conflicts found earlier which still apply rule out candidates
+        // through their own dependencies.
+        for dep in candidate.dependencies() {
+            if let Some(con) = past_conflicting_activations.conflicting(cx, dep) {
+                let mut con = con.keys().cloned().collect::<Vec<_>>();
+                con.sort();
+                let steps = con.iter().map(|&p| self.selected(p)).collect::<Vec<_>>();
+                return self.push(
+                    &steps,
+                    format!(
+                        "`{}` cannot be selected, as it depends on {}, which was found \
+                         to conflict with them.",
+                        id,
+                        describe_dep(dep)
+                    ),
+                );
+            }
+        }
+
+        match conflicting_activations.get(&parent.package_id()) {
+            Some(ConflictReason::MissingFeatures(features)) => {
+                return self.push(
+                    &[],
+                    format!(
+                        "`{}` cannot be selected, as it doesn't have the features `{}` \
+                         which `{}` requires.",
+                        id,
+                        features,
+                        parent.package_id()
+                    ),
+                );
+            }
+            Some(ConflictReason::RequiredDependencyAsFeatures(features)) => {
+                return self.push(
+                    &[],
+                    format!(
+                        "`{}` cannot be selected, as `{}` requires the features `{}`, \
+                         which are required dependencies of it rather than features.",
+                        id,
+                        parent.package_id(),
+                        features
+                    ),
+                );
+            }
+            _ => {}
+        }
+
+        let mut con = conflicting_activations.iter().collect::<Vec<_>>();
+        con.sort();
+        let public = con
+            .iter()
+            .any(|&(_, r)| *r == ConflictReason::PublicDependency);
+        let steps = con
+            .iter()
+            .map(|&(&p, _)| self.selected(p))
+            .collect::<Vec<_>>();
+        let reason = if public {
+            "it would make two versions of a public dependency visible to the same package"
+        } else {
+            "it conflicts with previously selected packages"
+        };
+        self.push(
+            &steps,
+            format!("`{}` cannot be selected, as {}.", id, reason),
+        )
+    }
+}
+
+fn describe_dep(dep: &Dependency) -> String {
+    format!("`{} = \"{}\"`", dep.package_name(), dep.version_req())
+}
@@ -68,7 +68,7 @@ use self::types::{FeaturesSet, RcVecIter, RemainingDeps, ResolverProgress};
 pub use self::encode::{EncodableDependency, EncodablePackageId, EncodableResolve};
 pub use self::encode::{Metadata, WorkspaceResolve};
 pub use self::errors::{ActivateError, ActivateResult, ResolveError};
-pub use self::resolve::Resolve;
+pub use self::resolve::{Resolve, ResolveVersion};
 pub use self::types::Method;
 
 mod conflict_cache;
@@ -133,7 +133,20 @@ pub fn resolve(
         Some(config) => config.cli_unstable().minimal_versions,
         None => false,
     };
-    let mut registry = RegistryQueryer::new(registry, replacements, try_to_use, minimal_versions);
+    let direct_minimal_versions = match config {
+        Some(config) if config.cli_unstable().direct_minimal_versions => summaries
+            .iter()
+            .map(|(summary, _)| summary.package_id())
+            .collect(),
+        _ => HashSet::new(),
+    };
+    let mut registry = RegistryQueryer::new(
+        registry,
+        replacements,
+        try_to_use,
+        minimal_versions,
+        direct_minimal_versions,
+    );
     let cx = activate_deps_loop(cx, &mut registry, summaries, config)?;
 
     let mut cksums = HashMap::new();
@@ -151,6 +164,7 @@ pub fn resolve(
         cksums,
         BTreeMap::new(),
         Vec::new(),
+        ResolveVersion::default(),
     );
 
     check_cycles(&resolve, &cx.activations)?;
@@ -332,6 +346,7 @@ fn activate_deps_loop(
                             &dep,
                             &conflicting_activations,
                             &candidates,
+                            &past_conflicting_activations,
                             config,
                         ))
                     }
@@ -891,12 +906,13 @@ fn generalize_conflicting(
             cx.is_active(*p).expect("parent not currently active!?") < backtrack_critical_age
         })
     {
+        let first_minimal_version = registry.first_minimal_version(*critical_parent);
         for critical_parents_dep in critical_parents_deps.iter() {
             // A dep is equivalent to one of the things it can resolve to.
             // Thus, if all the things it can resolve to have already ben determined
             // to be conflicting, then we can just say that we conflict with the parent.
             if registry
-                .query(critical_parents_dep)
+                .query(critical_parents_dep, first_minimal_version)
                 .expect("an already used dep now error!?")
                 .iter()
                 .rev() // the last one to be tried is the least likely to be in the cache, so start with that.
@@ -1,4 +1,5 @@
 use std::borrow::Borrow;
+use std::cmp;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::iter::FromIterator;
@@ -32,6 +33,29 @@ pub struct Resolve {
     unused_patches: Vec<PackageId>,
     // A map from packages to a set of their public dependencies
     public_dependencies: HashMap<PackageId, HashSet<PackageId>>,
+    /// The format of the lock file this is written to.
+    version: ResolveVersion,
+}
+
+/// The format of `Cargo.lock`.
+///
+/// A lock file keeps the format it's in when Cargo updates it, the newer ones
+/// are only used when asked for with `cargo update --lockfile-version`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, PartialOrd, Ord)]
+pub enum ResolveVersion {
+    /// The original format, which lists all the checksums in `[metadata]`,
+    /// and the name, version and source of every dependency.
+    V1,
+    /// `version = 2`, which lists the checksum of each package alongside it,
+    /// and leaves out the version and source of dependencies when they're
+    /// unambiguous, so that changes to different packages don't conflict.
+    V2,
+}
+
+impl Default for ResolveVersion {
+    fn default() -> ResolveVersion {
+        ResolveVersion::V1
+    }
 }
 
 impl Resolve {
@@ -42,6 +66,7 @@ impl Resolve {
         checksums: HashMap<PackageId, Option<String>>,
         metadata: Metadata,
         unused_patches: Vec<PackageId>,
+        version: ResolveVersion,
     ) -> Resolve {
         let reverse_replacements = replacements.iter().map(|(&p, &r)| (r, p)).collect();
         let public_dependencies = graph
@@ -78,6 +103,7 @@ impl Resolve {
             empty_features: HashSet::new(),
             reverse_replacements,
             public_dependencies,
+            version,
         }
     }
 
@@ -184,9 +210,54 @@ unable to verify that `{0}` is the same as when the lockfile was generated
 
         // Be sure to just copy over any unknown metadata.
         self.metadata = previous.metadata.clone();
+        // The lock file keeps its format.
+        self.version = previous.version;
         Ok(())
     }
 
+    /// Combines the packages and dependency edges of two resolves, such as
+    /// the two sides of a merge conflict in a lock file.
+    ///
+    /// The result isn't a valid resolve on its own, as it may hold several
+    /// versions of a package where only one is wanted, but it can be used as
+    /// the previous resolve to guide the resolution process.
+    pub fn union(&self, other: &Resolve) -> Resolve {
+        let mut graph = self.graph.clone();
+        for &id in other.graph.iter() {
+            graph.add(id);
+            for &(dep, _) in other.graph.edges(&id) {
+                graph.link(id, dep);
+            }
+        }
+
+        let mut replacements = other.replacements.clone();
+        replacements.extend(self.replacements.iter().map(|(&p, &r)| (p, r)));
+        let mut features = other.features.clone();
+        for (&id, f) in self.features.iter() {
+            features.entry(id).or_default().extend(f.iter().cloned());
+        }
+        let mut checksums = other.checksums.clone();
+        checksums.extend(self.checksums.iter().map(|(&id, c)| (id, c.clone())));
+        let mut metadata = other.metadata.clone();
+        metadata.extend(self.metadata.iter().map(|(k, v)| (k.clone(), v.clone())));
+        let mut unused_patches = self.unused_patches.clone();
+        for id in other.unused_patches.iter() {
+            if !unused_patches.contains(id) {
+                unused_patches.push(*id);
+            }
+        }
+
+        Resolve::new(
+            graph,
+            replacements,
+            features,
+            checksums,
+            metadata,
+            unused_patches,
+            cmp::max(self.version, other.version),
+        )
+    }
+
     pub fn contains<Q: ?Sized>(&self, k: &Q) -> bool
     where
         PackageId: Borrow<Q>,
@@ -254,6 +325,14 @@ unable to verify that `{0}` is the same as when the lockfile was generated
         &self.metadata
     }
 
+    pub fn version(&self) -> ResolveVersion {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: ResolveVersion) {
+        self.version = version;
+    }
+
     pub fn extern_crate_name(
         &self,
         from: PackageId,
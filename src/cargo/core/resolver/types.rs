@@ -261,9 +261,10 @@ pub enum ConflictReason {
     /// it had a dependency `foo` instead.
     RequiredDependencyAsFeatures(InternedString),
 
-    // TODO: needs more info for `activation_error`
     // TODO: needs more info for `find_candidate`
-    /// pub dep error
+    /// A package would see two different versions of a crate with the same
+    /// name through its public dependencies. The package recorded with this
+    /// reason is the one which would see both.
     PublicDependency,
 }
 
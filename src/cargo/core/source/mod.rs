@@ -7,7 +7,7 @@ use crate::util::{CargoResult, Config};
 
 mod source_id;
 
-pub use self::source_id::{GitReference, SourceId};
+pub use self::source_id::{GitReference, GitSubmodules, SourceId};
 
 /// Something that finds and downloads remote packages based on names and versions.
 pub trait Source {
@@ -307,6 +307,14 @@ impl<'src> SourceMap<'src> {
         self.map.len()
     }
 
+    /// Merges the given map into this one, keeping the sources already in
+    /// this map where both have the same `SourceId`.
+    pub fn add_source_map(&mut self, other: SourceMap<'src>) {
+        for (id, source) in other.map {
+            self.map.entry(id).or_insert(source);
+        }
+    }
+
     /// Like `HashMap::values`.
     pub fn sources<'a>(&'a self) -> impl Iterator<Item = &'a Box<dyn Source + 'src>> {
         self.map.values()
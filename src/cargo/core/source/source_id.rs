@@ -11,6 +11,7 @@ use std::sync::Mutex;
 use log::trace;
 use serde::de;
 use serde::ser;
+use url::form_urlencoded;
 use url::Url;
 
 use crate::core::PackageId;
@@ -38,6 +39,8 @@ struct SourceIdInner {
     canonical_url: Url,
     /// The source kind.
     kind: Kind,
+    /// For a Git source, the submodules which are checked out with it.
+    submodules: GitSubmodules,
     /// For example, the exact Git revision of the specified branch for a Git Source.
     precise: Option<String>,
     /// Name of the registry source for alternative registries
@@ -73,19 +76,25 @@ pub enum GitReference {
     Rev(String),
 }
 
+/// The submodules of a Git repository which are checked out along with it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GitSubmodules {
+    /// All of them, recursively.
+    All,
+    /// None of them.
+    None,
+    /// Only the ones at the given paths, along with all of their own
+    /// submodules. The paths are sorted and unique, see
+    /// `GitSubmodules::paths`.
+    Paths(Vec<String>),
+}
+
 impl SourceId {
     /// Creates a `SourceId` object from the kind and URL.
     ///
     /// The canonical url will be calculated, but the precise field will not
     fn new(kind: Kind, url: Url) -> CargoResult<SourceId> {
-        let source_id = SourceId::wrap(SourceIdInner {
-            kind,
-            canonical_url: git::canonicalize_url(&url)?,
-            url,
-            precise: None,
-            name: None,
-        });
-        Ok(source_id)
+        Ok(SourceId::wrap(SourceIdInner::new(kind, url)?))
     }
 
     fn wrap(inner: SourceIdInner) -> SourceId {
@@ -119,6 +128,7 @@ impl SourceId {
             "git" => {
                 let mut url = url.to_url()?;
                 let mut reference = GitReference::Branch("master".to_string());
+                let mut submodules = Vec::new();
                 for (k, v) in url.query_pairs() {
                     match &k[..] {
                         // Map older 'ref' to branch.
@@ -126,13 +136,16 @@ impl SourceId {
 
                         "rev" => reference = GitReference::Rev(v.into_owned()),
                         "tag" => reference = GitReference::Tag(v.into_owned()),
+                        "submodules" => submodules.push(v.into_owned()),
                         _ => {}
                     }
                 }
                 let precise = url.fragment().map(|s| s.to_owned());
                 url.set_fragment(None);
                 url.set_query(None);
-                Ok(SourceId::for_git(&url, reference)?.with_precise(precise))
+                Ok(SourceId::for_git(&url, reference)?
+                    .with_git_submodules(GitSubmodules::from_query(submodules))
+                    .with_precise(precise))
             }
             "registry" => {
                 let url = url.to_url()?;
@@ -215,11 +228,8 @@ impl SourceId {
     pub fn alt_registry(config: &Config, key: &str) -> CargoResult<SourceId> {
         let url = config.get_registry_index(key)?;
         Ok(SourceId::wrap(SourceIdInner {
-            kind: Kind::Registry,
-            canonical_url: git::canonicalize_url(&url)?,
-            url,
-            precise: None,
             name: Some(key.to_string()),
+            ..SourceIdInner::new(Kind::Registry, url)?
         }))
     }
 
@@ -323,6 +333,26 @@ impl SourceId {
         }
     }
 
+    /// Gets the submodules to check out if this is a git source, otherwise
+    /// `None`.
+    pub fn git_submodules(self) -> Option<&'static GitSubmodules> {
+        match self.inner.kind {
+            Kind::Git(_) => Some(&self.inner.submodules),
+            _ => None,
+        }
+    }
+
+    /// Creates a new `SourceId` from this git source which only checks out
+    /// the given `submodules`.
+    ///
+    /// Unlike `precise`, this is part of the identity of the source.
+    pub fn with_git_submodules(self, submodules: GitSubmodules) -> SourceId {
+        SourceId::wrap(SourceIdInner {
+            submodules,
+            ..(*self.inner).clone()
+        })
+    }
+
     /// Creates a new `SourceId` from this source with the given `precise`.
     pub fn with_precise(self, v: Option<String>) -> SourceId {
         SourceId::wrap(SourceIdInner {
@@ -452,6 +482,9 @@ impl PartialEq for SourceId {
         if self.inner.kind != other.inner.kind {
             return false;
         }
+        if self.inner.submodules != other.inner.submodules {
+            return false;
+        }
         if self.inner.url == other.inner.url {
             return true;
         }
@@ -465,6 +498,21 @@ impl PartialEq for SourceId {
     }
 }
 
+impl SourceIdInner {
+    /// Creates the source of the given kind and URL, with the default value
+    /// of every other field.
+    fn new(kind: Kind, url: Url) -> CargoResult<SourceIdInner> {
+        Ok(SourceIdInner {
+            kind,
+            canonical_url: git::canonicalize_url(&url)?,
+            url,
+            submodules: GitSubmodules::All,
+            precise: None,
+            name: None,
+        })
+    }
+}
+
 impl PartialOrd for SourceIdInner {
     fn partial_cmp(&self, other: &SourceIdInner) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -482,9 +530,8 @@ impl Ord for SourceIdInner {
             ord => return ord,
         }
         match (&self.kind, &other.kind) {
-            (Kind::Git(ref1), Kind::Git(ref2)) => {
-                (ref1, &self.canonical_url).cmp(&(ref2, &other.canonical_url))
-            }
+            (Kind::Git(ref1), Kind::Git(ref2)) => (ref1, &self.submodules, &self.canonical_url)
+                .cmp(&(ref2, &other.submodules, &other.canonical_url)),
             _ => self.kind.cmp(&other.kind),
         }
     }
@@ -519,12 +566,18 @@ impl<'a> fmt::Display for SourceIdToUrl<'a> {
             SourceIdInner {
                 kind: Kind::Git(ref reference),
                 ref url,
+                ref submodules,
                 ref precise,
                 ..
             } => {
                 write!(f, "git+{}", url)?;
+                let mut sep = '?';
                 if let Some(pretty) = reference.pretty_ref() {
-                    write!(f, "?{}", pretty)?;
+                    write!(f, "{}{}", sep, pretty)?;
+                    sep = '&';
+                }
+                if let Some(pretty) = submodules.pretty_submodules() {
+                    write!(f, "{}{}", sep, pretty)?;
                 }
                 if let Some(precise) = precise.as_ref() {
                     write!(f, "#{}", precise)?;
@@ -576,9 +629,77 @@ impl<'a> fmt::Display for PrettyRef<'a> {
     }
 }
 
+impl GitSubmodules {
+    /// The submodules at the given paths, in a canonical form so that the
+    /// same selection always gives the same source.
+    pub fn paths(mut paths: Vec<String>) -> GitSubmodules {
+        paths.sort();
+        paths.dedup();
+        if paths.is_empty() {
+            GitSubmodules::None
+        } else {
+            GitSubmodules::Paths(paths)
+        }
+    }
+
+    /// Parses the values of the `submodules` query parameters of a source
+    /// URL, of which there is one for each path.
+    fn from_query(values: Vec<String>) -> GitSubmodules {
+        match values.as_slice() {
+            [] => GitSubmodules::All,
+            [v] if v == "true" => GitSubmodules::All,
+            [v] if v == "false" => GitSubmodules::None,
+            _ => GitSubmodules::paths(values),
+        }
+    }
+
+    /// Returns a `Display`able view of these submodules, or None if all
+    /// submodules are checked out.
+    pub fn pretty_submodules(&self) -> Option<PrettySubmodules<'_>> {
+        match *self {
+            GitSubmodules::All => None,
+            _ => Some(PrettySubmodules { inner: self }),
+        }
+    }
+
+    /// Returns whether the submodule at `path`, relative to the root of the
+    /// repository, is checked out.
+    pub fn includes(&self, path: &Path) -> bool {
+        match *self {
+            GitSubmodules::All => true,
+            GitSubmodules::None => false,
+            GitSubmodules::Paths(ref paths) => paths.iter().any(|p| Path::new(p) == path),
+        }
+    }
+}
+
+/// The submodules of a git source that can be `Display`ed in its URL.
+pub struct PrettySubmodules<'a> {
+    inner: &'a GitSubmodules,
+}
+
+impl<'a> fmt::Display for PrettySubmodules<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self.inner {
+            GitSubmodules::All => write!(f, "submodules=true"),
+            GitSubmodules::None => write!(f, "submodules=false"),
+            GitSubmodules::Paths(ref paths) => {
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "&")?;
+                    }
+                    let path = form_urlencoded::byte_serialize(path.as_bytes());
+                    write!(f, "submodules={}", path.collect::<String>())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{GitReference, Kind, SourceId};
+    use super::{GitReference, GitSubmodules, Kind, SourceId};
     use crate::util::ToUrl;
 
     #[test]
@@ -596,4 +717,33 @@ mod tests {
         let s3 = SourceId::new(foo, loc).unwrap();
         assert_ne!(s1, s3);
     }
+
+    #[test]
+    fn git_submodules_round_trip() {
+        let loc = "https://github.com/foo/bar".to_url().unwrap();
+        let foo = GitReference::Branch("foo".to_string());
+        let s1 = SourceId::for_git(&loc, foo).unwrap();
+        let s2 = s1.with_git_submodules(GitSubmodules::paths(vec![
+            "b,c/d&e".to_string(),
+            "a".to_string(),
+            "a".to_string(),
+        ]));
+        assert_ne!(s1, s2);
+
+        let url = s2.to_url().to_string();
+        assert_eq!(
+            url,
+            "git+https://github.com/foo/bar?branch=foo&submodules=a&submodules=b%2Cc%2Fd%26e"
+        );
+        assert_eq!(SourceId::from_url(&url).unwrap(), s2);
+        assert_eq!(GitSubmodules::paths(Vec::new()), GitSubmodules::None);
+
+        let s3 = s1.with_git_submodules(GitSubmodules::None);
+        let url = s3.to_url().to_string();
+        assert_eq!(
+            url,
+            "git+https://github.com/foo/bar?branch=foo&submodules=false"
+        );
+        assert_eq!(SourceId::from_url(&url).unwrap(), s3);
+    }
 }
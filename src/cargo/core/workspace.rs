@@ -83,6 +83,11 @@ pub struct Workspace<'cfg> {
     // If `true`, then the resolver will ignore any existing `Cargo.lock`
     // file. This is set for `cargo install` without `--locked`.
     ignore_lock: bool,
+
+    // Local registries to layer on top of other sources when resolving, used
+    // to verify packages depending on siblings which are not published yet.
+    // See `PackageRegistry::add_local_overlay`.
+    local_overlays: HashMap<SourceId, PathBuf>,
 }
 
 // Separate structure for tracking loaded packages (to avoid loading anything
@@ -138,17 +143,24 @@ impl<'cfg> Workspace<'cfg> {
     /// root and all member packages. It will then validate the workspace
     /// before returning it, so `Ok` is only returned for valid workspaces.
     pub fn new(manifest_path: &Path, config: &'cfg Config) -> CargoResult<Workspace<'cfg>> {
-        let target_dir = config.target_dir()?;
+        let mut ws = Workspace::new_default(manifest_path.to_path_buf(), config);
+        ws.target_dir = config.target_dir()?;
+        ws.root_manifest = ws.find_root(manifest_path)?;
+        ws.find_members()?;
+        ws.validate()?;
+        Ok(ws)
+    }
 
-        let mut ws = Workspace {
+    fn new_default(current_manifest: PathBuf, config: &'cfg Config) -> Workspace<'cfg> {
+        Workspace {
             config,
-            current_manifest: manifest_path.to_path_buf(),
+            current_manifest,
             packages: Packages {
                 config,
                 packages: HashMap::new(),
             },
             root_manifest: None,
-            target_dir,
+            target_dir: None,
             members: Vec::new(),
             member_ids: HashSet::new(),
             default_members: Vec::new(),
@@ -156,10 +168,31 @@ impl<'cfg> Workspace<'cfg> {
             require_optional_deps: true,
             loaded_packages: RefCell::new(HashMap::new()),
             ignore_lock: false,
-        };
-        ws.root_manifest = ws.find_root(manifest_path)?;
+            local_overlays: HashMap::new(),
+        }
+    }
+
+    /// Creates a workspace rooted at `root_path` from a virtual manifest which
+    /// doesn't exist on the filesystem, such as the one used to build the
+    /// standard library from source.
+    ///
+    /// The workspace is ephemeral, so its lock file is never written.
+    pub fn new_virtual(
+        root_path: PathBuf,
+        current_manifest: PathBuf,
+        manifest: VirtualManifest,
+        config: &'cfg Config,
+    ) -> CargoResult<Workspace<'cfg>> {
+        let mut ws = Workspace::new_default(current_manifest, config);
+        ws.root_manifest = Some(root_path.join("Cargo.toml"));
+        ws.target_dir = config.target_dir()?;
+        ws.is_ephemeral = true;
+        ws.packages
+            .packages
+            .insert(root_path, MaybePackage::Virtual(manifest));
         ws.find_members()?;
-        ws.validate()?;
+        // Validation is skipped as it walks up from the members looking for
+        // the root manifest, which doesn't exist on the filesystem.
         Ok(ws)
     }
 
@@ -178,23 +211,9 @@ impl<'cfg> Workspace<'cfg> {
         target_dir: Option<Filesystem>,
         require_optional_deps: bool,
     ) -> CargoResult<Workspace<'cfg>> {
-        let mut ws = Workspace {
-            config,
-            current_manifest: package.manifest_path().to_path_buf(),
-            packages: Packages {
-                config,
-                packages: HashMap::new(),
-            },
-            root_manifest: None,
-            target_dir: None,
-            members: Vec::new(),
-            member_ids: HashSet::new(),
-            default_members: Vec::new(),
-            is_ephemeral: true,
-            require_optional_deps,
-            loaded_packages: RefCell::new(HashMap::new()),
-            ignore_lock: false,
-        };
+        let mut ws = Workspace::new_default(package.manifest_path().to_path_buf(), config);
+        ws.is_ephemeral = true;
+        ws.require_optional_deps = require_optional_deps;
         {
             let key = ws.current_manifest.parent().unwrap();
             let id = package.package_id();
@@ -349,6 +368,22 @@ impl<'cfg> Workspace<'cfg> {
         self
     }
 
+    /// Layers the local registry at `path` on top of `source_id` whenever
+    /// dependencies of this workspace are resolved.
+    pub fn add_local_overlay(&mut self, source_id: SourceId, path: &Path) {
+        self.local_overlays.insert(source_id, path.to_path_buf());
+    }
+
+    /// Creates a `PackageRegistry` for resolving this workspace, with any
+    /// local overlays already registered.
+    pub fn package_registry(&self) -> CargoResult<PackageRegistry<'cfg>> {
+        let mut registry = PackageRegistry::new(self.config)?;
+        for (source_id, path) in self.local_overlays.iter() {
+            registry.add_local_overlay(*source_id, path);
+        }
+        Ok(registry)
+    }
+
     /// Finds the root of a workspace for the crate whose manifest is located
     /// at `manifest_path`.
     ///
@@ -15,8 +15,8 @@ pub struct CleanOptions<'a> {
     pub config: &'a Config,
     /// A list of packages to clean. If empty, everything is cleaned.
     pub spec: Vec<String>,
-    /// The target arch triple to clean, or None for the host arch
-    pub target: Option<String>,
+    /// The target arch triples to clean, or empty for the host arch
+    pub targets: Vec<String>,
     /// Whether to clean the release directory
     pub release: bool,
     /// Whether to just clean the doc directory
@@ -50,14 +50,23 @@ pub fn clean(ws: &Workspace<'_>, opts: &CleanOptions<'_>) -> CargoResult<()> {
 
     let (packages, resolve) = ops::resolve_ws(ws)?;
 
+    // Translate the specs to packages.
+    let pkgs = opts
+        .spec
+        .iter()
+        .map(|spec| packages.get_one(resolve.query(spec)?))
+        .collect::<CargoResult<Vec<_>>>()?;
+
     let profiles = ws.profiles();
     let interner = UnitInterner::new();
-    let mut build_config = BuildConfig::new(config, Some(1), &opts.target, CompileMode::Build)?;
+    let mut build_config = BuildConfig::new(config, Some(1), &opts.targets, CompileMode::Build)?;
     build_config.release = opts.release;
     let bcx = BuildContext::new(
         ws,
         &resolve,
+        None,
         &packages,
+        &pkgs,
         opts.config,
         &build_config,
         profiles,
@@ -66,14 +75,18 @@ pub fn clean(ws: &Workspace<'_>, opts: &CleanOptions<'_>) -> CargoResult<()> {
     )?;
     let mut units = Vec::new();
 
-    for spec in opts.spec.iter() {
-        // Translate the spec to a Package
-        let pkgid = resolve.query(spec)?;
-        let pkg = packages.get_one(pkgid)?;
+    for pkg in pkgs.iter() {
+        // The package may have been compiled for the host, for the target it
+        // selects itself, or for the requested targets.
+        let mut kinds = vec![Kind::Host];
+        kinds.extend(bcx.root_kinds(pkg));
+        kinds.extend(bcx.requested_kinds());
+        kinds.sort();
+        kinds.dedup();
 
         // Generate all relevant `Unit` targets for this package
         for target in pkg.targets() {
-            for kind in [Kind::Host, Kind::Target].iter() {
+            for kind in kinds.iter() {
                 for mode in CompileMode::all_modes() {
                     for unit_for in UnitFor::all_values() {
                         let profile = if mode.is_run_custom_build() {
@@ -93,7 +106,7 @@ pub fn clean(ws: &Workspace<'_>, opts: &CleanOptions<'_>) -> CargoResult<()> {
                                 opts.release,
                             )
                         };
-                        units.push(bcx.units.intern(pkg, target, profile, *kind, *mode));
+                        units.push(bcx.units.intern(pkg, target, profile, *kind, *mode, false));
                     }
                 }
             }
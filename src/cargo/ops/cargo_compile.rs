@@ -26,6 +26,7 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 
+use crate::core::compiler::standard_lib;
 use crate::core::compiler::{BuildConfig, BuildContext, Compilation, Context};
 use crate::core::compiler::{CompileMode, Kind, Unit};
 use crate::core::compiler::{DefaultExecutor, Executor, UnitInterner};
@@ -73,7 +74,7 @@ impl<'a> CompileOptions<'a> {
     pub fn new(config: &'a Config, mode: CompileMode) -> CargoResult<CompileOptions<'a>> {
         Ok(CompileOptions {
             config,
-            build_config: BuildConfig::new(config, None, &None, mode)?,
+            build_config: BuildConfig::new(config, None, &[], mode)?,
             features: Vec::new(),
             all_features: false,
             no_default_features: false,
@@ -290,12 +291,6 @@ pub fn compile_ws<'a>(
         }
     }
 
-    let default_arch_kind = if build_config.requested_target.is_some() {
-        Kind::Target
-    } else {
-        Kind::Host
-    };
-
     let specs = spec.to_package_id_specs(ws)?;
     let features = Method::split_features(features);
     let method = Method::Required {
@@ -305,7 +300,19 @@ pub fn compile_ws<'a>(
         uses_default_features: !no_default_features,
     };
     let resolve = ops::resolve_ws_with_method(ws, method, &specs)?;
-    let (packages, resolve_with_overrides) = resolve;
+    let (mut packages, resolve_with_overrides) = resolve;
+
+    let std_resolve = match config.cli_unstable().build_std {
+        Some(ref crates) => {
+            if build_config.requested_targets.is_empty() {
+                failure::bail!("-Z build-std requires --target");
+            }
+            let (std_package_set, std_resolve) = standard_lib::resolve_std(ws, crates)?;
+            packages.add_set(std_package_set);
+            Some(std_resolve)
+        }
+        None => None,
+    };
 
     let to_build_ids = specs
         .iter()
@@ -353,7 +360,9 @@ pub fn compile_ws<'a>(
     let mut bcx = BuildContext::new(
         ws,
         &resolve_with_overrides,
+        std_resolve.as_ref(),
         &packages,
+        &to_builds,
         config,
         build_config,
         profiles,
@@ -365,7 +374,6 @@ pub fn compile_ws<'a>(
         profiles,
         &to_builds,
         filter,
-        default_arch_kind,
         &resolve_with_overrides,
         &bcx,
     )?;
@@ -580,12 +588,11 @@ fn generate_targets<'a>(
     profiles: &Profiles,
     packages: &[&'a Package],
     filter: &CompileFilter,
-    default_arch_kind: Kind,
     resolve: &Resolve,
     bcx: &BuildContext<'a, '_>,
 ) -> CargoResult<Vec<Unit<'a>>> {
     // Helper for creating a `Unit` struct.
-    let new_unit = |pkg: &'a Package, target: &'a Target, target_mode: CompileMode| {
+    let new_unit = |pkg: &'a Package, target: &'a Target, target_mode: CompileMode, kind: Kind| {
         let unit_for = if bcx.build_config.mode.is_any_test() {
             // NOTE: the `UnitFor` here is subtle. If you have a profile
             // with `panic` set, the `panic` flag is cleared for
@@ -641,11 +648,7 @@ fn generate_targets<'a>(
             _ => target_mode,
         };
         // Plugins or proc macros should be built for the host.
-        let kind = if target.for_host() {
-            Kind::Host
-        } else {
-            default_arch_kind
-        };
+        let kind = if target.for_host() { Kind::Host } else { kind };
         let profile = profiles.get_profile(
             pkg.package_id(),
             ws.is_member(pkg),
@@ -653,7 +656,8 @@ fn generate_targets<'a>(
             target_mode,
             bcx.build_config.release,
         );
-        bcx.units.intern(pkg, target, profile, kind, target_mode)
+        bcx.units
+            .intern(pkg, target, profile, kind, target_mode, false)
     };
 
     // Create a list of proposed targets.
@@ -800,8 +804,10 @@ fn generate_targets<'a>(
             None => Vec::new(),
         };
         if target.is_lib() || unavailable_features.is_empty() {
-            let unit = new_unit(pkg, target, mode);
-            units.insert(unit);
+            // Host units, such as proc macros, are shared by all targets.
+            for kind in bcx.root_kinds(pkg) {
+                units.insert(new_unit(pkg, target, mode, kind));
+            }
         } else if requires_features {
             let required_features = target.required_features().unwrap();
             let quoted_required_features: Vec<String> = required_features
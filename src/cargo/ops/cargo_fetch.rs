@@ -1,5 +1,5 @@
 use crate::core::compiler::{BuildConfig, CompileMode, Kind, TargetInfo};
-use crate::core::{PackageSet, Resolve, Workspace};
+use crate::core::{InternedString, PackageSet, Resolve, Workspace};
 use crate::ops;
 use crate::util::CargoResult;
 use crate::util::Config;
@@ -7,8 +7,8 @@ use std::collections::HashSet;
 
 pub struct FetchOptions<'a> {
     pub config: &'a Config,
-    /// The target arch triple to fetch dependencies for
-    pub target: Option<String>,
+    /// The target arch triples to fetch dependencies for, or empty for all
+    pub targets: Vec<String>,
 }
 
 /// Executes `cargo fetch`.
@@ -20,10 +20,14 @@ pub fn fetch<'a>(
 
     let jobs = Some(1);
     let config = ws.config();
-    let build_config = BuildConfig::new(config, jobs, &options.target, CompileMode::Build)?;
+    let build_config = BuildConfig::new(config, jobs, &options.targets, CompileMode::Build)?;
     let rustc = config.load_global_rustc(Some(ws))?;
-    let target_info =
-        TargetInfo::new(config, &build_config.requested_target, &rustc, Kind::Target)?;
+    let mut target_infos = Vec::new();
+    for target in options.targets.iter() {
+        let kind = Kind::Target(InternedString::new(target));
+        let info = TargetInfo::new(config, &build_config.requested_targets, &rustc, kind)?;
+        target_infos.push((target, info));
+    }
     {
         let mut fetched_packages = HashSet::new();
         let mut deps_to_fetch = ws.members().map(|p| p.package_id()).collect::<Vec<_>>();
@@ -41,18 +45,19 @@ pub fn fetch<'a>(
                     deps.iter().any(|d| {
                         // If no target was specified then all dependencies can
                         // be fetched.
-                        let target = match options.target {
-                            Some(ref t) => t,
-                            None => return true,
-                        };
+                        if target_infos.is_empty() {
+                            return true;
+                        }
                         // If this dependency is only available for certain
-                        // platforms, make sure we're only fetching it for that
-                        // platform.
+                        // platforms, make sure we're only fetching it for
+                        // those platforms.
                         let platform = match d.platform() {
                             Some(p) => p,
                             None => return true,
                         };
-                        platform.matches(target, target_info.cfg())
+                        target_infos
+                            .iter()
+                            .any(|(target, info)| platform.matches(target, info.cfg()))
                     })
                 })
                 .map(|(id, _deps)| id);
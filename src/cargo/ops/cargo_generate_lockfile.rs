@@ -1,14 +1,20 @@
-use std::collections::{BTreeMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use log::debug;
+use semver::{Version, VersionReq};
 use termcolor::Color::{self, Cyan, Green, Red};
 
+use crate::core::compiler::MessageFormat;
 use crate::core::registry::PackageRegistry;
-use crate::core::resolver::Method;
-use crate::core::PackageId;
+use crate::core::resolver::{Method, ResolveError, ResolveVersion};
+use crate::core::{PackageId, PackageIdSpec, Registry};
 use crate::core::{Resolve, SourceId, Workspace};
 use crate::ops;
 use crate::util::config::Config;
+use crate::util::machine_message::{self, LockfileChange};
+use crate::util::paths;
+use crate::util::toml as cargo_toml;
 use crate::util::CargoResult;
 
 pub struct UpdateOptions<'a> {
@@ -16,13 +22,33 @@ pub struct UpdateOptions<'a> {
     pub to_update: Vec<String>,
     pub precise: Option<&'a str>,
     pub aggressive: bool,
+    /// Whether to change the requirements of the members' dependencies to
+    /// allow the newest incompatible versions.
+    pub breaking: bool,
     pub dry_run: bool,
+    /// Whether to also print the changes to the lock file as JSON messages.
+    pub message_format: MessageFormat,
+    /// The format to write the lock file in, instead of keeping its own.
+    pub lockfile_version: Option<ResolveVersion>,
+    /// The packages to explain the selected versions of, or the failure to
+    /// select them.
+    pub explain: Option<&'a str>,
 }
 
 pub fn generate_lockfile(ws: &Workspace<'_>) -> CargoResult<()> {
+    // A lock file which is regenerated keeps its format.
+    let version = match ops::load_pkg_lockfile(ws) {
+        Ok(Some(resolve)) => resolve.version(),
+        _ => ResolveVersion::default(),
+    };
+    generate_lockfile_with_version(ws, version)
+}
+
+fn generate_lockfile_with_version(ws: &Workspace<'_>, version: ResolveVersion) -> CargoResult<()> {
     let mut registry = PackageRegistry::new(ws.config())?;
-    let resolve =
+    let mut resolve =
         ops::resolve_with_previous(&mut registry, ws, Method::Everything, None, None, &[], true)?;
+    resolve.set_version(version);
     ops::write_pkg_lockfile(ws, &resolve)?;
     Ok(())
 }
@@ -36,18 +62,84 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
         failure::bail!("you can't generate a lockfile for an empty workspace.")
     }
 
+    if opts.breaking && opts.precise.is_some() {
+        failure::bail!("cannot specify both breaking and precise simultaneously")
+    }
+
     if opts.config.cli_unstable().offline {
         failure::bail!("you can't update in the offline mode");
     }
 
-    let previous_resolve = match ops::load_pkg_lockfile(ws)? {
-        Some(resolve) => resolve,
-        None => return generate_lockfile(ws),
+    if opts.explain.is_some() && opts.message_format == MessageFormat::Json {
+        failure::bail!("cannot specify both explain and JSON messages simultaneously")
+    }
+    let explain = match opts.explain {
+        Some(spec) => Some(PackageIdSpec::parse(spec)?),
+        None => None,
+    };
+
+    // Upgrading to incompatible versions changes the members' manifests, so
+    // the workspace is loaded again afterwards.
+    let reloaded;
+    let ws = if opts.breaking {
+        if !upgrade_requirements(ws, opts)? {
+            return Ok(());
+        }
+        if opts.dry_run {
+            opts.config
+                .shell()
+                .warn("not updating manifests or lockfile due to dry run")?;
+            return Ok(());
+        }
+        reloaded = Workspace::new(&ws.root().join("Cargo.toml"), opts.config)?;
+        &reloaded
+    } else {
+        ws
+    };
+
+    let (previous_resolve, ours) = match ops::load_lockfile(ws)? {
+        Some(lockfile) => lockfile.into_parts(),
+        None => {
+            let version = opts.lockfile_version.unwrap_or_default();
+            explain_failure(&explain, generate_lockfile_with_version(ws, version))?;
+            if let (Some(spec), Some(resolve)) = (&explain, ops::load_pkg_lockfile(ws)?) {
+                let mut registry = PackageRegistry::new(opts.config)?;
+                explain_selection(&mut registry, opts, spec, &resolve, &HashSet::new())?;
+            }
+            return Ok(());
+        }
     };
+
+    // Only changing the format of the lock file doesn't update anything.
+    if let (Some(version), true, false) = (
+        opts.lockfile_version,
+        opts.to_update.is_empty(),
+        opts.breaking,
+    ) {
+        let mut resolve = previous_resolve;
+        resolve.set_version(version);
+        if opts.dry_run {
+            opts.config
+                .shell()
+                .warn("not updating lockfile due to dry run")?;
+        } else {
+            ops::write_pkg_lockfile(ws, &resolve)?;
+        }
+        if let Some(spec) = &explain {
+            let mut registry = PackageRegistry::new(opts.config)?;
+            let locked = resolve.iter().collect();
+            explain_selection(&mut registry, opts, spec, &resolve, &locked)?;
+        }
+        return Ok(());
+    }
+
     let mut registry = PackageRegistry::new(opts.config)?;
     let mut to_avoid = HashSet::new();
 
-    if opts.to_update.is_empty() {
+    if opts.breaking {
+        // Only the dependencies whose requirements were upgraded, which no
+        // longer match what's locked, are resolved again.
+    } else if opts.to_update.is_empty() {
         to_avoid.extend(previous_resolve.iter());
     } else {
         let mut sources = Vec::new();
@@ -76,21 +168,316 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
         registry.add_sources(sources)?;
     }
 
-    let resolve = ops::resolve_with_previous(
-        &mut registry,
-        ws,
-        Method::Everything,
-        Some(&previous_resolve),
-        Some(&to_avoid),
-        &[],
-        true,
+    let mut resolve = explain_failure(
+        &explain,
+        ops::resolve_with_previous(
+            &mut registry,
+            ws,
+            Method::Everything,
+            Some(&previous_resolve),
+            Some(&to_avoid),
+            &[],
+            true,
+        ),
     )?;
+    if let Some(version) = opts.lockfile_version {
+        resolve.set_version(version);
+    }
+
+    // Summarize what is changing for the user. The changes made to a lock
+    // file with merge conflicts are shown against our side of the merge.
+    let previous = ours.as_ref().unwrap_or(&previous_resolve);
+    print_lockfile_changes(opts.config, previous, &resolve)?;
+    if opts.message_format == MessageFormat::Json {
+        emit_lockfile_changes(previous, &resolve);
+    }
+    if opts.dry_run {
+        opts.config
+            .shell()
+            .warn("not updating lockfile due to dry run")?;
+    } else {
+        ops::write_pkg_lockfile(ws, &resolve)?;
+    }
+    if let Some(spec) = &explain {
+        let locked = previous_resolve
+            .iter()
+            .filter(|id| !to_avoid.contains(id))
+            .collect();
+        explain_selection(&mut registry, opts, spec, &resolve, &locked)?;
+    }
+    return Ok(());
+
+    fn fill_with_deps<'a>(
+        resolve: &'a Resolve,
+        dep: PackageId,
+        set: &mut HashSet<PackageId>,
+        visited: &mut HashSet<PackageId>,
+    ) {
+        if !visited.insert(dep) {
+            return;
+        }
+        set.insert(dep);
+        for dep in resolve.deps_not_replaced(dep) {
+            fill_with_deps(resolve, dep, set, visited);
+        }
+    }
+}
+
+/// Changes the requirements of the registry dependencies of the workspace
+/// members which don't allow the newest version of the dependency, nor any
+/// version compatible with it, to allow it instead. Only the dependencies
+/// matching the packages to update are changed, if there are any.
+///
+/// Returns whether any requirement was upgraded.
+fn upgrade_requirements(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoResult<bool> {
+    let specs = opts
+        .to_update
+        .iter()
+        .map(|spec| PackageIdSpec::parse(spec))
+        .collect::<CargoResult<Vec<_>>>()?;
+    let mut matched = vec![false; specs.len()];
+
+    let mut registry = PackageRegistry::new(opts.config)?;
+    registry.lock_patches();
+    let mut newest_versions = HashMap::new();
+
+    let mut edits = Vec::new();
+    for member in ws.members() {
+        let path = member.manifest_path();
+        let contents = paths::read(path)?;
+        let mut upgrades = Vec::new();
+        for req in cargo_toml::dependency_requirements(&contents) {
+            let dep = member.dependencies().iter().find(|dep| {
+                dep.kind() == req.kind
+                    && dep.name_in_toml().as_str() == req.name
+                    && dep.source_id().is_registry()
+            });
+            let dep = match dep {
+                Some(dep) => dep,
+                None => continue,
+            };
+            if !specs.is_empty() {
+                match specs
+                    .iter()
+                    .position(|spec| spec.name() == dep.package_name())
+                {
+                    Some(i) => matched[i] = true,
+                    None => continue,
+                }
+            }
+
+            let key = (dep.package_name(), dep.source_id());
+            if !newest_versions.contains_key(&key) {
+                let mut any = dep.clone();
+                any.set_version_req(VersionReq::any());
+                let newest = registry
+                    .query_vec(&any, false)?
+                    .into_iter()
+                    .map(|summary| summary.version().clone())
+                    .filter(|version| !version.is_prerelease())
+                    .max();
+                newest_versions.insert(key, newest);
+            }
+            let newest = match &newest_versions[&key] {
+                Some(newest) => newest,
+                None => continue,
+            };
+
+            if let Some(upgraded) = upgraded_requirement(&req.req, newest) {
+                opts.config.shell().status(
+                    "Upgrading",
+                    format!(
+                        "{} {} -> {} in {}",
+                        req.name,
+                        req.req,
+                        upgraded,
+                        member.name()
+                    ),
+                )?;
+                upgrades.push((req.span, upgraded));
+            }
+        }
+        if !upgrades.is_empty() {
+            edits.push((path, contents, upgrades));
+        }
+    }
+
+    for (spec, matched) in specs.iter().zip(matched) {
+        if !matched {
+            failure::bail!(
+                "package ID specification `{}` did not match any dependencies to upgrade",
+                spec
+            );
+        }
+    }
+
+    if opts.dry_run {
+        return Ok(!edits.is_empty());
+    }
+    let upgraded = !edits.is_empty();
+    for (path, mut contents, upgrades) in edits {
+        // The spans are in order, so replacing from the end keeps the rest
+        // of them valid.
+        for (span, upgraded) in upgrades.into_iter().rev() {
+            contents.replace_range(span, &upgraded);
+        }
+        paths::write(path, contents.as_bytes())?;
+    }
+    Ok(upgraded)
+}
+
+/// Returns a requirement like `req` for `version`, if `req` is a plain one,
+/// such as `1.2` or `^1.2.3`, which `version` isn't compatible with and which
+/// is older than it.
+fn upgraded_requirement(req: &str, version: &Version) -> Option<String> {
+    let req = req.trim();
+    let (op, bare) = match req.chars().next() {
+        Some('^') | Some('~') => req.split_at(1),
+        _ => ("", req),
+    };
+    let parts = bare
+        .trim()
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if parts.len() > 3 {
+        return None;
+    }
+
+    let lowest = Version::new(
+        parts[0],
+        parts.get(1).cloned().unwrap_or(0),
+        parts.get(2).cloned().unwrap_or(0),
+    );
+    let compatible = VersionReq::parse(&format!("^{}", bare.trim())).ok()?;
+    if *version < lowest || compatible.matches(version) {
+        return None;
+    }
+
+    let upgraded = match parts.len() {
+        1 => format!("{}", version.major),
+        2 => format!("{}.{}", version.major, version.minor),
+        _ => format!("{}.{}.{}", version.major, version.minor, version.patch),
+    };
+    Some(format!("{}{}", op, upgraded))
+}
+
+/// Prints the steps which led to a resolution failure, if `--explain` was
+/// given.
+fn explain_failure<T>(explain: &Option<PackageIdSpec>, result: CargoResult<T>) -> CargoResult<T> {
+    if let (Some(_), Err(e)) = (explain, &result) {
+        if let Some(derivation) = e
+            .downcast_ref::<ResolveError>()
+            .and_then(|e| e.derivation())
+        {
+            println!("{}", derivation);
+        }
+    }
+    result
+}
+
+/// Prints why the packages matching `spec` were selected, from the
+/// requirements on them traced back to the workspace members, and why those
+/// versions rather than newer ones. The packages in `locked` were kept at the
+/// versions in the lock file.
+fn explain_selection(
+    registry: &mut PackageRegistry<'_>,
+    opts: &UpdateOptions<'_>,
+    spec: &PackageIdSpec,
+    resolve: &Resolve,
+    locked: &HashSet<PackageId>,
+) -> CargoResult<()> {
+    let mut ids = resolve
+        .iter()
+        .filter(|&id| spec.matches(id))
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
+        failure::bail!("package ID specification `{}` matched no packages", spec);
+    }
+    ids.sort();
 
-    // Summarize what is changing for the user.
+    for (i, &id) in ids.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let mut deps = Vec::new();
+        let mut parents = resolve
+            .iter()
+            .filter_map(|p| {
+                let (_, d) = resolve.deps(p).find(|&(dep, _)| dep == id)?;
+                deps.extend(d.iter().cloned());
+                Some((p, d))
+            })
+            .collect::<Vec<_>>();
+        if parents.is_empty() {
+            println!("`{}` is a member of the workspace.", id);
+            continue;
+        }
+        parents.sort_by_key(|&(p, _)| p);
+
+        println!("`{}` is required by:", id);
+        for (parent, deps) in parents {
+            let mut reqs = deps
+                .iter()
+                .map(|d| format!("`{} = \"{}\"`", d.package_name(), d.version_req()))
+                .collect::<Vec<_>>();
+            reqs.dedup();
+            println!("  `{}`, with {}", parent, reqs.join(" and "));
+            for p in resolve.path_to_top(&parent).iter().skip(1) {
+                println!("      ... which is depended on by `{}`", p);
+            }
+        }
+
+        let precise = opts.precise.is_some()
+            && opts
+                .to_update
+                .iter()
+                .any(|spec| match PackageIdSpec::parse(spec) {
+                    Ok(spec) => spec.name() == id.name(),
+                    Err(_) => false,
+                });
+        if locked.contains(&id) {
+            println!(
+                "It is locked to this version in `Cargo.lock`, \
+                 `cargo update -p {}:{}` would update it.",
+                id.name(),
+                id.version()
+            );
+        } else if precise {
+            println!("It was selected with `--precise`.");
+        } else if let (true, Some(dep)) = (id.source_id().is_registry(), deps.first()) {
+            let mut any = dep.clone();
+            any.set_version_req(VersionReq::any());
+            let newest = registry
+                .query_vec(&any, false)?
+                .into_iter()
+                .map(|summary| summary.version().clone())
+                .filter(|v| deps.iter().all(|d| d.version_req().matches(v)))
+                .max();
+            match newest {
+                Some(ref newest) if newest > id.version() => println!(
+                    "Newer versions matching these requirements, up to {}, \
+                     conflict with other selected packages.",
+                    newest
+                ),
+                _ => println!("It is the newest version matching these requirements."),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the packages which were updated, added and removed between two
+/// resolves, in the same way as `cargo update`.
+pub fn print_lockfile_changes(
+    config: &Config,
+    previous_resolve: &Resolve,
+    resolve: &Resolve,
+) -> CargoResult<()> {
     let print_change = |status: &str, msg: String, color: Color| {
-        opts.config.shell().status_with_color(status, msg, color)
+        config.shell().status_with_color(status, msg, color)
     };
-    for (removed, added) in compare_dependency_graphs(&previous_resolve, &resolve) {
+    for (removed, added) in compare_dependency_graphs(previous_resolve, resolve) {
         if removed.len() == 1 && added.len() == 1 {
             let msg = if removed[0].source_id().is_git() {
                 format!(
@@ -111,101 +498,126 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
             }
         }
     }
-    if opts.dry_run {
-        opts.config
-            .shell()
-            .warn("not updating lockfile due to dry run")?;
-    } else {
-        ops::write_pkg_lockfile(ws, &resolve)?;
-    }
-    return Ok(());
+    Ok(())
+}
 
-    fn fill_with_deps<'a>(
-        resolve: &'a Resolve,
-        dep: PackageId,
-        set: &mut HashSet<PackageId>,
-        visited: &mut HashSet<PackageId>,
-    ) {
-        if !visited.insert(dep) {
-            return;
+/// Prints a JSON message for each package which was added, removed or
+/// changed between two resolves.
+fn emit_lockfile_changes(previous_resolve: &Resolve, resolve: &Resolve) {
+    // Map package names to `(change, old package, new package)`.
+    let mut changes = BTreeMap::new();
+    for (removed, added) in compare_dependency_graphs(previous_resolve, resolve) {
+        let name = match removed.iter().chain(added.iter()).next() {
+            Some(id) => id.name(),
+            None => continue,
+        };
+        let changes = changes.entry(name).or_insert_with(Vec::new);
+        if removed.len() == 1 && added.len() == 1 {
+            let change = match added[0].version().cmp(removed[0].version()) {
+                Ordering::Greater => "upgraded",
+                Ordering::Less => "downgraded",
+                Ordering::Equal => "updated",
+            };
+            changes.push((change, Some(removed[0]), Some(added[0])));
+        } else {
+            changes.extend(removed.iter().map(|&id| ("removed", Some(id), None)));
+            changes.extend(added.iter().map(|&id| ("added", None, Some(id))));
         }
-        set.insert(dep);
-        for dep in resolve.deps_not_replaced(dep) {
-            fill_with_deps(resolve, dep, set, visited);
+    }
+
+    for (name, changes) in changes {
+        // A package which is only removed from one source and added from
+        // another has moved between them.
+        let changes = match changes[..] {
+            [("removed", old, None), ("added", None, new)]
+            | [("added", None, new), ("removed", old, None)] => {
+                vec![("source-changed", old, new)]
+            }
+            _ => changes,
+        };
+        for (change, old, new) in changes {
+            machine_message::emit(&LockfileChange {
+                name: name.as_str(),
+                change,
+                old_version: old.as_ref().map(|id| id.version()),
+                new_version: new.as_ref().map(|id| id.version()),
+                old_source: old.map(|id| id.source_id()),
+                new_source: new.map(|id| id.source_id()),
+            });
         }
     }
+}
 
-    fn compare_dependency_graphs(
-        previous_resolve: &Resolve,
-        resolve: &Resolve,
-    ) -> Vec<(Vec<PackageId>, Vec<PackageId>)> {
-        fn key(dep: PackageId) -> (&'static str, SourceId) {
-            (dep.name().as_str(), dep.source_id())
-        }
-
-        // Removes all package IDs in `b` from `a`. Note that this is somewhat
-        // more complicated because the equality for source IDs does not take
-        // precise versions into account (e.g., git shas), but we want to take
-        // that into account here.
-        fn vec_subtract(a: &[PackageId], b: &[PackageId]) -> Vec<PackageId> {
-            a.iter()
-                .filter(|a| {
-                    // If this package ID is not found in `b`, then it's definitely
-                    // in the subtracted set.
-                    let i = match b.binary_search(a) {
-                        Ok(i) => i,
-                        Err(..) => return true,
-                    };
-
-                    // If we've found `a` in `b`, then we iterate over all instances
-                    // (we know `b` is sorted) and see if they all have different
-                    // precise versions. If so, then `a` isn't actually in `b` so
-                    // we'll let it through.
-                    //
-                    // Note that we only check this for non-registry sources,
-                    // however, as registries contain enough version information in
-                    // the package ID to disambiguate.
-                    if a.source_id().is_registry() {
-                        return false;
-                    }
-                    b[i..]
-                        .iter()
-                        .take_while(|b| a == b)
-                        .all(|b| a.source_id().precise() != b.source_id().precise())
-                })
-                .cloned()
-                .collect()
-        }
-
-        // Map `(package name, package source)` to `(removed versions, added versions)`.
-        let mut changes = BTreeMap::new();
-        let empty = (Vec::new(), Vec::new());
-        for dep in previous_resolve.iter() {
-            changes
-                .entry(key(dep))
-                .or_insert_with(|| empty.clone())
-                .0
-                .push(dep);
-        }
-        for dep in resolve.iter() {
-            changes
-                .entry(key(dep))
-                .or_insert_with(|| empty.clone())
-                .1
-                .push(dep);
-        }
-
-        for v in changes.values_mut() {
-            let (ref mut old, ref mut new) = *v;
-            old.sort();
-            new.sort();
-            let removed = vec_subtract(old, new);
-            let added = vec_subtract(new, old);
-            *old = removed;
-            *new = added;
-        }
-        debug!("{:#?}", changes);
-
-        changes.into_iter().map(|(_, v)| v).collect()
+fn compare_dependency_graphs(
+    previous_resolve: &Resolve,
+    resolve: &Resolve,
+) -> Vec<(Vec<PackageId>, Vec<PackageId>)> {
+    fn key(dep: PackageId) -> (&'static str, SourceId) {
+        (dep.name().as_str(), dep.source_id())
     }
+
+    // Removes all package IDs in `b` from `a`. Note that this is somewhat
+    // more complicated because the equality for source IDs does not take
+    // precise versions into account (e.g., git shas), but we want to take
+    // that into account here.
+    fn vec_subtract(a: &[PackageId], b: &[PackageId]) -> Vec<PackageId> {
+        a.iter()
+            .filter(|a| {
+                // If this package ID is not found in `b`, then it's definitely
+                // in the subtracted set.
+                let i = match b.binary_search(a) {
+                    Ok(i) => i,
+                    Err(..) => return true,
+                };
+
+                // If we've found `a` in `b`, then we iterate over all instances
+                // (we know `b` is sorted) and see if they all have different
+                // precise versions. If so, then `a` isn't actually in `b` so
+                // we'll let it through.
+                //
+                // Note that we only check this for non-registry sources,
+                // however, as registries contain enough version information in
+                // the package ID to disambiguate.
+                if a.source_id().is_registry() {
+                    return false;
+                }
+                b[i..]
+                    .iter()
+                    .take_while(|b| a == b)
+                    .all(|b| a.source_id().precise() != b.source_id().precise())
+            })
+            .cloned()
+            .collect()
+    }
+
+    // Map `(package name, package source)` to `(removed versions, added versions)`.
+    let mut changes = BTreeMap::new();
+    let empty = (Vec::new(), Vec::new());
+    for dep in previous_resolve.iter() {
+        changes
+            .entry(key(dep))
+            .or_insert_with(|| empty.clone())
+            .0
+            .push(dep);
+    }
+    for dep in resolve.iter() {
+        changes
+            .entry(key(dep))
+            .or_insert_with(|| empty.clone())
+            .1
+            .push(dep);
+    }
+
+    for v in changes.values_mut() {
+        let (ref mut old, ref mut new) = *v;
+        old.sort();
+        new.sort();
+        let removed = vec_subtract(old, new);
+        let added = vec_subtract(new, old);
+        *old = removed;
+        *new = added;
+    }
+    debug!("{:#?}", changes);
+
+    changes.into_iter().map(|(_, v)| v).collect()
 }
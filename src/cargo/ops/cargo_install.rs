@@ -258,8 +258,8 @@ fn install_one(
     let rustc = config.load_global_rustc(Some(&ws))?;
     let target = opts
         .build_config
-        .requested_target
-        .as_ref()
+        .requested_targets
+        .first()
         .unwrap_or(&rustc.host)
         .clone();
 
@@ -323,7 +323,7 @@ fn install_one(
     let mut binaries: Vec<(&str, &Path)> = compile
         .binaries
         .iter()
-        .map(|bin| {
+        .map(|(_, bin)| {
             let name = bin.file_name().unwrap();
             if let Some(s) = name.to_str() {
                 Ok((s, bin.as_ref()))
@@ -0,0 +1,208 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use semver::{Version, VersionReq};
+
+use crate::core::compiler::MessageFormat;
+use crate::core::{Dependency, PackageId, Source, Workspace};
+use crate::ops::{self, Packages};
+use crate::sources::SourceConfigMap;
+use crate::util::machine_message::{self, OutdatedDependency};
+use crate::util::{CargoResult, Config};
+
+pub struct OutdatedOptions<'a> {
+    pub config: &'a Config,
+    /// The packages whose dependencies are checked.
+    pub packages: Packages,
+    /// How deep in the dependency graph to look, where 1 is only the direct
+    /// dependencies of the packages, or everything if `None`.
+    pub depth: Option<u32>,
+    pub message_format: MessageFormat,
+}
+
+/// A dependency which has newer versions in its registry, or whose locked
+/// version was yanked.
+struct Outdated<'a> {
+    id: PackageId,
+    depth: u32,
+    yanked: bool,
+    compatible: Option<&'a Version>,
+    latest: Option<&'a Version>,
+}
+
+/// Reports the registry dependencies of the packages which are behind the
+/// newest versions in their registry, along with the newest versions allowed
+/// by the requirements on them, and those whose locked version was yanked.
+///
+/// Returns how many dependencies were reported.
+pub fn outdated(ws: &Workspace<'_>, opts: &OutdatedOptions<'_>) -> CargoResult<usize> {
+    let (packages, resolve) = ops::resolve_ws(ws)?;
+
+    // Find how far each dependency is from the packages, looking no deeper
+    // than asked.
+    let mut depths = BTreeMap::new();
+    let mut queue = opts
+        .packages
+        .get_packages(ws)?
+        .iter()
+        .map(|pkg| (pkg.package_id(), 0))
+        .collect::<VecDeque<_>>();
+    while let Some((id, depth)) = queue.pop_front() {
+        if depths.contains_key(&id) {
+            continue;
+        }
+        depths.insert(id, depth);
+        if opts.depth.map_or(true, |max| depth < max) {
+            queue.extend(resolve.deps(id).map(|(dep, _)| (dep, depth + 1)));
+        }
+    }
+
+    // The sources are loaded anew, as the ones the packages were resolved
+    // from don't update a registry's index when everything is locked. The
+    // locked versions are queried even if they were yanked, to find what
+    // they depend on.
+    let locked = resolve.iter().collect::<HashSet<_>>();
+    let map = SourceConfigMap::new(opts.config)?;
+    let mut sources = HashMap::new();
+    let mut versions = BTreeMap::new();
+    let mut yanked = HashSet::new();
+    let mut summaries = HashMap::new();
+    for id in resolve.iter() {
+        if !id.source_id().is_registry() {
+            continue;
+        }
+        let source_id = id.source_id().with_precise(None);
+        let source = match sources.entry(source_id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let mut source = map.load(source_id, &locked)?;
+                source.update()?;
+                e.insert(source)
+            }
+        };
+        let dep = Dependency::parse_no_deprecated(&id.name(), None, source_id)?;
+        let mut available = Vec::new();
+        for summary in source.query_vec(&dep)? {
+            if summary.version() == id.version() {
+                summaries.insert(id, summary.clone());
+            }
+            let summary_id = summary.package_id();
+            if !(locked.contains(&summary_id) && source.is_yanked(summary_id)?) {
+                available.push(summary.version().clone());
+            }
+        }
+        available.sort();
+        if source.is_yanked(id)? {
+            yanked.insert(id);
+        }
+        if depths.get(&id).map_or(false, |&depth| depth > 0) {
+            versions.insert(id, available);
+        }
+    }
+
+    // Every requirement on a package limits which versions it could be
+    // updated to, not only those of the packages checked. The requirements
+    // in the resolve are locked, so they're taken from the manifests.
+    let mut reqs = HashMap::new();
+    for id in resolve.iter() {
+        let summary = match summaries.get(&id) {
+            Some(summary) => summary.clone(),
+            None => packages.get_one(id)?.summary().clone(),
+        };
+        for (dep, _) in resolve.deps(id) {
+            reqs.entry(dep).or_insert_with(Vec::new).extend(
+                summary
+                    .dependencies()
+                    .iter()
+                    .filter(|d| d.package_name() == dep.name())
+                    .filter(|d| d.version_req().matches(dep.version()))
+                    .map(|d| d.version_req().clone()),
+            );
+        }
+    }
+
+    let mut outdated = Vec::new();
+    for (&id, available) in versions.iter() {
+        let reqs: &[VersionReq] = reqs.get(&id).map_or(&[], |reqs| reqs);
+        let compatible = available
+            .iter()
+            .filter(|v| reqs.iter().all(|req| req.matches(v)))
+            .max();
+        let latest = available
+            .iter()
+            .filter(|v| !v.is_prerelease())
+            .max()
+            .or_else(|| available.last());
+        let newer = |v: Option<&Version>| v.map_or(false, |v| v > id.version());
+        if newer(compatible) || newer(latest) || yanked.contains(&id) {
+            outdated.push(Outdated {
+                id,
+                depth: depths[&id],
+                yanked: yanked.contains(&id),
+                compatible,
+                latest,
+            });
+        }
+    }
+
+    if opts.message_format == MessageFormat::Json {
+        for dep in outdated.iter() {
+            machine_message::emit(&OutdatedDependency {
+                package_id: dep.id,
+                name: dep.id.name().as_str(),
+                depth: dep.depth,
+                locked_version: dep.id.version(),
+                yanked: dep.yanked,
+                compatible_version: dep.compatible,
+                latest_version: dep.latest,
+            });
+        }
+    } else if outdated.is_empty() {
+        println!("All dependencies are up to date.");
+    } else {
+        print_table(&outdated);
+    }
+    Ok(outdated.len())
+}
+
+fn print_table(outdated: &[Outdated<'_>]) {
+    let newer = |id: PackageId, v: Option<&Version>| match v {
+        Some(v) if v > id.version() => v.to_string(),
+        _ => "-".to_string(),
+    };
+    let mut rows = vec![[
+        "Name".to_string(),
+        "Locked".to_string(),
+        "Compatible".to_string(),
+        "Latest".to_string(),
+    ]];
+    for dep in outdated {
+        let locked = if dep.yanked {
+            format!("{} (yanked)", dep.id.version())
+        } else {
+            dep.id.version().to_string()
+        };
+        rows.push([
+            dep.id.name().to_string(),
+            locked,
+            newer(dep.id, dep.compatible),
+            newer(dep.id, dep.latest),
+        ]);
+    }
+
+    let mut widths = [0; 4];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in rows.iter() {
+        let line = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
@@ -1,4 +1,5 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::SeekFrom;
@@ -10,21 +11,23 @@ use flate2::read::GzDecoder;
 use flate2::{Compression, GzBuilder};
 use log::debug;
 use serde_json::{self, json};
-use tar::{Archive, Builder, EntryType, Header};
+use tar::{Archive, Builder, EntryType, Header, HeaderMode};
 use termcolor::Color;
 
-use crate::core::compiler::{BuildConfig, CompileMode, DefaultExecutor, Executor};
+use crate::core::compiler::{BuildConfig, CompileMode, DefaultExecutor, Executor, MessageFormat};
+use crate::core::dependency::Kind;
 use crate::core::resolver::Method;
-use crate::core::Feature;
 use crate::core::{
     Package, PackageId, PackageIdSpec, PackageSet, Resolve, Source, SourceId, Verbosity, Workspace,
 };
 use crate::ops;
-use crate::sources::PathSource;
+use crate::sources::path::{FileSource, ListedFile};
+use crate::sources::{registry, PathSource};
 use crate::util::errors::{CargoResult, CargoResultExt};
+use crate::util::graph::Graph;
 use crate::util::paths;
 use crate::util::toml::TomlManifest;
-use crate::util::{self, internal, Config, FileLock};
+use crate::util::{self, internal, machine_message, Config, FileLock, Sha256};
 
 pub struct PackageOpts<'cfg> {
     pub config: &'cfg Config,
@@ -32,19 +35,261 @@ pub struct PackageOpts<'cfg> {
     pub check_metadata: bool,
     pub allow_dirty: bool,
     pub verify: bool,
+    pub verify_reproducible: bool,
     pub jobs: Option<u32>,
     pub target: Option<String>,
     pub features: Vec<String>,
     pub all_features: bool,
     pub no_default_features: bool,
+    pub to_package: ops::Packages,
+    pub message_format: MessageFormat,
 }
 
 static VCS_INFO_FILE: &'static str = ".cargo_vcs_info.json";
 
-pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option<FileLock>> {
+pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option<Vec<FileLock>>> {
+    let pkgs = match opts.to_package {
+        ops::Packages::Default => vec![ws.current()?],
+        ref to_package => to_package.get_packages(ws)?,
+    };
+    if pkgs.is_empty() {
+        failure::bail!("no packages to package")
+    }
+    let pkgs = sort_members(&pkgs);
+
+    if opts.list {
+        // Make sure the Cargo.lock is up-to-date and valid.
+        ops::resolve_ws(ws)?;
+        // With several packages, paths are shown relative to the workspace
+        // root so that it's clear which package each file belongs to.
+        let prefix = if pkgs.len() > 1 {
+            ws.root()
+        } else {
+            pkgs[0].root()
+        };
+        for pkg in pkgs {
+            list_files(ws, pkg, prefix, opts)?;
+        }
+        return Ok(None);
+    }
+
+    package_many(ws, &pkgs, opts).map(Some)
+}
+
+/// Prints the files which would be included in the package of `pkg`, either
+/// as paths relative to `prefix` or as JSON messages.
+fn list_files(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    prefix: &Path,
+    opts: &PackageOpts<'_>,
+) -> CargoResult<()> {
+    let (src_files, vcs_info) = prepare_package(ws, pkg, opts)?;
+    let root = pkg.root();
+
+    if opts.message_format != MessageFormat::Json {
+        let mut list: Vec<_> = src_files
+            .iter()
+            .filter(|file| file.included)
+            .map(|file| file.path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        if pkg.include_lockfile() && !list.contains(&PathBuf::from("Cargo.lock")) {
+            // A generated Cargo.lock will be included.
+            list.push("Cargo.lock".into());
+        }
+        if vcs_info.is_some() {
+            list.push(Path::new(VCS_INFO_FILE).to_path_buf());
+        }
+        list.sort_unstable();
+        let base = root.strip_prefix(prefix).unwrap_or(root);
+        for file in list.iter() {
+            println!("{}", base.join(file).display());
+        }
+        return Ok(());
+    }
+
+    let mut messages = BTreeMap::new();
+    for file in src_files.iter() {
+        let relative = file.path.strip_prefix(root).unwrap();
+        if relative == Path::new("Cargo.lock") {
+            // This is generated anew below.
+            continue;
+        }
+        let source = match file.source {
+            FileSource::Vcs => "vcs",
+            FileSource::Walk => "walk",
+        };
+        let size = fs::metadata(&file.path).ok().map(|m| m.len());
+        let rule = file.rule.as_ref().map(|s| s.as_str());
+        if relative == Path::new("Cargo.toml") {
+            let toml = pkg.to_registry_toml(ws.config())?;
+            messages.insert(
+                PathBuf::from("Cargo.toml"),
+                ("generated", Some(toml.len() as u64), true, rule),
+            );
+            messages.insert(PathBuf::from("Cargo.toml.orig"), (source, size, true, rule));
+        } else {
+            messages.insert(relative.to_path_buf(), (source, size, file.included, rule));
+        }
+    }
+    if pkg.include_lockfile() {
+        // The lock file is only generated when actually packaging, so its
+        // size isn't known yet.
+        messages.insert(PathBuf::from("Cargo.lock"), ("generated", None, true, None));
+    }
+    if let Some(json) = vcs_info {
+        let size = format!("{}\n", serde_json::to_string_pretty(&json)?).len() as u64;
+        messages.insert(
+            PathBuf::from(VCS_INFO_FILE),
+            ("generated", Some(size), true, None),
+        );
+    }
+    for (path, (source, size, included, rule)) in messages.iter() {
+        machine_message::emit(&machine_message::PackageFile {
+            package_id: pkg.package_id(),
+            path,
+            source,
+            size: *size,
+            included: *included,
+            rule: *rule,
+        });
+    }
+    Ok(())
+}
+
+/// Packages each of `pkgs`, which must be members of `ws` sorted so that
+/// every package comes after the members it depends on (see `sort_members`).
+///
+/// All tarballs are created before any of them is verified. If some of the
+/// packages depend on each other, the tarballs are also gathered into a
+/// temporary local registry which is overlaid on the registries they will be
+/// published to, so that verification resolves those dependencies to the
+/// versions just packaged rather than to whatever is already published.
+///
+/// The returned tarballs are in the same order as `pkgs`.
+pub fn package_many(
+    ws: &Workspace<'_>,
+    pkgs: &[&Package],
+    opts: &PackageOpts<'_>,
+) -> CargoResult<Vec<FileLock>> {
     // Make sure the Cargo.lock is up-to-date and valid.
     ops::resolve_ws(ws)?;
-    let pkg = ws.current()?;
+    let config = ws.config();
+
+    let mut tmp_registry = TmpRegistry::new(ws, pkgs)?;
+
+    let mut tarballs = Vec::new();
+    for &pkg in pkgs {
+        let (src_files, vcs_info) = prepare_package(ws, pkg, opts)?;
+        let src_files = src_files
+            .into_iter()
+            .filter(|file| file.included)
+            .map(|file| file.path)
+            .collect::<Vec<_>>();
+
+        let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
+        let dir = ws.target_dir().join("package");
+        let mut dst = {
+            let tmp = format!(".{}", filename);
+            dir.open_rw(&tmp, config, "package scratch space")?
+        };
+
+        // Package up and test a temporary tarball and only move it to the final
+        // location if it actually passes all our tests. Any previously existing
+        // tarball can be assumed as corrupt or invalid, so we just blow it away if
+        // it exists.
+        config
+            .shell()
+            .status("Packaging", pkg.package_id().to_string())?;
+        dst.file().set_len(0)?;
+        tar(
+            ws,
+            pkg,
+            &src_files,
+            vcs_info.as_ref(),
+            dst.file(),
+            &filename,
+            tmp_registry.as_ref(),
+        )
+        .chain_err(|| failure::format_err!("failed to prepare local package for uploading"))?;
+        if opts.verify_reproducible {
+            check_reproducible(
+                ws,
+                pkg,
+                &src_files,
+                vcs_info.as_ref(),
+                &mut dst,
+                &filename,
+                tmp_registry.as_ref(),
+            )
+            .chain_err(|| {
+                failure::format_err!(
+                    "failed to verify that `{}` is reproducible",
+                    pkg.package_id()
+                )
+            })?;
+        }
+        if let Some(tmp_registry) = tmp_registry.as_mut() {
+            tmp_registry.add_package(pkg, &mut dst)?;
+        }
+        tarballs.push(dst);
+    }
+
+    if opts.verify {
+        for (&pkg, dst) in pkgs.iter().zip(tarballs.iter_mut()) {
+            dst.seek(SeekFrom::Start(0))?;
+            run_verify(ws, pkg, dst, opts, tmp_registry.as_ref())
+                .chain_err(|| "failed to verify package tarball")?
+        }
+    }
+
+    for (&pkg, dst) in pkgs.iter().zip(tarballs.iter_mut()) {
+        dst.seek(SeekFrom::Start(0))?;
+        let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
+        let src_path = dst.path();
+        let dst_path = dst.parent().join(&filename);
+        fs::rename(&src_path, &dst_path)
+            .chain_err(|| "failed to move temporary tarball into final location")?;
+    }
+    Ok(tarballs)
+}
+
+/// Sorts `pkgs` so that each package comes after the packages of `pkgs` it
+/// has a normal or build dependency on, which is the order they have to be
+/// published in.
+///
+/// Dev-dependencies are not taken into account as they are allowed to form
+/// cycles. Packages which don't depend on each other are sorted by name.
+pub fn sort_members<'a>(pkgs: &[&'a Package]) -> Vec<&'a Package> {
+    let mut graph = Graph::<PackageId, ()>::new();
+    for pkg in pkgs {
+        graph.add(pkg.package_id());
+        for dep in pkg.dependencies() {
+            if dep.kind() == Kind::Development {
+                continue;
+            }
+            for other in pkgs {
+                if other.package_id() != pkg.package_id() && dep.matches_id(other.package_id()) {
+                    graph.link(pkg.package_id(), other.package_id());
+                }
+            }
+        }
+    }
+    graph
+        .sort()
+        .into_iter()
+        .map(|id| *pkgs.iter().find(|p| p.package_id() == id).unwrap())
+        .collect()
+}
+
+/// Runs the checks needed before `pkg` can be packaged, returning the listed
+/// files (see `PathSource::list_files_detailed`) and the VCS information to
+/// record, if any.
+fn prepare_package(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    opts: &PackageOpts<'_>,
+) -> CargoResult<(Vec<ListedFile>, Option<serde_json::Value>)> {
     let config = ws.config();
 
     let mut src = PathSource::new(pkg.root(), pkg.package_id().source_id(), config);
@@ -57,7 +302,14 @@ pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option
     verify_dependencies(pkg)?;
 
     // `list_files` outputs warnings as a side effect, so only do it once.
-    let src_files = src.list_files(pkg)?;
+    let listed = src.list_files_detailed(pkg)?;
+    let src_files = listed
+        .iter()
+        .filter(|file| file.included)
+        .map(|file| file.path.clone())
+        .collect::<Vec<_>>();
+
+    check_listed_files(pkg, &listed, config)?;
 
     // Make sure a VCS info file is not included in source, regardless of if
     // we produced the file above, and in particular if we did not.
@@ -73,65 +325,201 @@ pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option
         None
     };
 
-    if opts.list {
-        let root = pkg.root();
-        let mut list: Vec<_> = src
-            .list_files(pkg)?
-            .iter()
-            .map(|file| file.strip_prefix(root).unwrap().to_path_buf())
-            .collect();
-        if pkg.include_lockfile() && !list.contains(&PathBuf::from("Cargo.lock")) {
-            // A generated Cargo.lock will be included.
-            list.push("Cargo.lock".into());
+    Ok((listed, vcs_info))
+}
+
+/// Warns about files which are probably not meant to be published: large
+/// ones, and ones which are ignored by git but still included because an
+/// `include` list is used.
+fn check_listed_files(pkg: &Package, files: &[ListedFile], config: &Config) -> CargoResult<()> {
+    const LARGE_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+    // Only consider a repository the package is actually part of, like
+    // `check_repo_state` does.
+    let repo = git2::Repository::discover(pkg.root()).ok().filter(|repo| {
+        let workdir = match repo.workdir() {
+            Some(workdir) => workdir,
+            None => return false,
+        };
+        let path = pkg.manifest_path();
+        let path = path.strip_prefix(workdir).unwrap_or(path);
+        repo.status_file(path)
+            .map(|status| (status & git2::Status::IGNORED).is_empty())
+            .unwrap_or(false)
+    });
+    let workdir = repo.as_ref().and_then(|repo| repo.workdir());
+    for file in files.iter().filter(|file| file.included) {
+        let relative = file.path.strip_prefix(pkg.root()).unwrap_or(&file.path);
+        if let Ok(metadata) = fs::metadata(&file.path) {
+            if metadata.len() > LARGE_FILE_SIZE {
+                config.shell().warn(format!(
+                    "file `{}` is {:.1} MiB large, consider excluding it from the package",
+                    relative.display(),
+                    metadata.len() as f64 / (1024.0 * 1024.0)
+                ))?;
+            }
         }
-        if vcs_info.is_some() {
-            list.push(Path::new(VCS_INFO_FILE).to_path_buf());
+        // Files listed through git can't be ignored by it.
+        if file.source == FileSource::Walk {
+            if let (Some(repo), Some(workdir)) = (repo.as_ref(), workdir) {
+                let in_repo = file.path.strip_prefix(workdir);
+                if in_repo.map_or(false, |p| repo.is_path_ignored(p).unwrap_or(false)) {
+                    config.shell().warn(format!(
+                        "file `{}` is included in the package but ignored by git",
+                        relative.display()
+                    ))?;
+                }
+            }
         }
-        list.sort_unstable();
-        for file in list.iter() {
-            println!("{}", file.display());
+    }
+    Ok(())
+}
+
+/// A local registry holding the tarballs packaged so far, used as an overlay
+/// when packaging workspace members which depend on each other.
+struct TmpRegistry<'cfg> {
+    config: &'cfg Config,
+    root: PathBuf,
+    // The registries the packaged members will be fetched from by the
+    // packages depending on them.
+    overlaid: Vec<SourceId>,
+}
+
+impl<'cfg> TmpRegistry<'cfg> {
+    /// Creates an empty registry in the target directory, unless none of
+    /// `pkgs` depends on another one, in which case `None` is returned.
+    fn new(ws: &Workspace<'cfg>, pkgs: &[&Package]) -> CargoResult<Option<TmpRegistry<'cfg>>> {
+        let config = ws.config();
+        let mut overlaid = Vec::new();
+        for pkg in pkgs {
+            for dep in pkg.dependencies() {
+                if !pkgs.iter().any(|p| dep.matches_id(p.package_id())) {
+                    continue;
+                }
+                let id = match dep.registry_id() {
+                    Some(id) => id,
+                    None => SourceId::crates_io(config)?,
+                };
+                if !overlaid.contains(&id) {
+                    overlaid.push(id);
+                }
+            }
         }
-        return Ok(None);
+        if overlaid.is_empty() {
+            return Ok(None);
+        }
+
+        let root = ws
+            .target_dir()
+            .join("package")
+            .join("tmp-registry")
+            .into_path_unlocked();
+        if root.exists() {
+            paths::remove_dir_all(&root)?;
+        }
+        fs::create_dir_all(root.join("index"))?;
+        Ok(Some(TmpRegistry {
+            config,
+            root,
+            overlaid,
+        }))
     }
 
-    let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
-    let dir = ws.target_dir().join("package");
-    let mut dst = {
-        let tmp = format!(".{}", filename);
-        dir.open_rw(&tmp, config, "package scratch space")?
-    };
+    /// Adds the freshly created tarball of `pkg` to the registry.
+    fn add_package(&mut self, pkg: &Package, tarball: &mut FileLock) -> CargoResult<()> {
+        let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
+        let mut contents = Vec::new();
+        tarball.seek(SeekFrom::Start(0))?;
+        tarball.read_to_end(&mut contents)?;
+        paths::write(&self.root.join(&filename), &contents)?;
+        let mut cksum = Sha256::new();
+        cksum.update(&contents);
+        let cksum = hex::encode(cksum.finish());
 
-    // Package up and test a temporary tarball and only move it to the final
-    // location if it actually passes all our tests. Any previously existing
-    // tarball can be assumed as corrupt or invalid, so we just blow it away if
-    // it exists.
-    config
-        .shell()
-        .status("Packaging", pkg.package_id().to_string())?;
-    dst.file().set_len(0)?;
-    tar(ws, &src_files, vcs_info.as_ref(), dst.file(), &filename)
-        .chain_err(|| failure::format_err!("failed to prepare local package for uploading"))?;
-    if opts.verify {
-        dst.seek(SeekFrom::Start(0))?;
-        run_verify(ws, &dst, opts).chain_err(|| "failed to verify package tarball")?
+        let crates_io = SourceId::crates_io(self.config)?;
+        let deps = pkg
+            .dependencies()
+            .iter()
+            .map(|dep| {
+                let registry = dep.registry_id().unwrap_or(crates_io);
+                let (name, package) = match dep.explicit_name_in_toml() {
+                    Some(rename) => (rename.to_string(), Some(dep.package_name().to_string())),
+                    None => (dep.package_name().to_string(), None),
+                };
+                json!({
+                    "name": name,
+                    "req": dep.version_req().to_string(),
+                    "features": dep.features().iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+                    "optional": dep.is_optional(),
+                    "default_features": dep.uses_default_features(),
+                    "target": dep.platform().map(|s| s.to_string()),
+                    "kind": match dep.kind() {
+                        Kind::Normal => "normal",
+                        Kind::Build => "build",
+                        Kind::Development => "dev",
+                    },
+                    "registry": registry.url().to_string(),
+                    "package": package,
+                    "public": dep.is_public(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let summary = pkg.summary();
+        let features = summary
+            .features()
+            .iter()
+            .map(|(feat, values)| {
+                (
+                    feat.to_string(),
+                    values
+                        .iter()
+                        .map(|fv| fv.to_string(summary))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        let line = json!({
+            "name": pkg.name().as_str(),
+            "vers": pkg.version().to_string(),
+            "deps": deps,
+            "features": features,
+            "cksum": cksum,
+            "yanked": false,
+            "links": pkg.manifest().links(),
+        });
+
+        let index_file = self
+            .root
+            .join("index")
+            .join(registry::make_dep_path(pkg.name().as_str()));
+        fs::create_dir_all(index_file.parent().unwrap())?;
+        let mut index_file = fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&index_file)?;
+        writeln!(index_file, "{}", serde_json::to_string(&line)?)?;
+        Ok(())
     }
-    dst.seek(SeekFrom::Start(0))?;
-    {
-        let src_path = dst.path();
-        let dst_path = dst.parent().join(&filename);
-        fs::rename(&src_path, &dst_path)
-            .chain_err(|| "failed to move temporary tarball into final location")?;
+
+    /// Layers this registry on top of the registries it stands in for when
+    /// `ws` is resolved.
+    fn overlay(&self, ws: &mut Workspace<'_>) {
+        for &id in self.overlaid.iter() {
+            ws.add_local_overlay(id, &self.root);
+        }
     }
-    Ok(Some(dst))
 }
 
 /// Construct `Cargo.lock` for the package to be published.
-fn build_lock(ws: &Workspace<'_>) -> CargoResult<String> {
+fn build_lock(
+    ws: &Workspace<'_>,
+    orig_pkg: &Package,
+    tmp_registry: Option<&TmpRegistry<'_>>,
+) -> CargoResult<String> {
     let config = ws.config();
     let orig_resolve = ops::load_pkg_lockfile(ws)?;
 
     // Convert Package -> TomlManifest -> Manifest -> Package
-    let orig_pkg = ws.current()?;
     let toml_manifest = Rc::new(orig_pkg.manifest().original().prepare_for_publish(config)?);
     let package_root = orig_pkg.root();
     let source_id = orig_pkg.package_id().source_id();
@@ -141,7 +529,10 @@ fn build_lock(ws: &Workspace<'_>) -> CargoResult<String> {
 
     // Regenerate Cargo.lock using the old one as a guide.
     let specs = vec![PackageIdSpec::from_package_id(new_pkg.package_id())];
-    let tmp_ws = Workspace::ephemeral(new_pkg, ws.config(), None, true)?;
+    let mut tmp_ws = Workspace::ephemeral(new_pkg, ws.config(), None, true)?;
+    if let Some(tmp_registry) = tmp_registry {
+        tmp_registry.overlay(&mut tmp_ws);
+    }
     let (pkg_set, new_resolve) = ops::resolve_ws_with_method(&tmp_ws, Method::Everything, &specs)?;
 
     if let Some(orig_resolve) = orig_resolve {
@@ -309,25 +700,30 @@ fn check_vcs_file_collision(pkg: &Package, src_files: &[PathBuf]) -> CargoResult
     Ok(())
 }
 
+/// The contents of a file to put into a `.crate` tarball.
+enum ArchiveFile {
+    /// A file of the package, read from disk.
+    OnDisk(PathBuf),
+    /// A file generated while packaging, such as the normalized manifest.
+    Generated(String),
+}
+
 fn tar(
     ws: &Workspace<'_>,
+    pkg: &Package,
     src_files: &[PathBuf],
     vcs_info: Option<&serde_json::Value>,
     dst: &File,
     filename: &str,
+    tmp_registry: Option<&TmpRegistry<'_>>,
 ) -> CargoResult<()> {
-    // Prepare the encoder and its header.
-    let filename = Path::new(filename);
-    let encoder = GzBuilder::new()
-        .filename(util::path2bytes(filename)?)
-        .write(dst, Compression::best());
-
-    // Put all package files into a compressed archive.
-    let mut ar = Builder::new(encoder);
-    let pkg = ws.current()?;
     let config = ws.config();
     let root = pkg.root();
+    let mtime = archive_mtime()?;
 
+    // Gather everything up front so that entries are archived in a stable
+    // order, regardless of the order in which the files were discovered.
+    let mut files = BTreeMap::new();
     for src_file in src_files {
         let relative = src_file.strip_prefix(root)?;
         check_filename(relative)?;
@@ -338,9 +734,51 @@ fn tar(
             // This is added manually below.
             continue;
         }
-        config
-            .shell()
-            .verbose(|shell| shell.status("Archiving", &relative_str))?;
+        if relative_str == "Cargo.toml" {
+            files.insert(
+                "Cargo.toml.orig".to_string(),
+                ArchiveFile::OnDisk(src_file.clone()),
+            );
+            files.insert(
+                relative_str.to_string(),
+                ArchiveFile::Generated(pkg.to_registry_toml(config)?),
+            );
+        } else {
+            files.insert(
+                relative_str.to_string(),
+                ArchiveFile::OnDisk(src_file.clone()),
+            );
+        }
+    }
+
+    if let Some(json) = vcs_info {
+        debug_assert!(check_filename(Path::new(VCS_INFO_FILE)).is_ok());
+        let json = format!("{}\n", serde_json::to_string_pretty(json)?);
+        files.insert(VCS_INFO_FILE.to_string(), ArchiveFile::Generated(json));
+    }
+
+    if pkg.include_lockfile() {
+        let new_lock = build_lock(ws, pkg, tmp_registry)?;
+        files.insert("Cargo.lock".to_string(), ArchiveFile::Generated(new_lock));
+    }
+
+    // Prepare the encoder and its header. The gzip header only records the
+    // name of the tarball, its timestamp is left unset.
+    let filename = Path::new(filename);
+    let encoder = GzBuilder::new()
+        .filename(util::path2bytes(filename)?)
+        .mtime(0)
+        .write(dst, Compression::best());
+
+    // Put all package files into a compressed archive.
+    let mut ar = Builder::new(encoder);
+
+    for (relative_str, file) in files.iter() {
+        if relative_str != "Cargo.toml.orig" {
+            config
+                .shell()
+                .verbose(|shell| shell.status("Archiving", relative_str))?;
+        }
         let path = format!(
             "{}-{}{}{}",
             pkg.name(),
@@ -371,93 +809,141 @@ fn tar(
         header
             .set_path(&path)
             .chain_err(|| format!("failed to add to archive: `{}`", relative_str))?;
-        let mut file = File::open(src_file)
-            .chain_err(|| format!("failed to open for archiving: `{}`", src_file.display()))?;
-        let metadata = file
-            .metadata()
-            .chain_err(|| format!("could not learn metadata for: `{}`", relative_str))?;
-        header.set_metadata(&metadata);
 
-        if relative_str == "Cargo.toml" {
-            let orig = Path::new(&path).with_file_name("Cargo.toml.orig");
-            header.set_path(&orig)?;
-            header.set_cksum();
-            ar.append(&header, &mut file).chain_err(|| {
-                internal(format!("could not archive source file `{}`", relative_str))
-            })?;
-
-            let mut header = Header::new_ustar();
-            let toml = pkg.to_registry_toml(ws.config())?;
-            header.set_path(&path)?;
-            header.set_entry_type(EntryType::file());
-            header.set_mode(0o644);
-            header.set_size(toml.len() as u64);
-            header.set_cksum();
-            ar.append(&header, toml.as_bytes()).chain_err(|| {
-                internal(format!("could not archive source file `{}`", relative_str))
-            })?;
-        } else {
-            header.set_cksum();
-            ar.append(&header, &mut file).chain_err(|| {
-                internal(format!("could not archive source file `{}`", relative_str))
-            })?;
+        // Only the size and whether a file is executable are taken from the
+        // file system, ownership and timestamps are normalized so that
+        // packaging the same sources always yields the same tarball.
+        match *file {
+            ArchiveFile::OnDisk(ref src_file) => {
+                let mut file = File::open(src_file).chain_err(|| {
+                    format!("failed to open for archiving: `{}`", src_file.display())
+                })?;
+                let metadata = file
+                    .metadata()
+                    .chain_err(|| format!("could not learn metadata for: `{}`", relative_str))?;
+                header.set_metadata_in_mode(&metadata, HeaderMode::Deterministic);
+                header.set_mtime(mtime);
+                header.set_cksum();
+                ar.append(&header, &mut file).chain_err(|| {
+                    internal(format!("could not archive source file `{}`", relative_str))
+                })?;
+            }
+            ArchiveFile::Generated(ref contents) => {
+                header.set_entry_type(EntryType::file());
+                header.set_mode(0o644);
+                header.set_uid(0);
+                header.set_gid(0);
+                header.set_mtime(mtime);
+                header.set_size(contents.len() as u64);
+                header.set_cksum();
+                ar.append(&header, contents.as_bytes()).chain_err(|| {
+                    internal(format!("could not archive source file `{}`", relative_str))
+                })?;
+            }
         }
     }
 
-    if let Some(json) = vcs_info {
-        let filename: PathBuf = Path::new(VCS_INFO_FILE).into();
-        debug_assert!(check_filename(&filename).is_ok());
-        let fnd = filename.display();
-        config
-            .shell()
-            .verbose(|shell| shell.status("Archiving", &fnd))?;
-        let path = format!(
-            "{}-{}{}{}",
-            pkg.name(),
-            pkg.version(),
-            path::MAIN_SEPARATOR,
-            fnd
-        );
-        let mut header = Header::new_ustar();
-        header
-            .set_path(&path)
-            .chain_err(|| format!("failed to add to archive: `{}`", fnd))?;
-        let json = format!("{}\n", serde_json::to_string_pretty(json)?);
-        let mut header = Header::new_ustar();
-        header.set_path(&path)?;
-        header.set_entry_type(EntryType::file());
-        header.set_mode(0o644);
-        header.set_size(json.len() as u64);
-        header.set_cksum();
-        ar.append(&header, json.as_bytes())
-            .chain_err(|| internal(format!("could not archive source file `{}`", fnd)))?;
+    let encoder = ar.into_inner()?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Packages `pkg` a second time next to `tarball` and checks that the result
+/// is identical to it.
+fn check_reproducible(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    src_files: &[PathBuf],
+    vcs_info: Option<&serde_json::Value>,
+    tarball: &mut FileLock,
+    filename: &str,
+    tmp_registry: Option<&TmpRegistry<'_>>,
+) -> CargoResult<()> {
+    let dir = ws.target_dir().join("package");
+    let tmp = format!(".{}.reproducible", filename);
+    let mut again = dir.open_rw(&tmp, ws.config(), "package scratch space")?;
+    again.file().set_len(0)?;
+    tar(
+        ws,
+        pkg,
+        src_files,
+        vcs_info,
+        again.file(),
+        filename,
+        tmp_registry,
+    )?;
+    let result = compare_tarballs(tarball, &mut again);
+    let path = again.path().to_path_buf();
+    drop(again);
+    paths::remove_file(&path)?;
+    result
+}
+
+/// Checks that two tarballs of the same package are byte for byte identical,
+/// pointing at the first differing entry if they are not.
+fn compare_tarballs(a: &mut FileLock, b: &mut FileLock) -> CargoResult<()> {
+    fn read(tarball: &mut FileLock) -> CargoResult<Vec<u8>> {
+        let mut contents = Vec::new();
+        tarball.seek(SeekFrom::Start(0))?;
+        tarball.read_to_end(&mut contents)?;
+        Ok(contents)
     }
 
-    if pkg.include_lockfile() {
-        let new_lock = build_lock(&ws)?;
+    fn entries(tarball: &[u8]) -> CargoResult<Vec<(String, Vec<u8>)>> {
+        let mut archive = Archive::new(GzDecoder::new(tarball));
+        let mut entries = Vec::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.display().to_string();
+            let mut data = entry.header().as_bytes().to_vec();
+            entry.read_to_end(&mut data)?;
+            entries.push((path, data));
+        }
+        Ok(entries)
+    }
 
-        config
-            .shell()
-            .verbose(|shell| shell.status("Archiving", "Cargo.lock"))?;
-        let path = format!(
-            "{}-{}{}Cargo.lock",
-            pkg.name(),
-            pkg.version(),
-            path::MAIN_SEPARATOR
-        );
-        let mut header = Header::new_ustar();
-        header.set_path(&path)?;
-        header.set_entry_type(EntryType::file());
-        header.set_mode(0o644);
-        header.set_size(new_lock.len() as u64);
-        header.set_cksum();
-        ar.append(&header, new_lock.as_bytes())
-            .chain_err(|| internal("could not archive source file `Cargo.lock`"))?;
+    let (a, b) = (read(a)?, read(b)?);
+    if a == b {
+        return Ok(());
+    }
+    let (a, b) = (entries(&a)?, entries(&b)?);
+    for (a, b) in a.iter().zip(b.iter()) {
+        if a.0 != b.0 {
+            failure::bail!(
+                "packaging twice archived `{}` and `{}` at the same position",
+                a.0,
+                b.0
+            );
+        }
+        if a.1 != b.1 {
+            failure::bail!("packaging twice yielded different contents for `{}`", a.0);
+        }
     }
+    if a.len() != b.len() {
+        failure::bail!("packaging twice yielded {} and {} files", a.len(), b.len());
+    }
+    failure::bail!("packaging twice yielded the same files but different compressed tarballs")
+}
 
-    let encoder = ar.into_inner()?;
-    encoder.finish()?;
-    Ok(())
+/// The modification time recorded for every entry of a tarball.
+///
+/// This is taken from the `SOURCE_DATE_EPOCH` environment variable if it is
+/// set, see <https://reproducible-builds.org/specs/source-date-epoch/>.
+/// Otherwise an arbitrary fixed, non-zero, timestamp is used as some tools
+/// don't handle files dated from the epoch well.
+fn archive_mtime() -> CargoResult<u64> {
+    // Jul 23, 2006, the same default as `tar::HeaderMode::Deterministic`.
+    const DEFAULT_MTIME: u64 = 1_153_704_088;
+
+    match env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch.trim().parse().map_err(|_| {
+            failure::format_err!(
+                "SOURCE_DATE_EPOCH must be a non-negative integer, found `{}`",
+                epoch
+            )
+        }),
+        Err(_) => Ok(DEFAULT_MTIME),
+    }
 }
 
 /// Generate warnings when packaging Cargo.lock, and the resolve have changed.
@@ -564,9 +1050,14 @@ fn check_yanked(config: &Config, pkg_set: &PackageSet<'_>, resolve: &Resolve) ->
     Ok(())
 }
 
-fn run_verify(ws: &Workspace<'_>, tar: &FileLock, opts: &PackageOpts<'_>) -> CargoResult<()> {
+fn run_verify(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    tar: &FileLock,
+    opts: &PackageOpts<'_>,
+    tmp_registry: Option<&TmpRegistry<'_>>,
+) -> CargoResult<()> {
     let config = ws.config();
-    let pkg = ws.current()?;
 
     config.shell().status("Verifying", pkg)?;
 
@@ -589,27 +1080,22 @@ fn run_verify(ws: &Workspace<'_>, tar: &FileLock, opts: &PackageOpts<'_>) -> Car
     let mut src = PathSource::new(&dst, id, ws.config());
     let new_pkg = src.root_package()?;
     let pkg_fingerprint = hash_all(&dst)?;
-    let ws = Workspace::ephemeral(new_pkg, config, None, true)?;
-
-    let rustc_args = if pkg
-        .manifest()
-        .features()
-        .require(Feature::public_dependency())
-        .is_ok()
-    {
-        // FIXME: Turn this on at some point in the future
-        //Some(vec!["-D exported_private_dependencies".to_string()])
-        None
-    } else {
-        None
-    };
+    let mut ws = Workspace::ephemeral(new_pkg, config, None, true)?;
+    if let Some(tmp_registry) = tmp_registry {
+        tmp_registry.overlay(&mut ws);
+    }
 
     let exec: Arc<dyn Executor> = Arc::new(DefaultExecutor);
     ops::compile_ws(
         &ws,
         &ops::CompileOptions {
             config,
-            build_config: BuildConfig::new(config, opts.jobs, &opts.target, CompileMode::Build)?,
+            build_config: BuildConfig::new(
+                config,
+                opts.jobs,
+                &opts.target.iter().cloned().collect::<Vec<_>>(),
+                CompileMode::Build,
+            )?,
             features: opts.features.clone(),
             no_default_features: opts.no_default_features,
             all_features: opts.all_features,
@@ -618,7 +1104,11 @@ fn run_verify(ws: &Workspace<'_>, tar: &FileLock, opts: &PackageOpts<'_>) -> Car
                 required_features_filterable: true,
             },
             target_rustdoc_args: None,
-            target_rustc_args: rustc_args,
+            // With the `public-dependency` feature, private dependencies are
+            // passed as such to rustc, so the `exported_private_dependencies`
+            // lint warns about them showing up in the public API. This build
+            // is the only place that happens, `--no-verify` skips the check.
+            target_rustc_args: None,
             local_rustdoc_args: None,
             export_dir: None,
         },
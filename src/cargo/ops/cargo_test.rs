@@ -1,16 +1,109 @@
+use std::cmp;
+use std::collections::HashMap;
 use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::value::RawValue;
 
 use crate::core::compiler::{Compilation, Doctest};
+use crate::core::manifest::TargetKind;
 use crate::core::shell::Verbosity;
-use crate::core::Workspace;
+use crate::core::{Package, Target, Workspace};
 use crate::ops;
-use crate::util::errors::CargoResult;
-use crate::util::{CargoTestError, ProcessError, Test};
+use crate::util::errors::{CargoResult, CargoResultExt};
+use crate::util::junit::{self, TestCase, TestOutcome, TestSuite};
+use crate::util::{hash_u64, machine_message, paths, process_error};
+use crate::util::{CargoTestError, Config, ProcessBuilder, ProcessError, Test};
 
 pub struct TestOptions<'a> {
     pub compile_opts: ops::CompileOptions<'a>,
     pub no_run: bool,
     pub no_fail_fast: bool,
+    /// Runs the test binaries concurrently, up to `-j` at a time, instead of
+    /// one after another.
+    pub parallel_binaries: bool,
+    /// Where to write a JUnit XML report of the unit and integration tests.
+    pub junit: Option<PathBuf>,
+    /// Only runs the share of the tests in this partition.
+    pub partition: Option<Partition>,
+    /// Prints the tests in each partition instead of running them.
+    pub list_partitions: bool,
+    /// How long a test binary may run for before it's killed, over the
+    /// `timeout` of its target.
+    pub test_timeout: Option<Duration>,
+    /// Warns about the test binaries which run for longer than this.
+    pub report_slow: Option<Duration>,
+}
+
+/// A share of the tests, so that they can be split across several machines
+/// with `--partition`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Partition {
+    pub kind: PartitionKind,
+    /// The partition to run, from 1 to `total`.
+    pub shard: u64,
+    pub total: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartitionKind {
+    /// The tests of each binary are dealt out in turn, which evens out the
+    /// size of the partitions. Adding or removing a test moves the tests
+    /// listed after it in the same binary to other partitions.
+    Count,
+    /// The tests are assigned by a hash of their name, so a test stays in its
+    /// partition whatever tests are added or removed.
+    Hash,
+}
+
+impl Partition {
+    /// Parses `count:N/M` or `hash:N/M`.
+    pub fn parse(s: &str) -> CargoResult<Partition> {
+        let parse = || {
+            let mut parts = s.splitn(2, ':');
+            let kind = match parts.next()? {
+                "count" => PartitionKind::Count,
+                "hash" => PartitionKind::Hash,
+                _ => return None,
+            };
+            let mut parts = parts.next()?.splitn(2, '/');
+            let shard = parts.next()?.parse().ok()?;
+            let total = parts.next()?.parse().ok()?;
+            Some(Partition { kind, shard, total })
+        };
+        let partition = match parse() {
+            Some(partition) => partition,
+            None => failure::bail!(
+                "invalid partition `{}`, expected `count:N/M` or `hash:N/M`",
+                s
+            ),
+        };
+        if partition.shard == 0 || partition.shard > partition.total {
+            failure::bail!("invalid partition `{}`, N must be between 1 and M", s);
+        }
+        Ok(partition)
+    }
+
+    /// The partition of a test, given its index among the tests of its binary
+    /// for `count:N/M`. Tests in other binaries never change the result.
+    fn of(&self, id: &(&str, &str, &str, &str), index: u64) -> u64 {
+        match self.kind {
+            PartitionKind::Count => {
+                // Binaries start dealing out their tests at different
+                // partitions, so that the first partition doesn't get more
+                // tests than the others.
+                let start = hash_u64(&(id.0, id.1, id.2));
+                (start.wrapping_add(index) % self.total) + 1
+            }
+            PartitionKind::Hash => hash_u64(id) % self.total + 1,
+        }
+    }
 }
 
 pub fn run_tests(
@@ -23,14 +116,29 @@ pub fn run_tests(
     if options.no_run {
         return Ok(None);
     }
-    let (test, mut errors) = run_unit_tests(options, test_args, &compilation)?;
+    let assignment = match options.partition {
+        Some(partition) => Some(Assignment::new(partition, test_args, &compilation)?),
+        None => None,
+    };
+    if let Some(assignment) = &assignment {
+        if options.list_partitions {
+            assignment.print(&compilation);
+            return Ok(None);
+        }
+    }
+    let assignment = assignment.as_ref();
+
+    let mut report = TestReport::new(ws, options, test_args)?;
+    let result = run_unit_tests(options, test_args, &compilation, assignment, &mut report);
+    report.finish()?;
+    let (test, mut errors) = result?;
 
     // If we have an error and want to fail fast, then return.
     if !errors.is_empty() && !options.no_fail_fast {
         return Ok(Some(CargoTestError::new(test, errors)));
     }
 
-    let (doctest, docerrors) = run_doc_tests(options, test_args, &compilation)?;
+    let (doctest, docerrors) = run_doc_tests(options, test_args, &compilation, assignment)?;
     let test = if docerrors.is_empty() { test } else { doctest };
     errors.extend(docerrors);
     if errors.is_empty() {
@@ -54,7 +162,10 @@ pub fn run_benches(
     let mut args = args.to_vec();
     args.push("--bench");
 
-    let (test, errors) = run_unit_tests(options, &args, &compilation)?;
+    let mut report = TestReport::new(ws, options, &args)?;
+    let result = run_unit_tests(options, &args, &compilation, None, &mut report);
+    report.finish()?;
+    let (test, errors) = result?;
 
     match errors.len() {
         0 => Ok(None),
@@ -73,39 +184,322 @@ fn compile_tests<'a>(
     Ok(compilation)
 }
 
+/// A test binary to run, and what it's been built from.
+struct TestBinary<'a> {
+    pkg: &'a Package,
+    target: &'a Target,
+    exe: &'a Path,
+    exe_display: String,
+    cmd: ProcessBuilder,
+}
+
+impl<'a> TestBinary<'a> {
+    /// The name of the binary in summaries and reports, such as
+    /// `foo (integration-test "bar")`.
+    fn name(&self) -> String {
+        binary_name(self.pkg, self.target)
+    }
+}
+
+fn binary_name(pkg: &Package, target: &Target) -> String {
+    match target.kind() {
+        TargetKind::Lib(..) => format!("{} (lib)", pkg.name()),
+        kind => format!(
+            "{} ({} \"{}\")",
+            pkg.name(),
+            kind.description(),
+            target.name()
+        ),
+    }
+}
+
+fn test_binaries<'a>(
+    options: &TestOptions<'_>,
+    test_args: &[&str],
+    compilation: &'a Compilation<'_>,
+    assignment: Option<&Assignment>,
+    report: &TestReport<'_>,
+) -> CargoResult<Vec<TestBinary<'a>>> {
+    let config = options.compile_opts.config;
+    let cwd = config.cwd();
+    let mut binaries = Vec::new();
+    for (i, &(ref pkg, ref target, kind, ref exe)) in compilation.tests.iter().enumerate() {
+        let mut cmd = compilation.target_process(exe, pkg, kind)?;
+        match assignment.map(|a| a.selected(i)) {
+            None | Some(Selection::All) => {
+                cmd.args(test_args);
+            }
+            Some(Selection::Skip(skipped)) => {
+                // The user's filters have been applied when listing the
+                // tests, and the tests they leave out are skipped as well.
+                // Libtest only takes a single name filter, but any number of
+                // `--skip`.
+                cmd.args(&libtest_options(test_args)).arg("--exact");
+                for test in skipped {
+                    cmd.arg("--skip").arg(test);
+                }
+            }
+            Some(Selection::Nothing) => continue,
+        }
+        cmd.timeout(
+            options
+                .test_timeout
+                .or_else(|| target.test_timeout().map(Duration::from_secs)),
+        );
+        if target.harness() && report.libtest_json {
+            cmd.args(&["-Z", "unstable-options", "--format", "json"]);
+        } else if target.harness() && config.shell().verbosity() == Verbosity::Quiet {
+            cmd.arg("--quiet");
+        }
+        binaries.push(TestBinary {
+            pkg,
+            target,
+            exe,
+            exe_display: exe.strip_prefix(cwd).unwrap_or(exe).display().to_string(),
+            cmd,
+        });
+    }
+    Ok(binaries)
+}
+
+/// Which tests of a test binary are run in a partition.
+enum Selection {
+    All,
+    /// All the tests but these.
+    Skip(Vec<String>),
+    Nothing,
+}
+
+/// The partition of every test, with `--partition`.
+///
+/// The tests of binaries using libtest are listed with `--list`, and each is
+/// assigned to a partition. Binaries which don't use libtest, and the
+/// documentation tests of a package, are assigned as a whole.
+struct Assignment {
+    partition: Partition,
+    /// For each test binary, the partition of each of its tests, or of the
+    /// whole binary. Tests left out by the user's filters have none.
+    binaries: Vec<Result<Vec<(String, Option<u64>)>, u64>>,
+    /// The partition of each package's documentation tests.
+    doc_tests: Vec<u64>,
+}
+
+impl Assignment {
+    fn new(
+        partition: Partition,
+        test_args: &[&str],
+        compilation: &Compilation<'_>,
+    ) -> CargoResult<Assignment> {
+        let mut binaries = Vec::new();
+        for &(ref pkg, ref target, unit_kind, ref exe) in &compilation.tests {
+            let pkg_name = pkg.name();
+            let kind = target.kind().description();
+            if !target.harness() {
+                let id = (pkg_name.as_str(), kind, target.name(), "");
+                binaries.push(Err(partition.of(&id, 0)));
+                continue;
+            }
+            let list = |args: &[&str]| -> CargoResult<Vec<String>> {
+                let mut cmd = compilation.target_process(exe, pkg, unit_kind)?;
+                cmd.args(args).arg("--list");
+                let output = cmd.exec_with_output().chain_err(|| {
+                    format!("failed to list the tests of {}", binary_name(pkg, target))
+                })?;
+                Ok(String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter_map(|line| {
+                        let mut parts = line.rsplitn(2, ": ");
+                        match (parts.next(), parts.next()) {
+                            (Some("test"), Some(name)) | (Some("benchmark"), Some(name)) => {
+                                Some(name.to_string())
+                            }
+                            _ => None,
+                        }
+                    })
+                    .collect())
+            };
+            let selected = list(test_args)?;
+            let all = if test_args.is_empty() {
+                selected.clone()
+            } else {
+                list(&[])?
+            };
+            let selected = selected
+                .iter()
+                .enumerate()
+                .map(|(i, test)| (test.as_str(), i))
+                .collect::<HashMap<_, _>>();
+            let tests = all
+                .iter()
+                .map(|test| {
+                    let shard = selected.get(test.as_str()).map(|&i| {
+                        let id = (pkg_name.as_str(), kind, target.name(), test.as_str());
+                        partition.of(&id, i as u64)
+                    });
+                    (test.clone(), shard)
+                })
+                .collect();
+            binaries.push(Ok(tests));
+        }
+        let doc_tests = compilation
+            .to_doc_test
+            .iter()
+            .map(|doctest| {
+                let id = (
+                    doctest.package.name().as_str(),
+                    "doc-test",
+                    doctest.target.name(),
+                    "",
+                );
+                partition.of(&id, 0)
+            })
+            .collect();
+        Ok(Assignment {
+            partition,
+            binaries,
+            doc_tests,
+        })
+    }
+
+    fn selected(&self, binary: usize) -> Selection {
+        let shard = self.partition.shard;
+        match &self.binaries[binary] {
+            Ok(tests) => {
+                let skipped = tests
+                    .iter()
+                    .filter(|&&(_, s)| s != Some(shard))
+                    .map(|(test, _)| test.clone())
+                    .collect::<Vec<_>>();
+                if skipped.len() == tests.len() {
+                    Selection::Nothing
+                } else if skipped.is_empty() {
+                    Selection::All
+                } else {
+                    Selection::Skip(skipped)
+                }
+            }
+            Err(s) if *s == shard => Selection::All,
+            Err(_) => Selection::Nothing,
+        }
+    }
+
+    fn runs_doc_tests(&self, package: usize) -> bool {
+        self.doc_tests[package] == self.partition.shard
+    }
+
+    /// Prints the tests of each partition, for `--list-partitions`.
+    fn print(&self, compilation: &Compilation<'_>) {
+        for shard in 1..=self.partition.total {
+            let mut tests = Vec::new();
+            for (assigned, (pkg, target, _, _)) in self.binaries.iter().zip(&compilation.tests) {
+                let name = binary_name(pkg, target);
+                match assigned {
+                    Ok(assigned) => tests.extend(
+                        assigned
+                            .iter()
+                            .filter(|&&(_, s)| s == Some(shard))
+                            .map(|(test, _)| format!("{}: {}", name, test)),
+                    ),
+                    Err(s) if *s == shard => tests.push(name),
+                    Err(_) => {}
+                }
+            }
+            for (&s, doctest) in self.doc_tests.iter().zip(&compilation.to_doc_test) {
+                if s == shard {
+                    tests.push(format!("{} (doc-tests)", doctest.package.name()));
+                }
+            }
+            println!(
+                "partition {}/{} ({} tests):",
+                shard,
+                self.partition.total,
+                tests.len()
+            );
+            for test in tests {
+                println!("    {}", test);
+            }
+        }
+    }
+}
+
+/// The options among the arguments to libtest, leaving out the test name
+/// filters, `--skip` and `--exact`.
+fn libtest_options<'a>(test_args: &[&'a str]) -> Vec<&'a str> {
+    const TAKES_VALUE: &[&str] = &["--color", "--format", "--logfile", "--test-threads", "-Z"];
+    let mut options = Vec::new();
+    let mut args = test_args.iter();
+    while let Some(&arg) = args.next() {
+        if !arg.starts_with('-') || arg == "--exact" {
+            continue;
+        }
+        if arg == "--skip" {
+            args.next();
+            continue;
+        }
+        options.push(arg);
+        if TAKES_VALUE.contains(&arg) {
+            options.extend(args.next());
+        }
+    }
+    options
+}
+
 /// Runs the unit and integration tests of a package.
 fn run_unit_tests(
     options: &TestOptions<'_>,
     test_args: &[&str],
     compilation: &Compilation<'_>,
+    assignment: Option<&Assignment>,
+    report: &mut TestReport<'_>,
 ) -> CargoResult<(Test, Vec<ProcessError>)> {
+    let binaries = test_binaries(options, test_args, compilation, assignment, report)?;
+    if options.parallel_binaries {
+        return run_unit_tests_parallel(options, &binaries, report);
+    }
     let config = options.compile_opts.config;
-    let cwd = options.compile_opts.config.cwd();
 
     let mut errors = Vec::new();
 
-    for &(ref pkg, ref target, ref exe) in &compilation.tests {
-        let kind = target.kind();
-        let test = target.name().to_string();
-        let exe_display = exe.strip_prefix(cwd).unwrap_or(exe).display();
-        let mut cmd = compilation.target_process(exe, pkg)?;
-        cmd.args(test_args);
-        if target.harness() && config.shell().verbosity() == Verbosity::Quiet {
-            cmd.arg("--quiet");
-        }
+    for binary in &binaries {
         config
             .shell()
-            .concise(|shell| shell.status("Running", &exe_display))?;
+            .concise(|shell| shell.status("Running", &binary.exe_display))?;
         config
             .shell()
-            .verbose(|shell| shell.status("Running", &cmd))?;
+            .verbose(|shell| shell.status("Running", &binary.cmd))?;
 
-        let result = cmd.exec();
+        report.started(binary);
+        let start = Instant::now();
+        let result = if report.captures_output() {
+            let mut output = report.output();
+            let result = binary
+                .cmd
+                .exec_with_streaming(
+                    &mut |line| output.on_stdout_line(binary, line),
+                    &mut |line| {
+                        writeln!(config.shell().err(), "{}", line)?;
+                        Ok(())
+                    },
+                    false,
+                )
+                .map(drop);
+            let error = result.as_ref().err().and_then(|e| e.downcast_ref());
+            report.finished(binary, output, error, start.elapsed());
+            result
+        } else {
+            binary.cmd.exec()
+        };
+        report.record_duration(binary, start.elapsed());
 
         match result {
             Err(e) => {
                 let e = e.downcast::<ProcessError>()?;
-                errors.push((kind.clone(), test.clone(), pkg.name().to_string(), e));
+                errors.push((
+                    binary.target.kind().clone(),
+                    binary.target.name().to_string(),
+                    binary.pkg.name().to_string(),
+                    e,
+                ));
                 if !options.no_fail_fast {
                     break;
                 }
@@ -114,21 +508,508 @@ fn run_unit_tests(
         }
     }
 
+    Ok(test_errors(errors))
+}
+
+/// Describes the failures of the unit and integration tests, pointing at the
+/// test binary when only one failed.
+fn test_errors(
+    mut errors: Vec<(TargetKind, String, String, ProcessError)>,
+) -> (Test, Vec<ProcessError>) {
     if errors.len() == 1 {
         let (kind, name, pkg_name, e) = errors.pop().unwrap();
-        Ok((
+        (
             Test::UnitTest {
                 kind,
                 name,
                 pkg_name,
             },
             vec![e],
-        ))
+        )
     } else {
-        Ok((
+        (
             Test::Multiple,
             errors.into_iter().map(|(_, _, _, e)| e).collect(),
-        ))
+        )
+    }
+}
+
+/// Runs the unit and integration tests of the packages with up to `-j` test
+/// binaries at a time. The output of each binary is buffered, and printed
+/// once it has exited, so that the output of different binaries doesn't
+/// interleave. The tests which failed in any of them are listed at the end.
+fn run_unit_tests_parallel(
+    options: &TestOptions<'_>,
+    binaries: &[TestBinary<'_>],
+    report: &mut TestReport<'_>,
+) -> CargoResult<(Test, Vec<ProcessError>)> {
+    let config = options.compile_opts.config;
+    let jobs = cmp::min(
+        options.compile_opts.build_config.jobs as usize,
+        binaries.len(),
+    );
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let no_fail_fast = options.no_fail_fast;
+    let (tx, rx) = channel();
+    let mut failures = Vec::new();
+    let mut ran = 0;
+    let cmds = binaries.iter().map(|b| &b.cmd).collect::<Vec<_>>();
+    crossbeam_utils::thread::scope(|scope| -> CargoResult<()> {
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let (cmds, next, stop) = (&cmds, &next, &stop);
+            scope.spawn(move |_| {
+                // Once a binary failed without `--no-fail-fast`, the ones
+                // which are running are waited for, but no others are run.
+                while !stop.load(Ordering::SeqCst) {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    let cmd = match cmds.get(i) {
+                        Some(cmd) => cmd,
+                        None => break,
+                    };
+                    let start = Instant::now();
+                    let mut timed_out = false;
+                    let output = cmd
+                        .build_command()
+                        .stdin(Stdio::null())
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::piped())
+                        .spawn()
+                        .and_then(|child| {
+                            let watchdog = cmd.watchdog(&child);
+                            let output = child.wait_with_output();
+                            timed_out = watchdog.map_or(false, |w| w.finish());
+                            output
+                        });
+                    let failed = output.as_ref().map_or(true, |o| !o.status.success());
+                    if failed && !no_fail_fast {
+                        stop.store(true, Ordering::SeqCst);
+                    }
+                    let ran = (output, timed_out, start.elapsed());
+                    if tx.send((i, ran)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for (i, (output, timed_out, duration)) in rx {
+            let binary = &binaries[i];
+            ran += 1;
+            let output = output.chain_err(|| {
+                process_error(
+                    &format!("could not execute process {}", binary.cmd),
+                    None,
+                    None,
+                )
+            })?;
+            config
+                .shell()
+                .concise(|shell| shell.status("Running", &binary.exe_display))?;
+            config
+                .shell()
+                .verbose(|shell| shell.status("Running", &binary.cmd))?;
+            report.started(binary);
+            let mut test_output = report.output();
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                test_output.on_stdout_line(binary, line)?;
+            }
+            io::stdout().flush()?;
+            config.shell().err().write_all(&output.stderr)?;
+
+            let error = if timed_out {
+                Some(binary.cmd.timed_out_error(output.status, None))
+            } else if output.status.success() {
+                None
+            } else {
+                Some(process_error(
+                    &format!("process didn't exit successfully: {}", binary.cmd),
+                    Some(output.status),
+                    None,
+                ))
+            };
+            let failed_tests = test_output.failed_tests();
+            report.finished(binary, test_output, error.as_ref(), duration);
+            report.record_duration(binary, duration);
+            if let Some(e) = error {
+                failures.push((i, failed_tests, e));
+            }
+        }
+        Ok(())
+    })
+    .unwrap()?;
+
+    // The binaries finish in any order, but are reported in a stable one.
+    failures.sort_by_key(|&(i, _, _)| i);
+    print_summary(config, report.json_messages, binaries, ran, &failures)?;
+    let errors = failures
+        .into_iter()
+        .map(|(i, _, e)| {
+            let binary = &binaries[i];
+            (
+                binary.target.kind().clone(),
+                binary.target.name().to_string(),
+                binary.pkg.name().to_string(),
+                e,
+            )
+        })
+        .collect();
+    Ok(test_errors(errors))
+}
+
+/// Prints the outcome of all the test binaries which were run in parallel,
+/// listing the failed tests of every package. `ran` binaries were run, the
+/// others were skipped after a failure.
+fn print_summary(
+    config: &Config,
+    json_messages: bool,
+    binaries: &[TestBinary<'_>],
+    ran: usize,
+    failures: &[(usize, Vec<String>, ProcessError)],
+) -> CargoResult<()> {
+    let mut summary = format!("\ntest binaries: {} passed", ran - failures.len());
+    if !failures.is_empty() {
+        summary.push_str(&format!("; {} failed", failures.len()));
+    }
+    if ran < binaries.len() {
+        summary.push_str(&format!("; {} not run", binaries.len() - ran));
+    }
+    summary.push_str("\n");
+    if !failures.is_empty() {
+        summary.push_str("\nfailures across all test binaries:\n");
+        for (i, tests, e) in failures {
+            let name = binaries[*i].name();
+            if tests.is_empty() {
+                summary.push_str(&format!("    {}: {}\n", name, e.desc));
+            }
+            for test in tests {
+                summary.push_str(&format!("    {}: {}\n", name, test));
+            }
+        }
+    }
+    // Standard output only has JSON messages in JSON mode.
+    if json_messages {
+        config.shell().err().write_all(summary.as_bytes())?;
+    } else {
+        print!("{}", summary);
+        io::stdout().flush()?;
+    }
+    Ok(())
+}
+
+/// Reports the results of the test binaries as they finish, in JSON messages
+/// and in a JUnit report.
+struct TestReport<'cfg> {
+    config: &'cfg Config,
+    json_messages: bool,
+    /// Whether libtest is asked for its (unstable) JSON output, which is
+    /// translated to messages for each test.
+    libtest_json: bool,
+    junit: Option<(PathBuf, Vec<TestSuite>)>,
+    /// The threshold of `--report-slow`, and the binaries which exceeded it.
+    slow: Option<(Duration, Vec<(String, Duration)>)>,
+}
+
+impl<'cfg> TestReport<'cfg> {
+    fn new(
+        ws: &Workspace<'cfg>,
+        options: &TestOptions<'_>,
+        test_args: &[&str],
+    ) -> CargoResult<TestReport<'cfg>> {
+        let config = ws.config();
+        let json_messages = options.compile_opts.build_config.json_messages();
+        // Libtest only outputs JSON on nightly toolchains, and the user may
+        // have asked for another format.
+        let libtest_json = json_messages
+            && !test_args.iter().any(|arg| arg.starts_with("--format"))
+            && is_nightly(&config.load_global_rustc(Some(ws))?.verbose_version);
+        Ok(TestReport {
+            config,
+            json_messages,
+            libtest_json,
+            junit: options.junit.clone().map(|path| (path, Vec::new())),
+            slow: options.report_slow.map(|threshold| (threshold, Vec::new())),
+        })
+    }
+
+    /// Whether the output of the test binaries needs to be looked at,
+    /// rather than just printed.
+    fn captures_output(&self) -> bool {
+        self.json_messages || self.junit.is_some()
+    }
+
+    fn output(&self) -> TestOutput<'cfg> {
+        TestOutput {
+            config: self.config,
+            json_messages: self.json_messages,
+            libtest_json: self.libtest_json,
+            cases: Vec::new(),
+            failure_outputs: HashMap::new(),
+            state: OutputState::Tests,
+        }
+    }
+
+    fn started(&self, binary: &TestBinary<'_>) {
+        if self.json_messages {
+            machine_message::emit(&machine_message::TestBinaryStarted {
+                package_id: binary.pkg.package_id(),
+                target: binary.target,
+                executable: binary.exe,
+            });
+        }
+    }
+
+    fn finished(
+        &mut self,
+        binary: &TestBinary<'_>,
+        output: TestOutput<'_>,
+        error: Option<&ProcessError>,
+        duration: Duration,
+    ) {
+        if self.json_messages {
+            let exit = error.and_then(|e| e.exit);
+            machine_message::emit(&machine_message::TestBinaryFinished {
+                package_id: binary.pkg.package_id(),
+                target: binary.target,
+                executable: binary.exe,
+                success: error.is_none(),
+                exit_code: exit.and_then(|e| e.code()),
+                duration: duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9,
+            });
+        }
+        if let Some((_, suites)) = &mut self.junit {
+            let mut cases = output.into_cases();
+            // The binary failed for another reason than its tests failing,
+            // such as crashing, or it doesn't use libtest.
+            if let Some(e) = error {
+                if !cases.iter().any(|c| c.outcome == TestOutcome::Failed) {
+                    cases.push(TestCase {
+                        name: binary.target.name().to_string(),
+                        outcome: TestOutcome::Failed,
+                        output: Some(e.desc.clone()),
+                    });
+                }
+            } else if cases.is_empty() {
+                cases.push(TestCase {
+                    name: binary.target.name().to_string(),
+                    outcome: TestOutcome::Passed,
+                    output: None,
+                });
+            }
+            suites.push(TestSuite {
+                name: binary.name(),
+                time: duration,
+                cases,
+            });
+        }
+    }
+
+    fn record_duration(&mut self, binary: &TestBinary<'_>, duration: Duration) {
+        if let Some((threshold, slow)) = &mut self.slow {
+            if duration > *threshold {
+                slow.push((binary.name(), duration));
+            }
+        }
+    }
+
+    /// Warns about the slow test binaries, and writes the JUnit report, if
+    /// they were asked for.
+    fn finish(self) -> CargoResult<()> {
+        if let Some((threshold, mut slow)) = self.slow {
+            if !slow.is_empty() {
+                slow.sort_by(|a, b| b.1.cmp(&a.1));
+                let mut msg = format!(
+                    "{} test {} ran for longer than {} seconds:",
+                    slow.len(),
+                    if slow.len() == 1 {
+                        "binary"
+                    } else {
+                        "binaries"
+                    },
+                    threshold.as_secs()
+                );
+                for (name, duration) in slow {
+                    msg.push_str(&format!(
+                        "\n    {}: {}.{:02}s",
+                        name,
+                        duration.as_secs(),
+                        duration.subsec_millis() / 10
+                    ));
+                }
+                self.config.shell().warn(msg)?;
+            }
+        }
+        if let Some((path, suites)) = self.junit {
+            paths::write(&path, junit::to_xml(&suites).as_bytes())
+                .chain_err(|| "failed to write the JUnit report")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_nightly(rustc_verbose_version: &str) -> bool {
+    rustc_verbose_version
+        .lines()
+        .filter(|line| line.starts_with("release: "))
+        .any(|line| line.contains("-nightly") || line.contains("-dev"))
+}
+
+/// Where the human output of libtest is at, when looking for the results of
+/// the tests in it.
+enum OutputState {
+    /// The `test name ... ok` lines.
+    Tests,
+    /// The output of the failed tests, starting with `---- name stdout ----`.
+    FailureOutput(String),
+    /// The list of failed tests at the end.
+    Failures,
+}
+
+/// Collects the results of the tests from the output of a test binary,
+/// passing it on to the user.
+struct TestOutput<'cfg> {
+    config: &'cfg Config,
+    json_messages: bool,
+    libtest_json: bool,
+    cases: Vec<TestCase>,
+    failure_outputs: HashMap<String, String>,
+    state: OutputState,
+}
+
+impl<'cfg> TestOutput<'cfg> {
+    fn on_stdout_line(&mut self, binary: &TestBinary<'_>, line: &str) -> CargoResult<()> {
+        if self.libtest_json && binary.target.harness() && line.starts_with('{') {
+            let message: Box<RawValue> = serde_json::from_str(line)
+                .chain_err(|| format!("test produced invalid json: `{}`", line))?;
+            self.on_libtest_event(message.get());
+            machine_message::emit(&machine_message::FromTest {
+                package_id: binary.pkg.package_id(),
+                target: binary.target,
+                message,
+            });
+            return Ok(());
+        }
+
+        self.on_human_line(line);
+        // Standard output only has JSON messages in JSON mode.
+        if self.json_messages {
+            writeln!(self.config.shell().err(), "{}", line)?;
+        } else {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    fn on_libtest_event(&mut self, event: &str) {
+        #[derive(Deserialize)]
+        struct Event {
+            #[serde(rename = "type")]
+            kind: String,
+            event: Option<String>,
+            name: Option<String>,
+            stdout: Option<String>,
+        }
+        let event = match serde_json::from_str::<Event>(event) {
+            Ok(event) => event,
+            Err(_) => return,
+        };
+        let name = match event.name {
+            Some(name) => name,
+            None => return,
+        };
+        let outcome = match (
+            event.kind.as_str(),
+            event.event.as_ref().map(|s| s.as_str()),
+        ) {
+            ("test", Some("ok")) | ("bench", _) => TestOutcome::Passed,
+            ("test", Some("failed")) => TestOutcome::Failed,
+            ("test", Some("ignored")) => TestOutcome::Ignored,
+            _ => return,
+        };
+        self.cases.push(TestCase {
+            name,
+            outcome,
+            output: event.stdout,
+        });
+    }
+
+    fn on_human_line(&mut self, line: &str) {
+        if line == "failures:" {
+            self.state = OutputState::Failures;
+            return;
+        }
+        if line.starts_with("---- ") && line.ends_with(" stdout ----") {
+            let name = &line["---- ".len()..line.len() - " stdout ----".len()];
+            self.state = OutputState::FailureOutput(name.to_string());
+            return;
+        }
+        match &self.state {
+            OutputState::Tests => {
+                // `test name ... ok`, or `FAILED`, `ignored` or `bench: ...`.
+                if !line.starts_with("test ") {
+                    return;
+                }
+                let mut parts = line["test ".len()..].rsplitn(2, " ... ");
+                let (result, name) = match (parts.next(), parts.next()) {
+                    (Some(result), Some(name)) => (result, name),
+                    _ => return,
+                };
+                let outcome = if result == "ok" || result.starts_with("bench:") {
+                    TestOutcome::Passed
+                } else if result == "FAILED" {
+                    TestOutcome::Failed
+                } else if result.starts_with("ignored") {
+                    TestOutcome::Ignored
+                } else {
+                    return;
+                };
+                self.cases.push(TestCase {
+                    name: name.to_string(),
+                    outcome,
+                    output: None,
+                });
+            }
+            OutputState::FailureOutput(name) => {
+                let output = self.failure_outputs.entry(name.clone()).or_default();
+                output.push_str(line);
+                output.push('\n');
+            }
+            OutputState::Failures => {
+                // Failed tests are listed here even with `--quiet`, which
+                // otherwise doesn't print their names.
+                if line.starts_with("    ") {
+                    let name = line.trim();
+                    if !self.cases.iter().any(|c| c.name == name) {
+                        self.cases.push(TestCase {
+                            name: name.to_string(),
+                            outcome: TestOutcome::Failed,
+                            output: None,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn failed_tests(&self) -> Vec<String> {
+        self.cases
+            .iter()
+            .filter(|c| c.outcome == TestOutcome::Failed)
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    fn into_cases(self) -> Vec<TestCase> {
+        let mut outputs = self.failure_outputs;
+        let mut cases = self.cases;
+        for case in &mut cases {
+            if case.output.is_none() {
+                case.output = outputs.remove(&case.name);
+            }
+        }
+        cases
     }
 }
 
@@ -136,29 +1017,34 @@ fn run_doc_tests(
     options: &TestOptions<'_>,
     test_args: &[&str],
     compilation: &Compilation<'_>,
+    assignment: Option<&Assignment>,
 ) -> CargoResult<(Test, Vec<ProcessError>)> {
     let mut errors = Vec::new();
     let config = options.compile_opts.config;
 
-    // We don't build/run doc tests if `target` does not equal `host`.
-    if compilation.host != compilation.target {
-        return Ok((Test::Doc, errors));
-    }
-
-    for doctest_info in &compilation.to_doc_test {
+    for (i, doctest_info) in compilation.to_doc_test.iter().enumerate() {
+        if assignment.map_or(false, |a| !a.runs_doc_tests(i)) {
+            continue;
+        }
         let Doctest {
             package,
             target,
+            kind,
             deps,
         } = doctest_info;
+        // We don't build/run doc tests if `target` does not equal `host`.
+        if compilation.host != compilation.triple(*kind) {
+            continue;
+        }
         config.shell().status("Doc-tests", target.name())?;
-        let mut p = compilation.rustdoc_process(package, target)?;
+        let mut p = compilation.rustdoc_process(package, target, *kind)?;
+        p.timeout(options.test_timeout);
         p.arg("--test")
             .arg(target.src_path().path().unwrap())
             .arg("--crate-name")
             .arg(&target.crate_name());
 
-        for &rust_dep in &[&compilation.deps_output] {
+        for &rust_dep in &[&compilation.deps_output[kind]] {
             let mut arg = OsString::from("dependency=");
             arg.push(rust_dep);
             p.arg("-L").arg(arg);
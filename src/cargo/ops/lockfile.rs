@@ -2,13 +2,42 @@ use std::io::prelude::*;
 
 use toml;
 
-use crate::core::resolver::WorkspaceResolve;
+use crate::core::resolver::{ResolveVersion, WorkspaceResolve};
 use crate::core::{resolver, Resolve, Workspace};
 use crate::util::errors::{CargoResult, CargoResultExt};
 use crate::util::toml as cargo_toml;
 use crate::util::Filesystem;
 
 pub fn load_pkg_lockfile(ws: &Workspace<'_>) -> CargoResult<Option<Resolve>> {
+    Ok(load_lockfile(ws)?.map(|lockfile| lockfile.into_parts().0))
+}
+
+/// The contents of `Cargo.lock`.
+pub enum Lockfile {
+    Resolved(Resolve),
+    /// `Cargo.lock` has git merge conflicts. Everything locked on either side
+    /// is used to guide the resolution, which then replaces the conflicts.
+    Conflicted {
+        /// Our side of the merge.
+        ours: Resolve,
+        /// The union of both sides.
+        merged: Resolve,
+    },
+}
+
+impl Lockfile {
+    /// The resolve which guides the resolution process, and our side of the
+    /// merge conflicts if there are any.
+    pub fn into_parts(self) -> (Resolve, Option<Resolve>) {
+        match self {
+            Lockfile::Resolved(resolve) => (resolve, None),
+            Lockfile::Conflicted { ours, merged } => (merged, Some(ours)),
+        }
+    }
+}
+
+/// Loads `Cargo.lock`, keeping track of whether it has merge conflicts.
+pub fn load_lockfile(ws: &Workspace<'_>) -> CargoResult<Option<Lockfile>> {
     if !ws.root().join("Cargo.lock").exists() {
         return Ok(None);
     }
@@ -20,13 +49,78 @@ pub fn load_pkg_lockfile(ws: &Workspace<'_>) -> CargoResult<Option<Resolve>> {
     f.read_to_string(&mut s)
         .chain_err(|| format!("failed to read file: {}", f.path().display()))?;
 
-    let resolve = (|| -> CargoResult<Option<Resolve>> {
-        let resolve: toml::Value = cargo_toml::parse(&s, f.path(), ws.config())?;
-        let v: resolver::EncodableResolve = resolve.try_into()?;
-        Ok(Some(v.into_resolve(ws)?))
+    let lockfile = (|| -> CargoResult<Option<Lockfile>> {
+        let parse = |s: &str| -> CargoResult<Resolve> {
+            let resolve: toml::Value = cargo_toml::parse(s, f.path(), ws.config())?;
+            let v: resolver::EncodableResolve = resolve.try_into()?;
+            Ok(v.into_resolve(ws)?)
+        };
+        let lockfile = match split_merge_conflicts(&s)? {
+            Some((ours, theirs)) => {
+                let ours = parse(&ours)?;
+                let merged = ours.union(&parse(&theirs)?);
+                Lockfile::Conflicted { ours, merged }
+            }
+            None => Lockfile::Resolved(parse(&s)?),
+        };
+        Ok(Some(lockfile))
     })()
     .chain_err(|| format!("failed to parse lock file at: {}", f.path().display()))?;
-    Ok(resolve)
+    Ok(lockfile)
+}
+
+/// Splits a file with git merge conflict markers into the two versions being
+/// merged, leaving out the common ancestor of `diff3` style conflicts.
+///
+/// Returns `None` if there are no conflicts.
+fn split_merge_conflicts(s: &str) -> CargoResult<Option<(String, String)>> {
+    #[derive(PartialEq)]
+    enum Section {
+        Common,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut conflicts = 0;
+    let mut section = Section::Common;
+    let mut ours = String::new();
+    let mut theirs = String::new();
+    for (i, line) in s.lines().enumerate() {
+        let marker = ['<', '|', '=', '>'].iter().cloned().find(|&c| {
+            let marker = c.to_string().repeat(7);
+            line == marker || (c != '=' && line.starts_with(&(marker + " ")))
+        });
+        section = match (marker, section) {
+            (Some('<'), Section::Common) => {
+                conflicts += 1;
+                Section::Ours
+            }
+            (Some('|'), Section::Ours) => Section::Base,
+            (Some('='), Section::Ours) | (Some('='), Section::Base) => Section::Theirs,
+            (Some('>'), Section::Theirs) => Section::Common,
+            (Some(_), _) => failure::bail!("unexpected merge conflict marker on line {}", i + 1),
+            (None, section) => {
+                if section != Section::Theirs && section != Section::Base {
+                    ours.push_str(line);
+                    ours.push('\n');
+                }
+                if section != Section::Ours && section != Section::Base {
+                    theirs.push_str(line);
+                    theirs.push('\n');
+                }
+                section
+            }
+        };
+    }
+
+    if conflicts == 0 {
+        return Ok(None);
+    }
+    if section != Section::Common {
+        failure::bail!("merge conflict is missing its end marker");
+    }
+    Ok(Some((ours, theirs)))
 }
 
 /// Generate a toml String of Cargo.lock from a Resolve.
@@ -122,6 +216,10 @@ fn resolve_to_string_orig(
         }
     }
 
+    if let Some(version) = toml.get("version") {
+        out.push_str(&format!("version = {}\n\n", version));
+    }
+
     let deps = toml["package"].as_array().unwrap();
     for dep in deps.iter() {
         let dep = dep.as_table().unwrap();
@@ -144,6 +242,14 @@ fn resolve_to_string_orig(
         out.push_str(&meta.to_string());
     }
 
+    // The original format leaves a blank line at the end of files without
+    // `[metadata]`, which is kept so as not to rewrite them.
+    if resolve.version() >= ResolveVersion::V2 {
+        while out.ends_with("\n\n") {
+            out.pop();
+        }
+    }
+
     Ok((orig.ok(), out, ws_root))
 }
 
@@ -185,6 +291,9 @@ fn emit_package(dep: &toml::value::Table, out: &mut String) {
     if dep.contains_key("source") {
         out.push_str(&format!("source = {}\n", &dep["source"]));
     }
+    if let Some(checksum) = dep.get("checksum") {
+        out.push_str(&format!("checksum = {}\n", checksum));
+    }
 
     if let Some(s) = dep.get("dependencies") {
         let slice = s.as_array().unwrap();
@@ -12,6 +12,7 @@ use failure::{bail, format_err};
 use log::{log, Level};
 use url::percent_encoding::{percent_encode, QUERY_ENCODE_SET};
 
+use crate::core::compiler::MessageFormat;
 use crate::core::dependency::Kind;
 use crate::core::manifest::ManifestMetadata;
 use crate::core::source::Source;
@@ -43,25 +44,60 @@ pub struct PublishOpts<'cfg> {
     pub features: Vec<String>,
     pub all_features: bool,
     pub no_default_features: bool,
+    pub to_publish: ops::Packages,
 }
 
 pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
-    let pkg = ws.current()?;
-
-    if let Some(ref allowed_registries) = *pkg.publish() {
-        let reg_name = opts
-            .registry
-            .clone()
-            .unwrap_or_else(|| CRATES_IO_REGISTRY.to_string());
-        if !allowed_registries.contains(&reg_name) {
-            bail!(
-                "`{}` cannot be published.\n\
-                 The registry `{}` is not listed in the `publish` value in Cargo.toml.",
-                pkg.name(),
-                reg_name
-            );
+    let pkgs = match opts.to_publish {
+        ops::Packages::Default => vec![ws.current()?],
+        ref to_publish => to_publish.get_packages(ws)?,
+    };
+    if pkgs.is_empty() {
+        bail!("no packages to publish")
+    }
+    // Members depending on each other have to be published in order, so that
+    // the registry knows about a dependency by the time it's used.
+    let pkgs = ops::sort_members(&pkgs);
+
+    let reg_name = opts
+        .registry
+        .clone()
+        .unwrap_or_else(|| CRATES_IO_REGISTRY.to_string());
+    // Members which may not be published to this registry, such as examples
+    // with `publish = false`, are skipped when publishing the whole
+    // workspace, but asking for one of them explicitly is an error.
+    let explicit = match opts.to_publish {
+        ops::Packages::Default | ops::Packages::Packages(_) => true,
+        ops::Packages::All | ops::Packages::OptOut(_) => false,
+    };
+    let mut publishable = Vec::new();
+    for pkg in pkgs {
+        match *pkg.publish() {
+            Some(ref allowed_registries) if !allowed_registries.contains(&reg_name) => {
+                if explicit {
+                    bail!(
+                        "`{}` cannot be published.\n\
+                         The registry `{}` is not listed in the `publish` value in Cargo.toml.",
+                        pkg.name(),
+                        reg_name
+                    );
+                }
+                opts.config.shell().status(
+                    "Skipping",
+                    format!(
+                        "{} (not allowed to be published to `{}`)",
+                        pkg.package_id(),
+                        reg_name
+                    ),
+                )?;
+            }
+            _ => publishable.push(pkg),
         }
     }
+    let pkgs = publishable;
+    if pkgs.is_empty() {
+        bail!("no packages to publish")
+    }
 
     let (mut registry, reg_id) = registry(
         opts.config,
@@ -71,15 +107,20 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
         true,
         !opts.dry_run,
     )?;
-    verify_dependencies(pkg, &registry, reg_id)?;
+    for pkg in pkgs.iter() {
+        verify_dependencies(pkg, &registry, reg_id)?;
+    }
 
-    // Prepare a tarball, with a non-surpressable warning if metadata
-    // is missing since this is being put online.
-    let tarball = ops::package(
+    // Prepare the tarballs, with a non-surpressable warning if metadata
+    // is missing since this is being put online. Nothing is uploaded
+    // unless every package could be packaged and verified.
+    let tarballs = ops::package_many(
         ws,
+        &pkgs,
         &ops::PackageOpts {
             config: opts.config,
             verify: opts.verify,
+            verify_reproducible: false,
             list: false,
             check_metadata: true,
             allow_dirty: opts.allow_dirty,
@@ -88,23 +129,40 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
             features: opts.features.clone(),
             all_features: opts.all_features,
             no_default_features: opts.no_default_features,
+            to_package: opts.to_publish.clone(),
+            message_format: MessageFormat::Human,
         },
-    )?
-    .unwrap();
-
-    // Upload said tarball to the specified destination
-    opts.config
-        .shell()
-        .status("Uploading", pkg.package_id().to_string())?;
-    transmit(
-        opts.config,
-        pkg,
-        tarball.file(),
-        &mut registry,
-        reg_id,
-        opts.dry_run,
     )?;
 
+    // Upload said tarballs to the specified destination
+    let mut published = Vec::new();
+    for (pkg, tarball) in pkgs.iter().zip(tarballs.iter()) {
+        opts.config
+            .shell()
+            .status("Uploading", pkg.package_id().to_string())?;
+        let result = transmit(
+            opts.config,
+            pkg,
+            tarball.file(),
+            &mut registry,
+            reg_id,
+            opts.dry_run,
+        );
+        if let Err(e) = result {
+            if published.is_empty() {
+                return Err(e);
+            }
+            let cx = format!(
+                "failed to publish `{}`, the following packages were already \
+                 published:\n  {}",
+                pkg.package_id(),
+                published.join("\n  ")
+            );
+            return Err(e.context(cx).into());
+        }
+        published.push(pkg.package_id().to_string());
+    }
+
     Ok(())
 }
 
@@ -24,7 +24,7 @@ version. This may also occur with an optional dependency that is not enabled.";
 /// This function will also write the result of resolution as a new
 /// lock file.
 pub fn resolve_ws<'a>(ws: &Workspace<'a>) -> CargoResult<(PackageSet<'a>, Resolve)> {
-    let mut registry = PackageRegistry::new(ws.config())?;
+    let mut registry = ws.package_registry()?;
     let resolve = resolve_with_registry(ws, &mut registry)?;
     let packages = get_resolved_packages(&resolve, registry)?;
     Ok((packages, resolve))
@@ -58,7 +58,7 @@ pub fn resolve_ws_with_method<'a>(
     method: Method,
     specs: &[PackageIdSpec],
 ) -> CargoResult<(PackageSet<'a>, Resolve)> {
-    let mut registry = PackageRegistry::new(ws.config())?;
+    let mut registry = ws.package_registry()?;
     let mut add_patches = true;
 
     let resolve = if ws.ignore_lock() {
@@ -111,7 +111,10 @@ fn resolve_with_registry<'cfg>(
     ws: &Workspace<'cfg>,
     registry: &mut PackageRegistry<'cfg>,
 ) -> CargoResult<Resolve> {
-    let prev = ops::load_pkg_lockfile(ws)?;
+    let (prev, ours) = match ops::load_lockfile(ws)?.map(ops::Lockfile::into_parts) {
+        Some((prev, ours)) => (Some(prev), ours),
+        None => (None, None),
+    };
     let resolve = resolve_with_previous(
         registry,
         ws,
@@ -123,6 +126,12 @@ fn resolve_with_registry<'cfg>(
     )?;
 
     if !ws.is_ephemeral() {
+        if let Some(ours) = &ours {
+            ws.config()
+                .shell()
+                .status("Merging", "conflicting changes to Cargo.lock")?;
+            ops::print_lockfile_changes(ws.config(), ours, &resolve)?;
+        }
         ops::write_pkg_lockfile(ws, &resolve)?;
     }
     Ok(resolve)
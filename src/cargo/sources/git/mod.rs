@@ -1,4 +1,5 @@
 pub use self::source::{canonicalize_url, GitSource};
-pub use self::utils::{fetch, GitCheckout, GitDatabase, GitRemote, GitRevision};
+pub use self::utils::{fetch, fetch_depth, FetchDepth};
+pub use self::utils::{GitCheckout, GitDatabase, GitRemote, GitRevision};
 mod source;
 mod utils;
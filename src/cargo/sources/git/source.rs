@@ -4,8 +4,8 @@ use log::trace;
 use url::Url;
 
 use crate::core::source::{MaybePackage, Source, SourceId};
-use crate::core::GitReference;
 use crate::core::{Dependency, Package, PackageId, Summary};
+use crate::core::{GitReference, GitSubmodules};
 use crate::sources::git::utils::{GitRemote, GitRevision};
 use crate::sources::PathSource;
 use crate::util::errors::CargoResult;
@@ -193,18 +193,25 @@ impl<'cfg> Source for GitSource<'cfg> {
         // on Windows. See <https://github.com/servo/servo/pull/14397>.
         let short_id = db.to_short_id(&actual_rev).unwrap();
 
+        // Checkouts with only some of the submodules are kept apart from the
+        // full ones, as they contain different files.
+        let submodules = self.source_id.git_submodules().unwrap();
+        let checkout_name = match *submodules {
+            GitSubmodules::All => short_id.as_str().to_string(),
+            _ => format!("{}-{}", short_id.as_str(), short_hash(submodules)),
+        };
         let checkout_path = lock
             .parent()
             .join("checkouts")
             .join(&self.ident)
-            .join(short_id.as_str());
+            .join(checkout_name);
 
         // Copy the database to the checkout location. After this we could drop
         // the lock on the database as we no longer needed it, but we leave it
         // in scope so the destructors here won't tamper with too much.
         // Checkout is immutable, so we don't need to protect it with a lock once
         // it is created.
-        db.copy_to(actual_rev.clone(), &checkout_path, self.config)?;
+        db.copy_to(actual_rev.clone(), &checkout_path, submodules, self.config)?;
 
         let source_id = self.source_id.with_precise(Some(actual_rev.to_string()));
         let path_source = PathSource::new_recursive(&checkout_path, source_id, self.config);
@@ -12,7 +12,7 @@ use serde::ser;
 use serde::Serialize;
 use url::Url;
 
-use crate::core::GitReference;
+use crate::core::{GitReference, GitSubmodules};
 use crate::util::errors::{CargoResult, CargoResultExt};
 use crate::util::paths;
 use crate::util::process_builder::process;
@@ -104,17 +104,17 @@ impl GitRemote {
         if let Ok(mut repo) = git2::Repository::open(into) {
             self.fetch_into(&mut repo, cargo_config)
                 .chain_err(|| format!("failed to fetch into {}", into.display()))?;
-            if let Ok(rev) = reference.resolve(&repo) {
+            if let Ok(rev) = self.resolve_deepening(&mut repo, reference, cargo_config) {
                 repo_and_rev = Some((repo, rev));
             }
         }
         let (repo, rev) = match repo_and_rev {
             Some(pair) => pair,
             None => {
-                let repo = self
+                let mut repo = self
                     .clone_into(into, cargo_config)
                     .chain_err(|| format!("failed to clone into: {}", into.display()))?;
-                let rev = reference.resolve(&repo)?;
+                let rev = self.resolve_deepening(&mut repo, reference, cargo_config)?;
                 (repo, rev)
             }
         };
@@ -141,7 +141,65 @@ impl GitRemote {
     fn fetch_into(&self, dst: &mut git2::Repository, cargo_config: &Config) -> CargoResult<()> {
         // Create a local anonymous remote in the repository to fetch the url
         let refspec = "refs/heads/*:refs/heads/*";
-        fetch(dst, &self.url, refspec, cargo_config)
+        let depth = fetch_depth(cargo_config)?;
+        fetch(dst, &self.url, refspec, depth, cargo_config)
+    }
+
+    /// Resolves `reference` in `repo`. If that fails because `repo` only
+    /// contains the tips of its branches, such as for an older revision
+    /// recorded in a lock file, as little history as possible is fetched
+    /// before resolving the reference again: a revision is first fetched on
+    /// its own, then the branches are deepened step by step, and only as a
+    /// last resort is their full history fetched.
+    fn resolve_deepening(
+        &self,
+        repo: &mut git2::Repository,
+        reference: &GitReference,
+        cargo_config: &Config,
+    ) -> CargoResult<GitRevision> {
+        let err = match reference.resolve(repo) {
+            Ok(rev) => return Ok(rev),
+            Err(e) => e,
+        };
+        if !is_shallow(repo) {
+            return Err(err);
+        }
+        debug!("{:?} not found in shallow clone, deepening", reference);
+
+        // Not all servers allow fetching a commit which isn't the tip of a
+        // ref, so failing to is only a reason to try deepening. The commit is
+        // kept in a branch of its own, so that checkouts cloned from the
+        // database get it too.
+        if let GitReference::Rev(rev) = reference {
+            if rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit()) {
+                let refspec = format!("+{0}:refs/heads/cargo-rev-{0}", rev);
+                let depth = FetchDepth::Shallow;
+                let fetched = fetch(repo, &self.url, &refspec, depth, cargo_config);
+                if let Err(e) = fetched {
+                    debug!("failed to fetch {} on its own: {}", rev, e);
+                } else if let Ok(rev) = reference.resolve(repo) {
+                    return Ok(rev);
+                }
+            }
+        }
+
+        let refspec = "refs/heads/*:refs/heads/*";
+        for &deepen in &[16, 256, 4096] {
+            if !is_shallow(repo) {
+                break;
+            }
+            let depth = FetchDepth::Deepen(deepen);
+            fetch(repo, &self.url, refspec, depth, cargo_config)
+                .chain_err(|| format!("failed to fetch the history of {}", self.url))?;
+            if let Ok(rev) = reference.resolve(repo) {
+                return Ok(rev);
+            }
+        }
+        if is_shallow(repo) {
+            fetch(repo, &self.url, refspec, FetchDepth::Full, cargo_config)
+                .chain_err(|| format!("failed to fetch the history of {}", self.url))?;
+        }
+        reference.resolve(repo)
     }
 
     fn clone_into(&self, dst: &Path, cargo_config: &Config) -> CargoResult<git2::Repository> {
@@ -154,6 +212,7 @@ impl GitRemote {
             &mut repo,
             &self.url,
             "refs/heads/*:refs/heads/*",
+            fetch_depth(cargo_config)?,
             cargo_config,
         )?;
         Ok(repo)
@@ -165,6 +224,7 @@ impl GitDatabase {
         &self,
         rev: GitRevision,
         dest: &Path,
+        submodules: &GitSubmodules,
         cargo_config: &Config,
     ) -> CargoResult<GitCheckout<'_>> {
         let mut checkout = None;
@@ -189,7 +249,7 @@ impl GitDatabase {
             Some(c) => c,
             None => GitCheckout::clone_into(dest, self, rev, cargo_config)?,
         };
-        checkout.update_submodules(cargo_config)?;
+        checkout.update_submodules(submodules, cargo_config)?;
         Ok(checkout)
     }
 
@@ -263,6 +323,24 @@ impl<'a> GitCheckout<'a> {
             paths::remove_dir_all(into)?;
         }
 
+        // libgit2 is unable to clone a shallow repository, so the checkout of
+        // a shallow database is cloned with `git`-the-CLI instead.
+        if is_shallow(&database.repo) {
+            let mut cmd = process("git");
+            cmd.arg("clone")
+                .arg("--no-checkout") // we'll do this below during a `reset`
+                .arg(database.path.to_url()?.to_string())
+                .arg(into);
+            config
+                .shell()
+                .verbose(|s| s.status("Running", &cmd.to_string()))?;
+            cmd.exec_with_output()?;
+            let repo = git2::Repository::open(into)?;
+            let checkout = GitCheckout::new(into, database, revision, repo);
+            checkout.reset(config)?;
+            return Ok(checkout);
+        }
+
         // we're doing a local filesystem-to-filesystem clone so there should
         // be no need to respect global configuration options, so pass in
         // an empty instance of `git2::Config` below.
@@ -309,10 +387,18 @@ impl<'a> GitCheckout<'a> {
     }
 
     fn fetch(&mut self, cargo_config: &Config) -> CargoResult<()> {
+        // A checkout of a shallow database is shallow itself, and fetching
+        // into it could end up asking the database for history it doesn't
+        // have. The checkout is cloned again instead if the revision is
+        // missing.
+        if is_shallow(&self.repo) {
+            return Ok(());
+        }
         info!("fetch {}", self.repo.path().display());
         let url = self.database.path.to_url()?;
         let refspec = "refs/heads/*:refs/heads/*";
-        fetch(&mut self.repo, &url, refspec, cargo_config)?;
+        let depth = FetchDepth::Full;
+        fetch(&mut self.repo, &url, refspec, depth, cargo_config)?;
         Ok(())
     }
 
@@ -338,8 +424,28 @@ impl<'a> GitCheckout<'a> {
         Ok(())
     }
 
-    fn update_submodules(&self, cargo_config: &Config) -> CargoResult<()> {
-        return update_submodules(&self.repo, cargo_config);
+    fn update_submodules(
+        &self,
+        submodules: &GitSubmodules,
+        cargo_config: &Config,
+    ) -> CargoResult<()> {
+        info!("update submodules for: {:?}", self.repo.workdir().unwrap());
+
+        // Only the submodules of the checkout itself are filtered, the
+        // ones selected are updated along with all of their own.
+        for mut child in self.repo.submodules()? {
+            if !submodules.includes(child.path()) {
+                debug!("skipping submodule `{}`", child.path().display());
+                continue;
+            }
+            update_submodule(&self.repo, &mut child, cargo_config).chain_err(|| {
+                format!(
+                    "failed to update submodule `{}`",
+                    child.name().unwrap_or("")
+                )
+            })?;
+        }
+        return Ok(());
 
         fn update_submodules(repo: &git2::Repository, cargo_config: &Config) -> CargoResult<()> {
             info!("update submodules for: {:?}", repo.workdir().unwrap());
@@ -397,7 +503,7 @@ impl<'a> GitCheckout<'a> {
             // Fetch data from origin and reset to the head commit
             let refspec = "refs/heads/*:refs/heads/*";
             let url = url.to_url()?;
-            fetch(&mut repo, &url, refspec, cargo_config).chain_err(|| {
+            fetch(&mut repo, &url, refspec, FetchDepth::Full, cargo_config).chain_err(|| {
                 internal(format!(
                     "failed to fetch submodule `{}` from {}",
                     child.name().unwrap_or(""),
@@ -667,10 +773,43 @@ pub fn with_fetch_options(
     })
 }
 
+/// How much of the history of the refs is downloaded by `fetch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchDepth {
+    /// All of it. A shallow repository is completed.
+    Full,
+    /// Only the commits at the tip of the refs.
+    Shallow,
+    /// This many more commits than a shallow repository already has.
+    Deepen(u32),
+}
+
+/// Returns how much history fetches from remote repositories download, only
+/// the commits at the tip of the fetched refs if `net.git-shallow` is set.
+pub fn fetch_depth(config: &Config) -> CargoResult<FetchDepth> {
+    let shallow = config
+        .get_bool("net.git-shallow")?
+        .map(|v| v.val)
+        .unwrap_or(false);
+    Ok(if shallow {
+        FetchDepth::Shallow
+    } else {
+        FetchDepth::Full
+    })
+}
+
+/// Returns whether `repo` only contains part of the history of its refs.
+fn is_shallow(repo: &git2::Repository) -> bool {
+    repo.path().join("shallow").exists()
+}
+
+/// Fetches `refspec` from `url` into `repo`, with as much history as `depth`
+/// asks for.
 pub fn fetch(
     repo: &mut git2::Repository,
     url: &Url,
     refspec: &str,
+    depth: FetchDepth,
     config: &Config,
 ) -> CargoResult<()> {
     if config.frozen() {
@@ -711,10 +850,16 @@ pub fn fetch(
     // speed and portability of using `libgit2`.
     if let Some(val) = config.get_bool("net.git-fetch-with-cli")? {
         if val.val {
-            return fetch_with_cli(repo, url, refspec, config);
+            return fetch_with_cli(repo, url, refspec, depth, config);
         }
     }
 
+    // `libgit2` can neither create shallow repositories nor fetch the
+    // missing history into them, so those fetches need `git` as well.
+    if depth != FetchDepth::Full || is_shallow(repo) {
+        return fetch_with_cli(repo, url, refspec, depth, config);
+    }
+
     debug!("doing a fetch for {}", url);
     let git_config = git2::Config::open_default()?;
     with_fetch_options(&git_config, url, config, &mut |mut opts| {
@@ -761,16 +906,27 @@ fn fetch_with_cli(
     repo: &mut git2::Repository,
     url: &Url,
     refspec: &str,
+    depth: FetchDepth,
     config: &Config,
 ) -> CargoResult<()> {
     let mut cmd = process("git");
     cmd.arg("fetch")
         .arg("--tags") // fetch all tags
         .arg("--force") // handle force pushes
-        .arg("--update-head-ok") // see discussion in #2078
-        .arg(url.to_string())
-        .arg(refspec)
-        .cwd(repo.path());
+        .arg("--update-head-ok"); // see discussion in #2078
+    match depth {
+        FetchDepth::Full if is_shallow(repo) => {
+            cmd.arg("--unshallow");
+        }
+        FetchDepth::Full => {}
+        FetchDepth::Shallow => {
+            cmd.arg("--depth=1");
+        }
+        FetchDepth::Deepen(n) => {
+            cmd.arg(format!("--deepen={}", n));
+        }
+    }
+    cmd.arg(url.to_string()).arg(refspec).cwd(repo.path());
     config
         .shell()
         .verbose(|s| s.status("Running", &cmd.to_string()))?;
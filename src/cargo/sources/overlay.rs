@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+
+use crate::core::source::MaybePackage;
+use crate::core::{Dependency, Package, PackageId, Source, SourceId, Summary};
+use crate::util::errors::{CargoResult, CargoResultExt};
+
+/// A source which layers the packages of a local registry on top of another
+/// source, typically a remote registry.
+///
+/// This is used when packaging or publishing several crates of a workspace at
+/// once: the freshly created `.crate` files are put into a temporary local
+/// registry which is then overlaid on the registry they are going to be
+/// uploaded to. That way a crate depending on a new version of one of its
+/// siblings can be verified before that sibling is actually published.
+///
+/// Summaries coming from the local registry are presented as if they came
+/// from the overlaid source, and they take precedence over any summary of the
+/// same version already present there.
+pub struct OverlaySource<'cfg> {
+    local: Box<dyn Source + 'cfg>,
+    remote: Box<dyn Source + 'cfg>,
+    // Packages, with the remote `SourceId`, which were returned by the local
+    // source and hence must be downloaded from it.
+    local_packages: HashSet<PackageId>,
+}
+
+impl<'cfg> OverlaySource<'cfg> {
+    pub fn new(
+        local: Box<dyn Source + 'cfg>,
+        remote: Box<dyn Source + 'cfg>,
+    ) -> OverlaySource<'cfg> {
+        OverlaySource {
+            local,
+            remote,
+            local_packages: HashSet::new(),
+        }
+    }
+
+    fn query_both(
+        &mut self,
+        dep: &Dependency,
+        fuzzy: bool,
+        f: &mut dyn FnMut(Summary),
+    ) -> CargoResult<()> {
+        let (local_id, remote_id) = (self.local.source_id(), self.remote.source_id());
+        let local_dep = dep.clone().map_source(remote_id, local_id);
+
+        let mut local_summaries = Vec::new();
+        {
+            let mut push = |s: Summary| local_summaries.push(s.map_source(local_id, remote_id));
+            if fuzzy {
+                self.local.fuzzy_query(&local_dep, &mut push)
+            } else {
+                self.local.query(&local_dep, &mut push)
+            }
+            .chain_err(|| format!("failed to query local overlay of {}", remote_id))?;
+        }
+
+        let local_versions = local_summaries
+            .iter()
+            .map(|s| s.package_id())
+            .collect::<HashSet<_>>();
+        self.local_packages.extend(local_versions.iter().cloned());
+        for summary in local_summaries {
+            f(summary);
+        }
+
+        let mut filter = |s: Summary| {
+            if !local_versions.contains(&s.package_id()) {
+                f(s)
+            }
+        };
+        if fuzzy {
+            self.remote.fuzzy_query(dep, &mut filter)
+        } else {
+            self.remote.query(dep, &mut filter)
+        }
+    }
+}
+
+impl<'cfg> Source for OverlaySource<'cfg> {
+    fn source_id(&self) -> SourceId {
+        self.remote.source_id()
+    }
+
+    fn replaced_source_id(&self) -> SourceId {
+        self.remote.replaced_source_id()
+    }
+
+    fn supports_checksums(&self) -> bool {
+        self.remote.supports_checksums()
+    }
+
+    fn requires_precise(&self) -> bool {
+        self.remote.requires_precise()
+    }
+
+    fn query(&mut self, dep: &Dependency, f: &mut dyn FnMut(Summary)) -> CargoResult<()> {
+        self.query_both(dep, false, f)
+    }
+
+    fn fuzzy_query(&mut self, dep: &Dependency, f: &mut dyn FnMut(Summary)) -> CargoResult<()> {
+        self.query_both(dep, true, f)
+    }
+
+    fn update(&mut self) -> CargoResult<()> {
+        self.local.update()?;
+        self.remote.update()
+    }
+
+    fn download(&mut self, id: PackageId) -> CargoResult<MaybePackage> {
+        if !self.local_packages.contains(&id) {
+            return self.remote.download(id);
+        }
+        let (local_id, remote_id) = (self.local.source_id(), self.remote.source_id());
+        let pkg = self.local.download(id.with_source_id(local_id))?;
+        Ok(match pkg {
+            MaybePackage::Ready(pkg) => MaybePackage::Ready(pkg.map_source(local_id, remote_id)),
+            other @ MaybePackage::Download { .. } => other,
+        })
+    }
+
+    fn finish_download(&mut self, id: PackageId, data: Vec<u8>) -> CargoResult<Package> {
+        if !self.local_packages.contains(&id) {
+            return self.remote.finish_download(id, data);
+        }
+        let (local_id, remote_id) = (self.local.source_id(), self.remote.source_id());
+        let pkg = self
+            .local
+            .finish_download(id.with_source_id(local_id), data)?;
+        Ok(pkg.map_source(local_id, remote_id))
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        if self.local_packages.contains(&pkg.package_id()) {
+            self.local.fingerprint(pkg)
+        } else {
+            self.remote.fingerprint(pkg)
+        }
+    }
+
+    fn verify(&self, id: PackageId) -> CargoResult<()> {
+        if self.local_packages.contains(&id) {
+            self.local.verify(id.with_source_id(self.local.source_id()))
+        } else {
+            self.remote.verify(id)
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} (overlaid with {})",
+            self.remote.describe(),
+            self.local.describe()
+        )
+    }
+
+    fn is_replaced(&self) -> bool {
+        self.remote.is_replaced()
+    }
+
+    fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        self.remote.add_to_yanked_whitelist(pkgs);
+    }
+
+    fn is_yanked(&mut self, pkg: PackageId) -> CargoResult<bool> {
+        if self.local_packages.contains(&pkg) {
+            // Packages about to be published can't be yanked yet.
+            Ok(false)
+        } else {
+            self.remote.is_yanked(pkg)
+        }
+    }
+}
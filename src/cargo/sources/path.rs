@@ -13,6 +13,30 @@ use crate::core::{Dependency, Package, PackageId, Source, SourceId, Summary};
 use crate::ops;
 use crate::util::{internal, paths, CargoResult, CargoResultExt, Config};
 
+/// How a file of a package was found by `PathSource::list_files_detailed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileSource {
+    /// Listed from the git repository the package lives in, this is either a
+    /// tracked file or an untracked one which is not ignored.
+    Vcs,
+    /// Found by walking the directory of the package.
+    Walk,
+}
+
+/// A file found while listing the files of a package, along with whether
+/// and why it is part of the package.
+#[derive(Clone, Debug)]
+pub struct ListedFile {
+    pub path: PathBuf,
+    pub source: FileSource,
+    /// Whether the file is part of the package. Files which are not are only
+    /// listed when a pattern of the manifest's `exclude` list left them out.
+    pub included: bool,
+    /// The pattern of the manifest's `include` or `exclude` list which
+    /// matched this file, if any.
+    pub rule: Option<String>,
+}
+
 pub struct PathSource<'cfg> {
     source_id: SourceId,
     path: PathBuf,
@@ -110,6 +134,18 @@ impl<'cfg> PathSource<'cfg> {
     ///
     /// See rust-lang/cargo#4268 for more info.
     pub fn list_files(&self, pkg: &Package) -> CargoResult<Vec<PathBuf>> {
+        Ok(self
+            .list_files_detailed(pkg)?
+            .into_iter()
+            .filter(|file| file.included)
+            .map(|file| file.path)
+            .collect())
+    }
+
+    /// Same as `list_files`, but also reports how each file was found and
+    /// which rule of the manifest selected it, as well as the files which
+    /// were left out by an `exclude` rule.
+    pub fn list_files_detailed(&self, pkg: &Package) -> CargoResult<Vec<ListedFile>> {
         let root = pkg.root();
         let no_include_option = pkg.manifest().include().is_empty();
 
@@ -197,6 +233,7 @@ impl<'cfg> PathSource<'cfg> {
 
         // Matching to paths.
 
+        let mut excluded = Vec::new();
         let mut filter = |path: &Path| -> CargoResult<bool> {
             let relative_path = path.strip_prefix(root)?;
             let glob_should_package = glob_should_package(relative_path);
@@ -245,17 +282,57 @@ impl<'cfg> PathSource<'cfg> {
                 // Update to `ignore_should_package` for Stage 2.
                 _ => glob_should_package,
             };
+            if !should_include && no_include_option {
+                excluded.push(path.to_path_buf());
+            }
 
             Ok(should_include)
         };
 
         // Attempt Git-prepopulate only if no `include` (see rust-lang/cargo#4135).
-        if no_include_option {
-            if let Some(result) = self.discover_git_and_list_files(pkg, root, &mut filter) {
-                return result;
+        let git_files = if no_include_option {
+            self.discover_git_and_list_files(pkg, root, &mut filter)
+        } else {
+            None
+        };
+        let (files, source) = match git_files {
+            Some(files) => (files?, FileSource::Vcs),
+            None => (self.list_files_walk(pkg, &mut filter)?, FileSource::Walk),
+        };
+
+        let matching_rule = |rules: &[String], patterns: &[Pattern], path: &Path| {
+            let relative = path.strip_prefix(root).ok()?;
+            rules
+                .iter()
+                .zip(patterns)
+                .find(|&(_, pattern)| pattern.matches_path(relative))
+                .map(|(rule, _)| rule.clone())
+        };
+        let mut listed = files
+            .into_iter()
+            .map(|path| {
+                let rule = matching_rule(pkg.manifest().include(), &glob_include, &path);
+                ListedFile {
+                    path,
+                    source,
+                    included: true,
+                    rule,
+                }
+            })
+            .collect::<Vec<_>>();
+        for path in excluded {
+            // Files can also be left out for other reasons, like `Cargo.lock`
+            // for libraries, only report the ones an exclude rule decided on.
+            if let Some(rule) = matching_rule(pkg.manifest().exclude(), &glob_exclude, &path) {
+                listed.push(ListedFile {
+                    path,
+                    source,
+                    included: false,
+                    rule: Some(rule),
+                });
             }
         }
-        self.list_files_walk(pkg, &mut filter)
+        Ok(listed)
     }
 
     // Returns `Some(_)` if found sibling `Cargo.toml` and `.git` directory;
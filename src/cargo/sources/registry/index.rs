@@ -8,7 +8,7 @@ use semver::Version;
 use crate::core::dependency::Dependency;
 use crate::core::{PackageId, SourceId, Summary};
 use crate::sources::registry::RegistryData;
-use crate::sources::registry::{RegistryPackage, INDEX_LOCK};
+use crate::sources::registry::{make_dep_path, RegistryPackage, INDEX_LOCK};
 use crate::util::{internal, CargoResult, Config, Filesystem, ToSemver};
 
 /// Crates.io treats hyphen and underscores as interchangeable, but the index and old Cargo do not.
@@ -178,18 +178,7 @@ impl<'cfg> RegistryIndex<'cfg> {
             (self.path.clone().into_path_unlocked(), None)
         };
 
-        let fs_name = name
-            .chars()
-            .flat_map(|c| c.to_lowercase())
-            .collect::<String>();
-
-        // See module comment for why this is structured the way it is.
-        let raw_path = match fs_name.len() {
-            1 => format!("1/{}", fs_name),
-            2 => format!("2/{}", fs_name),
-            3 => format!("3/{}/{}", &fs_name[..1], fs_name),
-            _ => format!("{}/{}/{}", &fs_name[0..2], &fs_name[2..4], fs_name),
-        };
+        let raw_path = make_dep_path(name);
         let mut ret = Vec::new();
         for path in UncanonicalizedIter::new(&raw_path).take(1024) {
             let mut hit_closure = false;
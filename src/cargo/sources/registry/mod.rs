@@ -392,6 +392,23 @@ fn short_name(id: SourceId) -> String {
     format!("{}-{}", ident, hash)
 }
 
+/// Returns the path, relative to the root of an index, of the file listing
+/// all versions of the crate `name`.
+///
+/// See the module comment for why this is structured the way it is.
+pub fn make_dep_path(name: &str) -> String {
+    let fs_name = name
+        .chars()
+        .flat_map(|c| c.to_lowercase())
+        .collect::<String>();
+    match fs_name.len() {
+        1 => format!("1/{}", fs_name),
+        2 => format!("2/{}", fs_name),
+        3 => format!("3/{}/{}", &fs_name[..1], fs_name),
+        _ => format!("{}/{}/{}", &fs_name[0..2], &fs_name[2..4], fs_name),
+    }
+}
+
 impl<'cfg> RegistrySource<'cfg> {
     pub fn remote(
         source_id: SourceId,
@@ -448,6 +465,12 @@ impl<'cfg> RegistrySource<'cfg> {
         }
     }
 
+    /// Unpacks the packages of this registry into `path` instead of the
+    /// source cache shared by all registries.
+    pub fn set_src_path(&mut self, path: Filesystem) {
+        self.src_path = path;
+    }
+
     /// Decode the configuration stored within the registry.
     ///
     /// This requires that the index has been at least checked out.
@@ -221,10 +221,14 @@ impl<'cfg> RegistryData for RemoteRegistry<'cfg> {
             .status("Updating", self.source_id.display_index())?;
 
         // git fetch origin master
+        //
+        // Only the tree of the latest commit is ever read, so a shallow
+        // fetch is enough for the index if it's been asked for.
         let url = self.source_id.url();
         let refspec = "refs/heads/master:refs/remotes/origin/master";
+        let depth = git::fetch_depth(self.config)?;
         let repo = self.repo.borrow_mut().unwrap();
-        git::fetch(repo, url, refspec, self.config)
+        git::fetch(repo, url, refspec, depth, self.config)
             .chain_err(|| format!("failed to fetch `{}`", url))?;
         self.config.updated_sources().insert(self.source_id);
         Ok(())
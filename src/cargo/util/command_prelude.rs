@@ -35,6 +35,17 @@ pub trait AppExt: Sized {
             ._arg(multi_opt("exclude", "SPEC", exclude))
     }
 
+    fn arg_package_spec_workspace(
+        self,
+        package: &'static str,
+        workspace: &'static str,
+        exclude: &'static str,
+    ) -> Self {
+        self.arg_package_spec_simple(package)
+            ._arg(opt("workspace", workspace))
+            ._arg(multi_opt("exclude", "SPEC", exclude))
+    }
+
     fn arg_package_spec_simple(self, package: &'static str) -> Self {
         self._arg(multi_opt("package", "SPEC", package).short("p"))
     }
@@ -121,6 +132,10 @@ pub trait AppExt: Sized {
         self._arg(opt("target", target).value_name("TRIPLE"))
     }
 
+    fn arg_target_triples(self, target: &'static str) -> Self {
+        self._arg(multi_opt("target", "TRIPLE", target))
+    }
+
     fn arg_target_dir(self) -> Self {
         self._arg(
             opt("target-dir", "Directory for all generated artifacts").value_name("DIRECTORY"),
@@ -141,6 +156,13 @@ pub trait AppExt: Sized {
         )
     }
 
+    fn arg_keep_going(self) -> Self {
+        self._arg(opt(
+            "keep-going",
+            "Keep building everything which doesn't depend on a failed unit",
+        ))
+    }
+
     fn arg_build_plan(self) -> Self {
         self._arg(opt(
             "build-plan",
@@ -288,19 +310,12 @@ pub trait ArgMatchesExt {
         self._value_of("target").map(|s| s.to_string())
     }
 
-    fn compile_options<'a>(
-        &self,
-        config: &'a Config,
-        mode: CompileMode,
-        workspace: Option<&Workspace<'a>>,
-    ) -> CargoResult<CompileOptions<'a>> {
-        let spec = Packages::from_flags(
-            self._is_present("all"),
-            self._values_of("exclude"),
-            self._values_of("package"),
-        )?;
+    fn targets(&self) -> Vec<String> {
+        self._values_of("target")
+    }
 
-        let message_format = match self._value_of("message-format") {
+    fn message_format(&self) -> MessageFormat {
+        match self._value_of("message-format") {
             None => MessageFormat::Human,
             Some(f) => {
                 if f.eq_ignore_ascii_case("json") {
@@ -313,11 +328,36 @@ pub trait ArgMatchesExt {
                     panic!("Impossible message format: {:?}", f)
                 }
             }
-        };
+        }
+    }
+
+    /// The packages selected with the arguments of
+    /// `arg_package_spec_workspace`.
+    fn packages_from_workspace_flags(&self) -> CargoResult<Packages> {
+        let workspace = self._is_present("workspace");
+        let exclude = self._values_of("exclude");
+        if !workspace && !exclude.is_empty() {
+            failure::bail!("--exclude can only be used together with --workspace")
+        }
+        Packages::from_flags(workspace, exclude, self._values_of("package"))
+    }
+
+    fn compile_options<'a>(
+        &self,
+        config: &'a Config,
+        mode: CompileMode,
+        workspace: Option<&Workspace<'a>>,
+    ) -> CargoResult<CompileOptions<'a>> {
+        let spec = Packages::from_flags(
+            self._is_present("all"),
+            self._values_of("exclude"),
+            self._values_of("package"),
+        )?;
 
-        let mut build_config = BuildConfig::new(config, self.jobs()?, &self.target(), mode)?;
-        build_config.message_format = message_format;
+        let mut build_config = BuildConfig::new(config, self.jobs()?, &self.targets(), mode)?;
+        build_config.message_format = self.message_format();
         build_config.release = self._is_present("release");
+        build_config.keep_going = self._is_present("keep-going");
         build_config.build_plan = self._is_present("build-plan");
         if build_config.build_plan {
             config
@@ -511,6 +511,31 @@ impl Config {
         }
     }
 
+    /// Gets a config value which may either be a single string or a list of
+    /// strings. Unlike `get_list_or_split_string`, a string is not split on
+    /// spaces, so that it may contain a path.
+    pub fn get_string_or_list(&self, key: &str) -> CargoResult<OptValue<Vec<String>>> {
+        if let Some(value) = self.get_env::<String>(&ConfigKey::from_str(key))? {
+            return Ok(Some(Value {
+                val: vec![value.val],
+                definition: value.definition,
+            }));
+        }
+
+        match self.get_cv(key)? {
+            Some(CV::List(i, path)) => Ok(Some(Value {
+                val: i.into_iter().map(|(s, _)| s).collect(),
+                definition: Definition::Path(path),
+            })),
+            Some(CV::String(i, path)) => Ok(Some(Value {
+                val: vec![i],
+                definition: Definition::Path(path),
+            })),
+            Some(val) => self.expected("string or list", key, &val),
+            None => Ok(None),
+        }
+    }
+
     pub fn get_table(&self, key: &str) -> CargoResult<OptValue<HashMap<String, CV>>> {
         match self.get_cv(key)? {
             Some(CV::Table(i, path)) => Ok(Some(Value {
@@ -3,66 +3,68 @@
 //!
 //! This structure is used to store the dependency graph and dynamically update
 //! it to figure out when a dependency should be built.
+//!
+//! Dependency edges are labeled with the piece of a dependency that is
+//! needed, so that a package may start as soon as the parts of its
+//! dependencies it needs are available, even if they are still being built.
 
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
 #[derive(Debug)]
-pub struct DependencyQueue<K: Eq + Hash, V> {
+pub struct DependencyQueue<K: Eq + Hash, E: Eq + Hash, V> {
     /// A list of all known keys to build.
     ///
     /// The value of the hash map is list of dependencies which still need to be
-    /// built before the package can be built. Note that the set is dynamically
-    /// updated as more dependencies are built.
-    dep_map: HashMap<K, (HashSet<K>, V)>,
+    /// built before the package can be built, along with the edge each one is
+    /// waited on for. Note that the set is dynamically updated as more
+    /// dependencies are built.
+    dep_map: HashMap<K, (HashSet<(K, E)>, V)>,
 
     /// A reverse mapping of a package to all packages that depend on that
-    /// package.
+    /// package, grouped by the edge they depend on it through.
     ///
     /// This map is statically known and does not get updated throughout the
     /// lifecycle of the DependencyQueue.
-    reverse_dep_map: HashMap<K, HashSet<K>>,
-
-    /// The packages which are currently being built, waiting for a call to
-    /// `finish`.
-    pending: HashSet<K>,
+    reverse_dep_map: HashMap<K, HashMap<E, HashSet<K>>>,
 
     /// Topological depth of each key
     depth: HashMap<K, usize>,
 }
 
-impl<K: Hash + Eq + Clone, V> Default for DependencyQueue<K, V> {
-    fn default() -> DependencyQueue<K, V> {
+impl<K: Hash + Eq + Clone, E: Hash + Eq + Clone, V> Default for DependencyQueue<K, E, V> {
+    fn default() -> DependencyQueue<K, E, V> {
         DependencyQueue::new()
     }
 }
 
-impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
+impl<K: Hash + Eq + Clone, E: Hash + Eq + Clone, V> DependencyQueue<K, E, V> {
     /// Creates a new dependency queue with 0 packages.
-    pub fn new() -> DependencyQueue<K, V> {
+    pub fn new() -> DependencyQueue<K, E, V> {
         DependencyQueue {
             dep_map: HashMap::new(),
             reverse_dep_map: HashMap::new(),
-            pending: HashSet::new(),
             depth: HashMap::new(),
         }
     }
 
     /// Adds a new package to this dependency queue.
     ///
-    /// It is assumed that any dependencies of this package will eventually also
-    /// be added to the dependency queue.
-    pub fn queue(&mut self, key: &K, value: V, dependencies: &[K]) {
+    /// Each dependency is given together with the edge that must be finished
+    /// before this package can be built. It is assumed that any dependencies
+    /// of this package will eventually also be added to the dependency queue.
+    pub fn queue(&mut self, key: &K, value: V, dependencies: &[(K, E)]) {
         assert!(!self.dep_map.contains_key(key));
 
         let mut my_dependencies = HashSet::new();
-        for dep in dependencies {
-            my_dependencies.insert(dep.clone());
-            let rev = self
-                .reverse_dep_map
+        for (dep, edge) in dependencies {
+            my_dependencies.insert((dep.clone(), edge.clone()));
+            self.reverse_dep_map
                 .entry(dep.clone())
-                .or_insert_with(HashSet::new);
-            rev.insert(key.clone());
+                .or_insert_with(HashMap::new)
+                .entry(edge.clone())
+                .or_insert_with(HashSet::new)
+                .insert(key.clone());
         }
         self.dep_map.insert(key.clone(), (my_dependencies, value));
     }
@@ -74,9 +76,9 @@ impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
             depth(key, &self.reverse_dep_map, &mut self.depth);
         }
 
-        fn depth<K: Hash + Eq + Clone>(
+        fn depth<K: Hash + Eq + Clone, E: Hash + Eq + Clone>(
             key: &K,
-            map: &HashMap<K, HashSet<K>>,
+            map: &HashMap<K, HashMap<E, HashSet<K>>>,
             results: &mut HashMap<K, usize>,
         ) -> usize {
             const IN_PROGRESS: usize = !0;
@@ -91,7 +93,8 @@ impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
             let depth = 1 + map
                 .get(key)
                 .into_iter()
-                .flat_map(|it| it)
+                .flat_map(|it| it.values())
+                .flat_map(|set| set)
                 .map(|dep| depth(dep, map, results))
                 .max()
                 .unwrap_or(0);
@@ -128,32 +131,33 @@ impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
             None => return None,
         };
         let (_, data) = self.dep_map.remove(&key).unwrap();
-        self.pending.insert(key.clone());
         Some((key, data))
     }
 
-    /// Returns `true` if there are remaining packages to be built.
+    /// Returns `true` if there are remaining packages which haven't been
+    /// dequeued yet.
     pub fn is_empty(&self) -> bool {
-        self.dep_map.is_empty() && self.pending.is_empty()
+        self.dep_map.is_empty()
     }
 
-    /// Returns the number of remaining packages to be built.
+    /// Returns the number of remaining packages which haven't been dequeued
+    /// yet.
     pub fn len(&self) -> usize {
-        self.dep_map.len() + self.pending.len()
+        self.dep_map.len()
     }
 
-    /// Indicate that a package has been built.
+    /// Indicate that the `edge` of a dequeued package has been built.
     ///
     /// This function will update the dependency queue with this information,
     /// possibly allowing the next invocation of `dequeue` to return a package.
-    pub fn finish(&mut self, key: &K) {
-        assert!(self.pending.remove(key));
-        let reverse_deps = match self.reverse_dep_map.get(key) {
+    pub fn finish(&mut self, key: &K, edge: &E) {
+        let reverse_deps = match self.reverse_dep_map.get(key).and_then(|map| map.get(edge)) {
             Some(deps) => deps,
             None => return,
         };
+        let key = (key.clone(), edge.clone());
         for dep in reverse_deps.iter() {
-            assert!(self.dep_map.get_mut(dep).unwrap().0.remove(key));
+            assert!(self.dep_map.get_mut(dep).unwrap().0.remove(&key));
         }
     }
 }
@@ -167,24 +171,42 @@ mod test {
         let mut q = DependencyQueue::new();
 
         q.queue(&1, (), &[]);
-        q.queue(&2, (), &[1]);
+        q.queue(&2, (), &[(1, ())]);
         q.queue(&3, (), &[]);
-        q.queue(&4, (), &[2, 3]);
-        q.queue(&5, (), &[4, 3]);
+        q.queue(&4, (), &[(2, ()), (3, ())]);
+        q.queue(&5, (), &[(4, ()), (3, ())]);
         q.queue_finished();
 
         assert_eq!(q.dequeue(), Some((1, ())));
         assert_eq!(q.dequeue(), Some((3, ())));
         assert_eq!(q.dequeue(), None);
-        q.finish(&3);
+        q.finish(&3, &());
         assert_eq!(q.dequeue(), None);
-        q.finish(&1);
+        q.finish(&1, &());
         assert_eq!(q.dequeue(), Some((2, ())));
         assert_eq!(q.dequeue(), None);
-        q.finish(&2);
+        q.finish(&2, &());
         assert_eq!(q.dequeue(), Some((4, ())));
         assert_eq!(q.dequeue(), None);
-        q.finish(&4);
+        q.finish(&4, &());
         assert_eq!(q.dequeue(), Some((5, ())));
     }
+
+    #[test]
+    fn edges() {
+        let mut q = DependencyQueue::new();
+
+        q.queue(&1, (), &[]);
+        q.queue(&2, (), &[(1, "meta")]);
+        q.queue(&3, (), &[(1, "all")]);
+        q.queue_finished();
+
+        assert_eq!(q.dequeue(), Some((1, ())));
+        assert_eq!(q.dequeue(), None);
+        q.finish(&1, &"meta");
+        assert_eq!(q.dequeue(), Some((2, ())));
+        assert_eq!(q.dequeue(), None);
+        q.finish(&1, &"all");
+        assert_eq!(q.dequeue(), Some((3, ())));
+    }
 }
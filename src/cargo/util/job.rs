@@ -14,6 +14,15 @@
 //! Conveniently whenever a process in the job object spawns a new process the
 //! child will be associated with the job object as well. This means if we add
 //! ourselves to the job object we create then everything will get torn down!
+//!
+//! This module also takes care of killing processes which run for too long,
+//! such as hung tests.
+
+use std::io;
+use std::process::Command;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 pub use self::imp::Setup;
 
@@ -21,13 +30,75 @@ pub fn setup() -> Option<Setup> {
     unsafe { imp::setup() }
 }
 
+/// Makes a command start in a process group of its own, so that the processes
+/// it spawns can be killed along with it by `kill_process_group`.
+///
+/// On Unix the process group doesn't get the signal of Ctrl-C in a terminal,
+/// so cargo forwards it to the groups which are watched by a `Watchdog`.
+pub fn new_process_group(cmd: &mut Command) {
+    imp::new_process_group(cmd)
+}
+
+/// Kills a process started with `new_process_group`, and on Unix the
+/// processes it spawned.
+pub fn kill_process_group(pid: u32) -> io::Result<()> {
+    imp::kill_process_group(pid)
+}
+
+/// Kills a process group if it's still running once a timeout has passed.
+pub struct Watchdog {
+    pid: u32,
+    done: Sender<()>,
+    thread: JoinHandle<bool>,
+}
+
+impl Watchdog {
+    pub fn new(pid: u32, timeout: Duration) -> Watchdog {
+        imp::watch(pid);
+        let (done, rx) = channel();
+        let thread = thread::spawn(move || match rx.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = kill_process_group(pid);
+                true
+            }
+            _ => false,
+        });
+        Watchdog { pid, done, thread }
+    }
+
+    /// Stops watching once the process has exited, returning whether it was
+    /// killed for running for too long.
+    pub fn finish(self) -> bool {
+        let Watchdog { pid, done, thread } = self;
+        imp::unwatch(pid);
+        drop(done);
+        thread.join().unwrap_or(false)
+    }
+}
+
 #[cfg(unix)]
 mod imp {
     use libc;
+    use std::collections::HashSet;
     use std::env;
+    use std::io;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Mutex, Once};
+    use std::thread;
 
     pub type Setup = ();
 
+    lazy_static::lazy_static! {
+        /// The process groups which Ctrl-C is forwarded to.
+        static ref GROUPS: Mutex<HashSet<u32>> = Mutex::new(HashSet::new());
+    }
+
+    /// The write end of the pipe which the SIGINT handler wakes the
+    /// forwarding thread up with.
+    static INTERRUPT_PIPE: AtomicUsize = AtomicUsize::new(0);
+
     pub unsafe fn setup() -> Option<()> {
         // There's a test case for the behavior of
         // when-cargo-is-killed-subprocesses-are-also-killed, but that requires
@@ -38,12 +109,85 @@ mod imp {
         }
         Some(())
     }
+
+    pub fn new_process_group(cmd: &mut Command) {
+        forward_interrupts();
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setpgid(0, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    pub fn kill_process_group(pid: u32) -> io::Result<()> {
+        // The process is the leader of its group, whose id is its own.
+        if unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn watch(pid: u32) {
+        GROUPS.lock().unwrap().insert(pid);
+    }
+
+    pub fn unwatch(pid: u32) {
+        GROUPS.lock().unwrap().remove(&pid);
+    }
+
+    /// Installs a SIGINT handler which sends the signal on to the watched
+    /// process groups, and then lets cargo be interrupted as it would have
+    /// been without it.
+    ///
+    /// Little can be done in a signal handler, so it only writes to a pipe,
+    /// and the forwarding is done by a thread reading from it.
+    fn forward_interrupts() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| unsafe {
+            let mut fds = [0; 2];
+            if libc::pipe(fds.as_mut_ptr()) == -1 {
+                return;
+            }
+            let read = fds[0];
+            INTERRUPT_PIPE.store(fds[1] as usize, Ordering::SeqCst);
+            thread::spawn(move || {
+                let mut buf = [0u8; 1];
+                loop {
+                    match libc::read(read, buf.as_mut_ptr() as *mut libc::c_void, 1) {
+                        1 => break,
+                        -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {}
+                        _ => return,
+                    }
+                }
+                for &pid in GROUPS.lock().unwrap().iter() {
+                    libc::kill(-(pid as libc::pid_t), libc::SIGINT);
+                }
+                libc::signal(libc::SIGINT, libc::SIG_DFL);
+                libc::raise(libc::SIGINT);
+            });
+            libc::signal(
+                libc::SIGINT,
+                on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        });
+    }
+
+    extern "C" fn on_interrupt(_signal: libc::c_int) {
+        let fd = INTERRUPT_PIPE.load(Ordering::SeqCst) as libc::c_int;
+        unsafe {
+            libc::write(fd, b"\0".as_ptr() as *const libc::c_void, 1);
+        }
+    }
 }
 
 #[cfg(windows)]
 mod imp {
     use std::io;
     use std::mem;
+    use std::process::Command;
     use std::ptr;
 
     use log::info;
@@ -132,6 +276,30 @@ mod imp {
         }
     }
 
+    pub fn new_process_group(_cmd: &mut Command) {
+        // Processes are killed on their own on Windows.
+    }
+
+    pub fn watch(_pid: u32) {
+        // The processes get Ctrl-C, or are killed along with the job.
+    }
+
+    pub fn unwatch(_pid: u32) {}
+
+    pub fn kill_process_group(pid: u32) -> io::Result<()> {
+        unsafe {
+            let process = OpenProcess(PROCESS_TERMINATE, FALSE, pid);
+            if process.is_null() {
+                return Err(last_err());
+            }
+            let process = Handle { inner: process };
+            if TerminateProcess(process.inner, 1) == 0 {
+                return Err(last_err());
+            }
+        }
+        Ok(())
+    }
+
     impl Drop for Handle {
         fn drop(&mut self) {
             unsafe {
@@ -0,0 +1,167 @@
+//! Writing of test results in the JUnit XML format, which most CI services
+//! know how to display.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+/// The outcome of a single test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// A single test, which JUnit calls a test case.
+#[derive(Debug)]
+pub struct TestCase {
+    pub name: String,
+    pub outcome: TestOutcome,
+    /// What the test printed, which is only reported if it failed.
+    pub output: Option<String>,
+}
+
+/// The tests of a test binary, which JUnit calls a test suite.
+#[derive(Debug)]
+pub struct TestSuite {
+    pub name: String,
+    pub time: Duration,
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    fn count(&self, outcome: TestOutcome) -> usize {
+        self.cases.iter().filter(|c| c.outcome == outcome).count()
+    }
+}
+
+/// Renders the results of the test suites as a JUnit XML document.
+pub fn to_xml(suites: &[TestSuite]) -> String {
+    let count = |outcome| suites.iter().map(|s| s.count(outcome)).sum::<usize>();
+    let tests = suites.iter().map(|s| s.cases.len()).sum::<usize>();
+    let time = suites.iter().map(|s| s.time).sum::<Duration>();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"cargo test\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">",
+        tests,
+        count(TestOutcome::Failed),
+        count(TestOutcome::Ignored),
+        seconds(time),
+    );
+    for suite in suites {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">",
+            escape(&suite.name),
+            suite.cases.len(),
+            suite.count(TestOutcome::Failed),
+            suite.count(TestOutcome::Ignored),
+            seconds(suite.time),
+        );
+        for case in &suite.cases {
+            let attrs = format!(
+                "name=\"{}\" classname=\"{}\"",
+                escape(&case.name),
+                escape(&suite.name)
+            );
+            match case.outcome {
+                TestOutcome::Passed => {
+                    let _ = writeln!(xml, "    <testcase {}/>", attrs);
+                }
+                TestOutcome::Ignored => {
+                    let _ = writeln!(xml, "    <testcase {}>", attrs);
+                    xml.push_str("      <skipped/>\n");
+                    xml.push_str("    </testcase>\n");
+                }
+                TestOutcome::Failed => {
+                    let _ = writeln!(xml, "    <testcase {}>", attrs);
+                    let _ = writeln!(
+                        xml,
+                        "      <failure message=\"test failed\">{}</failure>",
+                        escape(case.output.as_ref().map(|s| s.as_str()).unwrap_or(""))
+                    );
+                    xml.push_str("    </testcase>\n");
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn seconds(duration: Duration) -> String {
+    format!(
+        "{}.{:03}",
+        duration.as_secs(),
+        duration.subsec_nanos() / 1_000_000
+    )
+}
+
+/// Escapes text for use in attributes and elements, dropping the control
+/// characters which XML doesn't allow, such as those of ANSI escapes.
+fn escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            '\n' | '\r' | '\t' => ret.push(c),
+            c if c.is_control() => {}
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use super::{to_xml, TestCase, TestOutcome, TestSuite};
+    use std::time::Duration;
+
+    #[test]
+    fn suites() {
+        let suites = vec![TestSuite {
+            name: "foo (lib)".to_string(),
+            time: Duration::from_millis(1500),
+            cases: vec![
+                TestCase {
+                    name: "passes".to_string(),
+                    outcome: TestOutcome::Passed,
+                    output: None,
+                },
+                TestCase {
+                    name: "fails".to_string(),
+                    outcome: TestOutcome::Failed,
+                    output: Some("assertion `a < b` failed\u{1b}[0m".to_string()),
+                },
+                TestCase {
+                    name: "ignored".to_string(),
+                    outcome: TestOutcome::Ignored,
+                    output: None,
+                },
+            ],
+        }];
+        assert_eq!(
+            to_xml(&suites),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="cargo test" tests="3" failures="1" skipped="1" time="1.500">
+  <testsuite name="foo (lib)" tests="3" failures="1" skipped="1" time="1.500">
+    <testcase name="passes" classname="foo (lib)"/>
+    <testcase name="fails" classname="foo (lib)">
+      <failure message="test failed">assertion `a &lt; b` failed[0m</failure>
+    </testcase>
+    <testcase name="ignored" classname="foo (lib)">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -1,10 +1,11 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use semver::Version;
 use serde::ser;
 use serde::Serialize;
 use serde_json::{self, json, value::RawValue};
 
-use crate::core::{PackageId, Target};
+use crate::core::{PackageId, SourceId, Target};
 
 pub trait Message: ser::Serialize {
     fn reason(&self) -> &str;
@@ -34,6 +35,9 @@ impl<'a> Message for FromCompiler<'a> {
 pub struct Artifact<'a> {
     pub package_id: PackageId,
     pub target: &'a Target,
+    /// The triple the artifact was compiled for, which is the host's for
+    /// build scripts and procedural macros.
+    pub target_triple: &'a str,
     pub profile: ArtifactProfile,
     pub features: Vec<String>,
     pub filenames: Vec<PathBuf>,
@@ -73,3 +77,106 @@ impl<'a> Message for BuildScript<'a> {
         "build-script-executed"
     }
 }
+
+#[derive(Serialize)]
+pub struct PackageFile<'a> {
+    pub package_id: PackageId,
+    pub path: &'a Path,
+    pub source: &'a str,
+    pub size: Option<u64>,
+    pub included: bool,
+    pub rule: Option<&'a str>,
+}
+
+impl<'a> Message for PackageFile<'a> {
+    fn reason(&self) -> &str {
+        "package-file"
+    }
+}
+
+#[derive(Serialize)]
+pub struct TestBinaryStarted<'a> {
+    pub package_id: PackageId,
+    pub target: &'a Target,
+    pub executable: &'a Path,
+}
+
+impl<'a> Message for TestBinaryStarted<'a> {
+    fn reason(&self) -> &str {
+        "test-binary-started"
+    }
+}
+
+#[derive(Serialize)]
+pub struct TestBinaryFinished<'a> {
+    pub package_id: PackageId,
+    pub target: &'a Target,
+    pub executable: &'a Path,
+    pub success: bool,
+    /// The exit code, which is missing if the binary was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// How long the binary ran, in seconds.
+    pub duration: f64,
+}
+
+impl<'a> Message for TestBinaryFinished<'a> {
+    fn reason(&self) -> &str {
+        "test-binary-finished"
+    }
+}
+
+/// An event of libtest's JSON output, such as a test starting or failing.
+#[derive(Serialize)]
+pub struct FromTest<'a> {
+    pub package_id: PackageId,
+    pub target: &'a Target,
+    pub message: Box<RawValue>,
+}
+
+impl<'a> Message for FromTest<'a> {
+    fn reason(&self) -> &str {
+        "test-message"
+    }
+}
+
+/// A package which `cargo update` adds to, removes from or changes in the
+/// lock file.
+#[derive(Serialize)]
+pub struct LockfileChange<'a> {
+    pub name: &'a str,
+    /// One of `added`, `removed`, `upgraded`, `downgraded`, `updated` (for a
+    /// new revision of the same version) or `source-changed`.
+    pub change: &'a str,
+    pub old_version: Option<&'a Version>,
+    pub new_version: Option<&'a Version>,
+    pub old_source: Option<SourceId>,
+    pub new_source: Option<SourceId>,
+}
+
+impl<'a> Message for LockfileChange<'a> {
+    fn reason(&self) -> &str {
+        "lockfile-change"
+    }
+}
+
+/// A dependency which `cargo outdated` found to have newer versions in its
+/// registry, or whose locked version was yanked.
+#[derive(Serialize)]
+pub struct OutdatedDependency<'a> {
+    pub package_id: PackageId,
+    pub name: &'a str,
+    /// How far the dependency is from the packages checked, where 1 is a
+    /// direct dependency.
+    pub depth: u32,
+    pub locked_version: &'a Version,
+    pub yanked: bool,
+    /// The newest version which every requirement on the dependency allows.
+    pub compatible_version: Option<&'a Version>,
+    pub latest_version: Option<&'a Version>,
+}
+
+impl<'a> Message for OutdatedDependency<'a> {
+    fn reason(&self) -> &str {
+        "outdated-dependency"
+    }
+}
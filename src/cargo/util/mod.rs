@@ -39,6 +39,7 @@ pub mod graph;
 pub mod hex;
 pub mod important_paths;
 pub mod job;
+pub mod junit;
 pub mod lev_distance;
 mod lockserver;
 pub mod machine_message;
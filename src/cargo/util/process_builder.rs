@@ -3,13 +3,15 @@ use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::path::Path;
-use std::process::{Command, Output, Stdio};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::time::Duration;
 
 use failure::Fail;
 use jobserver::Client;
 use shell_escape::escape;
 
-use crate::util::{process_error, read2, CargoResult, CargoResultExt};
+use crate::util::job::Watchdog;
+use crate::util::{job, process_error, read2, CargoResult, CargoResultExt, ProcessError};
 
 /// A builder object for an external process, similar to `std::process::Command`.
 #[derive(Clone, Debug)]
@@ -29,6 +31,8 @@ pub struct ProcessBuilder {
     jobserver: Option<Client>,
     /// `true` to include environment variable in display.
     display_env_vars: bool,
+    /// How long the process may run for before it's killed.
+    timeout: Option<Duration>,
 }
 
 impl fmt::Display for ProcessBuilder {
@@ -143,6 +147,36 @@ impl ProcessBuilder {
         self
     }
 
+    /// (chainable) Kills the process, and the processes it spawned, if it runs
+    /// for longer than `timeout`. The process runs in a process group of its
+    /// own.
+    pub fn timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Gets how long the process may run for before it's killed.
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Starts killing the process once it ran for too long, if it has a
+    /// timeout.
+    pub fn watchdog(&self, child: &Child) -> Option<Watchdog> {
+        self.timeout
+            .map(|timeout| Watchdog::new(child.id(), timeout))
+    }
+
+    /// The error for a process which was killed for running for too long.
+    pub fn timed_out_error(&self, status: ExitStatus, output: Option<&Output>) -> ProcessError {
+        let timeout = self.timeout.map_or(0, |t| t.as_secs());
+        process_error(
+            &format!("process timed out after {} seconds: {}", timeout, self),
+            Some(status),
+            output,
+        )
+    }
+
     /// Enables environment variable display.
     pub fn display_env_vars(&mut self) -> &mut Self {
         self.display_env_vars = true;
@@ -152,11 +186,22 @@ impl ProcessBuilder {
     /// Runs the process, waiting for completion, and mapping non-success exit codes to an error.
     pub fn exec(&self) -> CargoResult<()> {
         let mut command = self.build_command();
-        let exit = command.status().chain_err(|| {
-            process_error(&format!("could not execute process {}", self), None, None)
-        })?;
+        let mut timed_out = false;
+        let exit = command
+            .spawn()
+            .and_then(|mut child| {
+                let watchdog = self.watchdog(&child);
+                let exit = child.wait();
+                timed_out = watchdog.map_or(false, |w| w.finish());
+                exit
+            })
+            .chain_err(|| {
+                process_error(&format!("could not execute process {}", self), None, None)
+            })?;
 
-        if exit.success() {
+        if timed_out {
+            Err(self.timed_out_error(exit, None).into())
+        } else if exit.success() {
             Ok(())
         } else {
             Err(process_error(
@@ -190,12 +235,27 @@ impl ProcessBuilder {
     /// Executes the process, returning the stdio output, or an error if non-zero exit status.
     pub fn exec_with_output(&self) -> CargoResult<Output> {
         let mut command = self.build_command();
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut timed_out = false;
+        let output = command
+            .spawn()
+            .and_then(|child| {
+                let watchdog = self.watchdog(&child);
+                let output = child.wait_with_output();
+                timed_out = watchdog.map_or(false, |w| w.finish());
+                output
+            })
+            .chain_err(|| {
+                process_error(&format!("could not execute process {}", self), None, None)
+            })?;
 
-        let output = command.output().chain_err(|| {
-            process_error(&format!("could not execute process {}", self), None, None)
-        })?;
-
-        if output.status.success() {
+        if timed_out {
+            Err(self.timed_out_error(output.status, Some(&output)).into())
+        } else if output.status.success() {
             Ok(output)
         } else {
             Err(process_error(
@@ -228,8 +288,10 @@ impl ProcessBuilder {
             .stdin(Stdio::null());
 
         let mut callback_error = None;
+        let mut timed_out = false;
         let status = (|| {
             let mut child = cmd.spawn()?;
+            let watchdog = self.watchdog(&child);
             let out = child.stdout.take().unwrap();
             let err = child.stderr.take().unwrap();
             read2(out, err, &mut |is_out, data, eof| {
@@ -270,7 +332,9 @@ impl ProcessBuilder {
                     data.drain(..idx);
                 }
             })?;
-            child.wait()
+            let status = child.wait();
+            timed_out = watchdog.map_or(false, |w| w.finish());
+            status
         })()
         .chain_err(|| process_error(&format!("could not execute process {}", self), None, None))?;
         let output = Output {
@@ -281,7 +345,9 @@ impl ProcessBuilder {
 
         {
             let to_print = if capture_output { Some(&output) } else { None };
-            if let Some(e) = callback_error {
+            if timed_out {
+                return Err(self.timed_out_error(output.status, to_print).into());
+            } else if let Some(e) = callback_error {
                 let cx = process_error(
                     &format!("failed to parse process output: {}", self),
                     Some(output.status),
@@ -324,6 +390,9 @@ impl ProcessBuilder {
         if let Some(ref c) = self.jobserver {
             c.configure(&mut command);
         }
+        if self.timeout.is_some() {
+            job::new_process_group(&mut command);
+        }
         command
     }
 }
@@ -337,6 +406,7 @@ pub fn process<T: AsRef<OsStr>>(cmd: T) -> ProcessBuilder {
         env: HashMap::new(),
         jobserver: None,
         display_env_vars: false,
+        timeout: None,
     }
 }
 
@@ -82,6 +82,14 @@ impl Rustc {
         util::process(&self.path)
     }
 
+    /// Gets the sysroot of the compiler, as printed by `rustc --print sysroot`.
+    pub fn sysroot(&self) -> CargoResult<PathBuf> {
+        let mut cmd = self.process_no_wrapper();
+        cmd.arg("--print").arg("sysroot");
+        let (output, _) = self.cached_output(&cmd)?;
+        Ok(PathBuf::from(output.trim()))
+    }
+
     pub fn cached_output(&self, cmd: &ProcessBuilder) -> CargoResult<(String, String)> {
         self.cache.lock().unwrap().cached_output(cmd)
     }
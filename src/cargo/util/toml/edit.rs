@@ -0,0 +1,300 @@
+//! Finding the parts of a manifest to change when rewriting it, so that the
+//! rest of it, such as comments and formatting, is left as it was.
+
+use std::ops::Range;
+
+use crate::core::dependency::Kind;
+
+/// The version requirement of a dependency, as written in a manifest.
+#[derive(Debug, PartialEq)]
+pub struct DependencyRequirement {
+    /// The kind of the table the dependency is listed in.
+    pub kind: Kind,
+    /// The name the dependency is listed under, which is the name of the
+    /// package unless it's renamed.
+    pub name: String,
+    /// The requirement, without quotes.
+    pub req: String,
+    /// The position of `req` in the manifest.
+    pub span: Range<usize>,
+}
+
+/// Finds the version requirements of the dependencies of a manifest, in the
+/// `[dependencies]` style tables, either top-level or for a target.
+///
+/// This only understands what is commonly written in manifests. Anything
+/// else, such as dotted keys, is left out.
+pub fn dependency_requirements(contents: &str) -> Vec<DependencyRequirement> {
+    let mut reqs = Vec::new();
+    // The table we're in, if it lists dependencies, along with the name of
+    // the dependency for tables like `[dependencies.foo]`.
+    let mut table: Option<(Kind, Option<String>)> = None;
+    let mut in_multiline_string = false;
+    let mut offset = 0;
+
+    for line in contents.split('\n') {
+        let start = offset;
+        offset += line.len() + 1;
+
+        let was_in_multiline_string = in_multiline_string;
+        for delim in &["\"\"\"", "'''"] {
+            if line.matches(delim).count() % 2 == 1 {
+                in_multiline_string = !in_multiline_string;
+            }
+        }
+        if was_in_multiline_string {
+            continue;
+        }
+
+        let mut p = Parser::new(line);
+        p.skip_whitespace();
+        if p.peek() == Some('[') {
+            table = dependency_table(&mut p);
+            continue;
+        }
+        let (kind, name) = match &table {
+            Some(table) => table,
+            None => continue,
+        };
+        let key = match p.key() {
+            Some(key) => key,
+            None => continue,
+        };
+        p.skip_whitespace();
+        if !p.eat('=') {
+            continue;
+        }
+        p.skip_whitespace();
+
+        let (name, span) = match name {
+            Some(name) if key == "version" => (name.clone(), p.string()),
+            Some(_) => continue,
+            None if p.peek() == Some('{') => (key, p.inline_table_version()),
+            None => (key, p.string()),
+        };
+        if let Some(span) = span {
+            reqs.push(DependencyRequirement {
+                kind: *kind,
+                name,
+                req: line[span.clone()].to_string(),
+                span: start + span.start..start + span.end,
+            });
+        }
+    }
+    reqs
+}
+
+/// Parses a table header, returning the kind of dependencies it lists, if
+/// any, and the name of the dependency for tables like `[dependencies.foo]`.
+fn dependency_table(p: &mut Parser<'_>) -> Option<(Kind, Option<String>)> {
+    // Arrays of tables, like `[[bin]]`, never list dependencies.
+    if !p.eat('[') || p.eat('[') {
+        return None;
+    }
+    let mut keys = Vec::new();
+    loop {
+        p.skip_whitespace();
+        keys.push(p.key()?);
+        p.skip_whitespace();
+        if p.eat(']') {
+            break;
+        }
+        if !p.eat('.') {
+            return None;
+        }
+    }
+
+    let keys = match keys.first().map(|s| s.as_str()) {
+        Some("target") if keys.len() > 2 => &keys[2..],
+        _ => &keys[..],
+    };
+    let kind = match keys[0].as_str() {
+        "dependencies" => Kind::Normal,
+        "dev-dependencies" | "dev_dependencies" => Kind::Development,
+        "build-dependencies" | "build_dependencies" => Kind::Build,
+        _ => return None,
+    };
+    match keys.len() {
+        1 => Some((kind, None)),
+        2 => Some((kind, Some(keys[1].clone()))),
+        _ => None,
+    }
+}
+
+/// Reads the parts of TOML found in a single line.
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Parser<'a> {
+        Parser { s, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.eat(' ') || self.eat('\t') {}
+    }
+
+    /// Reads a bare or quoted key.
+    fn key(&mut self) -> Option<String> {
+        match self.peek() {
+            Some('"') | Some('\'') => self.string().map(|span| self.s[span].to_string()),
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    None
+                } else {
+                    Some(self.s[start..self.pos].to_string())
+                }
+            }
+        }
+    }
+
+    /// Reads a basic or literal string, returning the position of its
+    /// contents.
+    fn string(&mut self) -> Option<Range<usize>> {
+        let quote = self.peek().filter(|&c| c == '"' || c == '\'')?;
+        self.pos += 1;
+        let start = self.pos;
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+            if c == quote && !escaped {
+                return Some(start..self.pos - 1);
+            }
+            escaped = quote == '"' && c == '\\' && !escaped;
+        }
+        None
+    }
+
+    /// Skips over a value, including any arrays or inline tables in it.
+    fn skip_value(&mut self) {
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                '"' | '\'' => {
+                    if self.string().is_none() {
+                        return;
+                    }
+                    continue;
+                }
+                '[' | '{' => depth += 1,
+                ']' | '}' if depth == 0 => return,
+                ']' | '}' => depth -= 1,
+                ',' if depth == 0 => return,
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Reads an inline table, returning the position of the contents of its
+    /// `version` string.
+    fn inline_table_version(&mut self) -> Option<Range<usize>> {
+        if !self.eat('{') {
+            return None;
+        }
+        loop {
+            self.skip_whitespace();
+            if self.eat('}') {
+                return None;
+            }
+            let key = self.key()?;
+            self.skip_whitespace();
+            if !self.eat('=') {
+                return None;
+            }
+            self.skip_whitespace();
+            if key == "version" {
+                return self.string();
+            }
+            self.skip_value();
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reqs(contents: &str) -> Vec<(Kind, String, &str)> {
+        dependency_requirements(contents)
+            .into_iter()
+            .map(|r| {
+                assert_eq!(&contents[r.span.clone()], r.req);
+                (r.kind, r.name, &contents[r.span])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_requirements() {
+        let contents = r#"
+[package]
+name = "foo"
+version = "0.1.0"
+description = """
+[dependencies]
+fake = "1.0"
+"""
+
+[dependencies]
+a = "1.0" # a comment
+"b" = { version = '0.2', features = ["x", "y,z"] }
+c = { path = "c" }
+d = { git = "https://example.com/d", version = "0.4" }
+
+[dependencies.e]
+features = ["x"]
+version = "~0.5"
+
+[target.'cfg(unix)'.dev-dependencies]
+f = { default-features = false, version = "^0.6" }
+
+[target."x86_64-pc-windows-gnu".build-dependencies.g]
+version = "=0.7"
+
+[[bin]]
+name = "h"
+version = "0.8"
+
+[features]
+i = "0.9"
+"#;
+        assert_eq!(
+            reqs(contents),
+            vec![
+                (Kind::Normal, "a".to_string(), "1.0"),
+                (Kind::Normal, "b".to_string(), "0.2"),
+                (Kind::Normal, "d".to_string(), "0.4"),
+                (Kind::Normal, "e".to_string(), "~0.5"),
+                (Kind::Development, "f".to_string(), "^0.6"),
+                (Kind::Build, "g".to_string(), "=0.7"),
+            ]
+        );
+    }
+}
@@ -13,18 +13,22 @@ use serde::ser;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::core::dependency::{Kind, Platform};
+use crate::core::dependency::{Artifact, ArtifactKind, Kind, Platform};
 use crate::core::manifest::{LibKind, ManifestMetadata, TargetSourcePath, Warnings};
 use crate::core::profiles::Profiles;
 use crate::core::{Dependency, Manifest, PackageId, Summary, Target};
 use crate::core::{Edition, EitherManifest, Feature, Features, VirtualManifest};
-use crate::core::{GitReference, PackageIdSpec, SourceId, WorkspaceConfig, WorkspaceRootConfig};
+use crate::core::{
+    GitReference, GitSubmodules, PackageIdSpec, SourceId, WorkspaceConfig, WorkspaceRootConfig,
+};
 use crate::sources::{CRATES_IO_INDEX, CRATES_IO_REGISTRY};
 use crate::util::errors::{CargoResult, CargoResultExt, ManifestError};
 use crate::util::paths;
 use crate::util::{self, validate_package_name, Config, ToUrl};
 
+mod edit;
 mod targets;
+pub use self::edit::{dependency_requirements, DependencyRequirement};
 use self::targets::targets;
 
 pub fn read_manifest(
@@ -233,6 +237,7 @@ pub struct DetailedTomlDependency {
     branch: Option<String>,
     tag: Option<String>,
     rev: Option<String>,
+    submodules: Option<VecStringOrBool>,
     features: Option<Vec<String>>,
     optional: Option<bool>,
     default_features: Option<bool>,
@@ -240,6 +245,9 @@ pub struct DetailedTomlDependency {
     default_features2: Option<bool>,
     package: Option<String>,
     public: Option<bool>,
+    artifact: Option<String>,
+    target: Option<String>,
+    lib: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -673,6 +681,10 @@ pub struct TomlProject {
     namespaced_features: Option<bool>,
     #[serde(rename = "default-run")]
     default_run: Option<String>,
+    #[serde(rename = "forced-target")]
+    forced_target: Option<String>,
+    #[serde(rename = "default-target")]
+    default_target: Option<String>,
 
     // Package metadata.
     description: Option<String>,
@@ -1047,6 +1059,31 @@ impl TomlManifest {
             )
         }
 
+        if project.forced_target.is_some() && project.default_target.is_some() {
+            bail!("only one of `forced-target` or `default-target` can be specified")
+        }
+        // Target specification files are looked up relative to the package.
+        let target_path = |target: &String| -> CargoResult<String> {
+            if !target.ends_with(".json") {
+                return Ok(target.clone());
+            }
+            package_root
+                .join(target)
+                .into_os_string()
+                .into_string()
+                .map_err(|_| failure::format_err!("target path is not valid unicode"))
+        };
+        let forced_target = project
+            .forced_target
+            .as_ref()
+            .map(&target_path)
+            .transpose()?;
+        let default_target = project
+            .default_target
+            .as_ref()
+            .map(&target_path)
+            .transpose()?;
+
         let custom_metadata = project.metadata.clone();
         let mut manifest = Manifest::new(
             summary,
@@ -1066,6 +1103,8 @@ impl TomlManifest {
             edition,
             project.im_a_teapot,
             project.default_run.clone(),
+            forced_target,
+            default_target,
             Rc::clone(me),
             project.metabuild.clone().map(|sov| sov.0),
         );
@@ -1347,6 +1386,14 @@ impl DetailedTomlDependency {
                     cx.warnings.push(msg)
                 }
             }
+            if self.submodules.is_some() {
+                let msg = format!(
+                    "key `submodules` is ignored for dependency ({}). \
+                     This will be considered an error in future versions",
+                    name_in_toml
+                );
+                cx.warnings.push(msg)
+            }
         }
 
         let new_source_id = match (
@@ -1398,8 +1445,21 @@ impl DetailedTomlDependency {
                     .or_else(|| self.tag.clone().map(GitReference::Tag))
                     .or_else(|| self.rev.clone().map(GitReference::Rev))
                     .unwrap_or_else(|| GitReference::Branch("master".to_string()));
+                let submodules = match self.submodules {
+                    Some(ref submodules) => {
+                        cx.features.require(Feature::git_submodules())?;
+                        match *submodules {
+                            VecStringOrBool::Bool(true) => GitSubmodules::All,
+                            VecStringOrBool::Bool(false) => GitSubmodules::None,
+                            VecStringOrBool::VecString(ref paths) => {
+                                GitSubmodules::paths(paths.clone())
+                            }
+                        }
+                    }
+                    None => GitSubmodules::All,
+                };
                 let loc = git.to_url()?;
-                SourceId::for_git(&loc, reference)?
+                SourceId::for_git(&loc, reference)?.with_git_submodules(submodules)
             }
             (None, Some(path), _, _) => {
                 cx.nested_paths.push(PathBuf::from(path));
@@ -1472,6 +1532,29 @@ impl DetailedTomlDependency {
 
             dep.set_public(p);
         }
+
+        match &self.artifact {
+            Some(artifact) => {
+                cx.features.require(Feature::artifact_dependencies())?;
+                let kind = artifact.parse::<ArtifactKind>().chain_err(|| {
+                    format!(
+                        "failed to parse `artifact` of dependency `{}`",
+                        name_in_toml
+                    )
+                })?;
+                let target = self.target.as_ref().map(|s| s.as_str());
+                dep.set_artifact(Some(Artifact::new(kind, target, self.lib.unwrap_or(false))));
+            }
+            None => {
+                if self.target.is_some() || self.lib.is_some() {
+                    bail!(
+                        "the `target` and `lib` keys of dependency `{}` \
+                         can only be used together with `artifact`",
+                        name_in_toml
+                    );
+                }
+            }
+        }
         Ok(dep)
     }
 }
@@ -1501,6 +1584,7 @@ struct TomlTarget {
     #[serde(rename = "required-features")]
     required_features: Option<Vec<String>>,
     edition: Option<String>,
+    timeout: Option<u64>,
 }
 
 #[derive(Clone)]
@@ -780,6 +780,12 @@ fn configure(features: &Features, toml: &TomlTarget, target: &mut Target) -> Car
                 .chain_err(|| "failed to parse the `edition` key")?,
         );
     }
+    if let Some(timeout) = toml.timeout {
+        features
+            .require(Feature::test_timeout())
+            .chain_err(|| "the `timeout` key is unstable")?;
+        target.set_test_timeout(Some(timeout));
+    }
     Ok(())
 }
 
@@ -0,0 +1,333 @@
+use crate::support::{basic_bin_manifest, basic_manifest, project, rustc_host};
+
+#[test]
+fn artifact_dependencies_unstable() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar", artifact = "bin" }
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  feature `artifact-dependencies` is required
+
+consider adding `cargo-features = [\"artifact-dependencies\"]` to the manifest
+",
+        )
+        .run();
+}
+
+#[test]
+fn invalid_artifact_kind() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["artifact-dependencies"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar", artifact = "rlib" }
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  failed to parse `artifact` of dependency `bar`
+
+Caused by:
+  unknown artifact kind `rlib`, expected one of `bin`, `cdylib` or `staticlib`
+",
+        )
+        .run();
+}
+
+#[test]
+fn target_requires_artifact() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar", target = "x86_64-unknown-linux-gnu" }
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  the `target` and `lib` keys of dependency `bar` can only be used together with `artifact`
+",
+        )
+        .run();
+}
+
+#[test]
+fn missing_artifact_target() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["artifact-dependencies"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar", artifact = "cdylib" }
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] package `foo v0.0.1 ([CWD])` depends on `bar` with `artifact = \"cdylib\"`, \
+but `bar` has no such target
+",
+        )
+        .run();
+}
+
+#[test]
+fn bin_artifact_for_integration_test() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["artifact-dependencies"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dev-dependencies]
+            bar = { path = "bar", artifact = "bin" }
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "tests/t.rs",
+            r#"
+            use std::process::Command;
+
+            #[test]
+            fn runs_bar() {
+                let out = Command::new(env!("CARGO_BIN_FILE_BAR_bar")).output().unwrap();
+                assert_eq!(String::from_utf8(out.stdout).unwrap(), "hello from bar\n");
+            }
+        "#,
+        )
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file(
+            "bar/src/main.rs",
+            r#"fn main() { println!("hello from bar"); }"#,
+        )
+        .build();
+
+    p.cargo("test -v")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar bar/src/main.rs [..]`")
+        .with_stderr_line_without(&["[RUNNING] `rustc --crate-name t "], &["--extern bar"])
+        .with_stdout_contains("test runs_bar ... ok")
+        .run();
+
+    // Binaries of dependencies are not uplifted next to the package's own.
+    assert!(!p.bin("bar").is_file());
+}
+
+#[test]
+fn bin_artifact_for_build_script() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["artifact-dependencies"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [build-dependencies]
+            tool = { path = "tool", artifact = "bin" }
+        "#,
+        )
+        .file(
+            "build.rs",
+            r#"
+            use std::env;
+            use std::process::Command;
+
+            fn main() {
+                let tool = env::var("CARGO_BIN_FILE_TOOL_tool").unwrap();
+                let out = Command::new(tool).output().unwrap();
+                let out = String::from_utf8(out.stdout).unwrap();
+                println!("cargo:rustc-env=TOOL_OUTPUT={}", out.trim());
+            }
+        "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { assert_eq!(env!("TOOL_OUTPUT"), "generated"); }"#,
+        )
+        .file("tool/Cargo.toml", &basic_bin_manifest("tool"))
+        .file(
+            "tool/src/main.rs",
+            r#"fn main() { println!("generated"); }"#,
+        )
+        .build();
+
+    p.cargo("build").masquerade_as_nightly_cargo().run();
+    p.process(&p.bin("foo")).run();
+
+    // The artifact is needed even if the package is only checked.
+    p.cargo("clean").masquerade_as_nightly_cargo().run();
+    p.cargo("check").masquerade_as_nightly_cargo().run();
+}
+
+#[test]
+fn cdylib_artifact_with_lib() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["artifact-dependencies"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar", artifact = "cdylib", lib = true }
+        "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"
+            fn main() {
+                bar::hello();
+                assert!(std::path::Path::new(env!("CARGO_CDYLIB_FILE_BAR")).is_file());
+            }
+        "#,
+        )
+        .file(
+            "bar/Cargo.toml",
+            r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [lib]
+            crate-type = ["rlib", "cdylib"]
+        "#,
+        )
+        .file("bar/src/lib.rs", "pub fn hello() {}")
+        .build();
+
+    p.cargo("run").masquerade_as_nightly_cargo().run();
+}
+
+#[test]
+fn artifact_for_explicit_target() {
+    let host = rustc_host();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                cargo-features = ["artifact-dependencies"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = {{ path = "bar", artifact = "bin", target = "{}" }}
+            "#,
+                host
+            ),
+        )
+        .file(
+            "src/main.rs",
+            &format!(
+                r#"
+                fn main() {{
+                    let bar = env!("CARGO_BIN_FILE_BAR_bar");
+                    assert!(bar.contains("{}"));
+                    assert!(std::path::Path::new(bar).is_file());
+                }}
+            "#,
+                host
+            ),
+        )
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("run -v")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains(format!(
+            "[RUNNING] `rustc --crate-name bar [..]--target {} [..]`",
+            host
+        ))
+        .run();
+}
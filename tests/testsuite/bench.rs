@@ -1615,6 +1615,7 @@ fn json_artifact_includes_executable_for_benchmark() {
                 "filenames": [ "[..]/foo/target/release/benchmark-[..][EXE]" ],
                 "fresh": false,
                 "package_id": "foo 0.0.1 ([..])",
+                "target_triple": "[..]",
                 "profile": "{...}",
                 "reason": "compiler-artifact",
                 "target": {
@@ -3056,6 +3056,7 @@ fn compiler_json_error_format() {
             "name":"build-script-build",
             "src_path":"[..]build.rs"
         },
+        "target_triple": "[..]",
         "profile": {
             "debug_assertions": true,
             "debuginfo": 2,
@@ -3084,6 +3085,7 @@ fn compiler_json_error_format() {
 
     {
         "reason":"compiler-artifact",
+        "target_triple": "[..]",
         "profile": {
             "debug_assertions": true,
             "debuginfo": 2,
@@ -3137,6 +3139,7 @@ fn compiler_json_error_format() {
             "name":"foo",
             "src_path":"[..]main.rs"
         },
+        "target_triple": "[..]",
         "profile": {
             "debug_assertions": true,
             "debuginfo": 2,
@@ -3168,6 +3171,7 @@ fn compiler_json_error_format() {
             "name":"build-script-build",
             "src_path":"[..]build.rs"
         },
+        "target_triple": "[..]",
         "profile": {
             "debug_assertions": true,
             "debuginfo": 2,
@@ -3183,6 +3187,7 @@ fn compiler_json_error_format() {
 
     {
         "reason":"compiler-artifact",
+        "target_triple": "[..]",
         "profile": {
             "debug_assertions": true,
             "debuginfo": 2,
@@ -3223,6 +3228,7 @@ fn compiler_json_error_format() {
             "name":"foo",
             "src_path":"[..]main.rs"
         },
+        "target_triple": "[..]",
         "profile": {
             "debug_assertions": true,
             "debuginfo": 2,
@@ -3291,6 +3297,7 @@ fn message_format_json_forward_stderr() {
             "name":"foo",
             "src_path":"[..]"
         },
+        "target_triple": "[..]",
         "profile":{
             "debug_assertions":false,
             "debuginfo":null,
@@ -1,5 +1,5 @@
 use crate::support::registry::Package;
-use crate::support::{basic_bin_manifest, basic_manifest, main_file, project};
+use crate::support::{basic_bin_manifest, basic_manifest, main_file, project, rustc_host};
 
 #[test]
 fn cargo_build_plan_simple() {
@@ -39,6 +39,46 @@ fn cargo_build_plan_simple() {
     assert!(!p.bin("foo").is_file());
 }
 
+#[test]
+fn cargo_build_plan_target() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/foo.rs", &main_file(r#""i am foo""#, &[]))
+        .build();
+
+    p.cargo("build --build-plan -Zunstable-options --target")
+        .arg(rustc_host())
+        .masquerade_as_nightly_cargo()
+        .with_json(&format!(
+            r#"
+    {{
+        "inputs": [
+            "[..]/foo/Cargo.toml"
+        ],
+        "invocations": [
+            {{
+                "args": "{{...}}",
+                "cwd": "[..]/cit/[..]/foo",
+                "deps": [],
+                "env": "{{...}}",
+                "kind": "Target",
+                "target_triple": "{}",
+                "links": "{{...}}",
+                "outputs": "{{...}}",
+                "package_name": "foo",
+                "package_version": "0.5.0",
+                "program": "rustc",
+                "target_kind": ["bin"],
+                "compile_mode": "build"
+            }}
+        ]
+    }}
+    "#,
+            rustc_host()
+        ))
+        .run();
+}
+
 #[test]
 fn cargo_build_plan_single_dep() {
     let p = project()
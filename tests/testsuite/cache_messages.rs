@@ -0,0 +1,208 @@
+use std::process::Output;
+
+use crate::support::{basic_manifest, project, Project};
+
+/// Returns the stderr of a build, without the lines which differ between
+/// the build which compiles the package and the one which finds it fresh.
+fn messages(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone())
+        .unwrap()
+        .lines()
+        .filter(|line| !line.contains("Compiling") && !line.contains("Finished"))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+fn warning_project() -> Project {
+    project()
+        .file(
+            "src/lib.rs",
+            "
+                pub fn f() {
+                    let x = 1;
+                }
+            ",
+        )
+        .build()
+}
+
+#[test]
+fn replays_warnings() {
+    let p = warning_project();
+
+    let first = p
+        .cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .exec_with_output()
+        .expect("cargo to run");
+    assert!(messages(&first).contains("unused variable"));
+
+    let second = p
+        .cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .exec_with_output()
+        .expect("cargo to run");
+    assert!(!String::from_utf8_lossy(&second.stderr).contains("Compiling"));
+    assert_eq!(messages(&first), messages(&second));
+}
+
+#[test]
+fn messages_match_uncached_build() {
+    let p = warning_project();
+
+    let uncached = p.cargo("build").exec_with_output().expect("cargo to run");
+    p.cargo("clean").run();
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .run();
+    let replayed = p
+        .cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .exec_with_output()
+        .expect("cargo to run");
+    assert_eq!(messages(&uncached), messages(&replayed));
+}
+
+#[test]
+fn not_enabled_by_default() {
+    let p = warning_project();
+
+    p.cargo("build")
+        .with_stderr_contains("[..]unused variable[..]")
+        .run();
+    p.cargo("build")
+        .with_stderr_does_not_contain("[..]unused variable[..]")
+        .run();
+}
+
+#[test]
+fn replays_as_json() {
+    let p = warning_project();
+
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .run();
+    p.cargo("build -Zcache-messages --message-format=json")
+        .masquerade_as_nightly_cargo()
+        .with_stdout_contains(r#"{"reason":"compiler-message",[..]unused variable[..]"#)
+        .with_stdout_does_not_contain(r#"[..]\u001b[..]"#)
+        .run();
+}
+
+#[test]
+fn replays_with_color() {
+    let p = warning_project();
+
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_does_not_contain("[..]\x1b[..]")
+        .run();
+    p.cargo("build -Zcache-messages --color=always")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[..]\x1b[..]unused variable[..]")
+        .run();
+}
+
+#[test]
+fn short_message_format_rebuilds() {
+    let p = warning_project();
+
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .run();
+    p.cargo("build -Zcache-messages --message-format=short")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[COMPILING] foo [..]")
+        .with_stderr_contains("src/lib.rs:[..]: warning: unused variable[..]")
+        .run();
+    p.cargo("build -Zcache-messages --message-format=short")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+src/lib.rs:[..]: warning: unused variable[..]
+[..]
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[test]
+fn fixed_warnings_are_not_replayed() {
+    let p = warning_project();
+
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[..]unused variable[..]")
+        .run();
+    p.change_file("src/lib.rs", "pub fn f() {}");
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[COMPILING] foo [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("[FINISHED] [..]")
+        .run();
+}
+
+#[test]
+fn failed_builds_are_not_cached() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.1.0"))
+        .file(
+            "src/lib.rs",
+            "pub fn f() { let x = 1; } pub fn g() { missing(); }",
+        )
+        .build();
+
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[..]unused variable[..]")
+        .run();
+    assert_eq!(p.glob("target/debug/.fingerprint/foo-*/output").count(), 0);
+
+    p.change_file("src/lib.rs", "pub fn f() { let x = 1; }");
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .run();
+    assert_eq!(p.glob("target/debug/.fingerprint/foo-*/output").count(), 1);
+}
+
+#[test]
+fn replays_path_dependency_warnings() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                authors = []
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/main.rs", "fn main() { bar::f(); }")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "pub fn f() { let x = 1; }")
+        .build();
+
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[COMPILING] bar [..]")
+        .with_stderr_contains("[..]unused variable[..]")
+        .run();
+    p.cargo("build -Zcache-messages")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_does_not_contain("[COMPILING] [..]")
+        .with_stderr_contains("[..]unused variable[..]")
+        .run();
+}
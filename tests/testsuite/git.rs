@@ -9,6 +9,7 @@ use std::sync::Arc;
 use std::thread;
 
 use crate::support::paths::{self, CargoPathExt};
+use crate::support::registry::Package;
 use crate::support::sleep_ms;
 use crate::support::Project;
 use crate::support::{basic_lib_manifest, basic_manifest, git, main_file, path2url, project};
@@ -2694,3 +2695,385 @@ fn git_with_cli_force() {
     p.cargo("build").run();
     p.rename_run("foo", "foo2").with_stdout("two").run();
 }
+
+#[test]
+fn shallow_fetch() {
+    if disable_git_cli() {
+        return;
+    }
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", r#"pub fn f() { println!("one"); }"#)
+    })
+    .unwrap();
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    git_project.change_file("src/lib.rs", r#"pub fn f() { println!("two"); }"#);
+    git::add(&repo);
+    git::commit(&repo);
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                [project]
+                name = "foo"
+                version = "0.0.1"
+                edition = "2018"
+
+                [dependencies]
+                dep1 = {{ git = "{}" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/main.rs", "fn main() { dep1::f(); }")
+        .file(
+            ".cargo/config",
+            "
+            [net]
+            git-shallow = true
+            ",
+        )
+        .build();
+
+    p.cargo("build -v")
+        .with_stderr_contains("[RUNNING] `git fetch [..]--depth=1 [..]`")
+        .run();
+    p.rename_run("foo", "foo1").with_stdout("two").run();
+
+    let db = paths::home().join(".cargo/git/db");
+    let db = db.read_dir().unwrap().next().unwrap().unwrap().path();
+    assert!(db.join("shallow").exists());
+}
+
+#[test]
+fn shallow_fetch_deepens_for_locked_revision() {
+    if disable_git_cli() {
+        return;
+    }
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", r#"pub fn f() { println!("one"); }"#)
+    })
+    .unwrap();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                [project]
+                name = "foo"
+                version = "0.0.1"
+                edition = "2018"
+
+                [dependencies]
+                dep1 = {{ git = "{}" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/main.rs", "fn main() { dep1::f(); }")
+        .build();
+
+    // Lock the first commit, then move the branch past it and start over
+    // from an empty git cache with shallow fetches.
+    p.cargo("generate-lockfile").run();
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    git_project.change_file("src/lib.rs", r#"pub fn f() { println!("two"); }"#);
+    git::add(&repo);
+    git::commit(&repo);
+    paths::home().join(".cargo/git").rm_rf();
+    fs::create_dir_all(p.root().join(".cargo")).unwrap();
+    File::create(p.root().join(".cargo/config"))
+        .unwrap()
+        .write_all(b"[net]\ngit-shallow = true\n")
+        .unwrap();
+
+    p.cargo("build -v")
+        .with_stderr_contains("[RUNNING] `git fetch [..]--depth=1 [..]`")
+        .with_stderr_does_not_contain("[..]--unshallow[..]")
+        .run();
+    p.rename_run("foo", "foo1").with_stdout("one").run();
+}
+
+#[test]
+fn shallow_registry_index() {
+    if disable_git_cli() {
+        return;
+    }
+    Package::new("bar", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config",
+            "
+            [net]
+            git-shallow = true
+            ",
+        )
+        .build();
+
+    p.cargo("generate-lockfile").run();
+    Package::new("bar", "0.1.1").publish();
+    p.cargo("update").run();
+    p.cargo("pkgid bar").with_stdout("[..]#bar:0.1.1").run();
+
+    let index = paths::home().join(".cargo/registry/index");
+    let index = index.read_dir().unwrap().next().unwrap().unwrap().path();
+    assert!(index.join(".git/shallow").exists());
+}
+
+#[test]
+fn shallow_fetch_dep_with_submodule() {
+    if disable_git_cli() {
+        return;
+    }
+    let git_project = git::new("dep1", |project| {
+        project.file("Cargo.toml", &basic_manifest("dep1", "0.5.0"))
+    })
+    .unwrap();
+    let git_project2 =
+        git::new("dep2", |project| project.file("lib.rs", "pub fn dep() {}")).unwrap();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    let url = path2url(git_project2.root()).to_string();
+    git::add_submodule(&repo, &url, Path::new("src"));
+    git::commit(&repo);
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                [project]
+                name = "foo"
+                version = "0.5.0"
+
+                [dependencies]
+                dep1 = {{ git = "{}" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file(
+            "src/lib.rs",
+            "extern crate dep1; pub fn foo() { dep1::dep() }",
+        )
+        .file(
+            ".cargo/config",
+            "
+            [net]
+            git-shallow = true
+            ",
+        )
+        .build();
+
+    p.cargo("build -v")
+        .with_stderr_contains("[RUNNING] `git fetch [..]--depth=1 [..]`")
+        .with_stderr_contains("[COMPILING] dep1 [..]")
+        .run();
+}
+
+#[test]
+fn dep_without_submodules() {
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", "pub fn dep() {}")
+    })
+    .unwrap();
+    let data = git::new("data", |project| project.file("big.bin", "")).unwrap();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    let url = path2url(data.root()).to_string();
+    git::add_submodule(&repo, &url, Path::new("data"));
+    git::commit(&repo);
+    // Any attempt to fetch the submodule now fails.
+    data.root().rm_rf();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                cargo-features = ["git-submodules"]
+
+                [project]
+                name = "foo"
+                version = "0.5.0"
+
+                [dependencies]
+                dep1 = {{ git = '{}', submodules = false }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file(
+            "src/lib.rs",
+            "extern crate dep1; pub fn foo() { dep1::dep() }",
+        )
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[UPDATING] git repository [..]
+[COMPILING] dep1 [..]
+[COMPILING] foo [..]
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]\n",
+        )
+        .run();
+    assert!(p.read_lockfile().contains("submodules=false"));
+}
+
+#[test]
+fn dep_with_selected_submodules() {
+    let git_project = git::new("dep1", |project| {
+        project.file("Cargo.toml", &basic_manifest("dep1", "0.5.0"))
+    })
+    .unwrap();
+    let src = git::new("dep2", |project| project.file("lib.rs", "pub fn dep() {}")).unwrap();
+    let data = git::new("data", |project| project.file("big.bin", "")).unwrap();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    let url = path2url(src.root()).to_string();
+    git::add_submodule(&repo, &url, Path::new("src"));
+    let url = path2url(data.root()).to_string();
+    git::add_submodule(&repo, &url, Path::new("data"));
+    git::commit(&repo);
+    data.root().rm_rf();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                cargo-features = ["git-submodules"]
+
+                [project]
+                name = "foo"
+                version = "0.5.0"
+
+                [dependencies]
+                dep1 = {{ git = '{}', submodules = ["src"] }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file(
+            "src/lib.rs",
+            "extern crate dep1; pub fn foo() { dep1::dep() }",
+        )
+        .build();
+
+    p.cargo("build").masquerade_as_nightly_cargo().run();
+}
+
+#[test]
+fn dep_submodules_requires_feature() {
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", "")
+    })
+    .unwrap();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                [project]
+                name = "foo"
+                version = "0.5.0"
+
+                [dependencies]
+                dep1 = {{ git = '{}', submodules = false }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  feature `git-submodules` is required
+
+consider adding `cargo-features = [\"git-submodules\"]` to the manifest
+",
+        )
+        .run();
+}
+
+#[test]
+fn dep_with_submodule_with_cli() {
+    if disable_git_cli() {
+        return;
+    }
+    let git_project = git::new("dep1", |project| {
+        project.file("Cargo.toml", &basic_manifest("dep1", "0.5.0"))
+    })
+    .unwrap();
+    let git_project2 =
+        git::new("dep2", |project| project.file("lib.rs", "pub fn dep() {}")).unwrap();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    let url = path2url(git_project2.root()).to_string();
+    git::add_submodule(&repo, &url, Path::new("src"));
+    git::commit(&repo);
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                [project]
+                name = "foo"
+                version = "0.5.0"
+
+                [dependencies]
+                dep1 = {{ git = '{}' }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file(
+            "src/lib.rs",
+            "extern crate dep1; pub fn foo() { dep1::dep() }",
+        )
+        .file(
+            ".cargo/config",
+            "
+            [net]
+            git-fetch-with-cli = true
+            ",
+        )
+        .build();
+
+    p.cargo("build -v")
+        .with_stderr_contains(&format!("[RUNNING] `git fetch [..]{}[..]`", url))
+        .run();
+}
@@ -0,0 +1,95 @@
+use crate::support::{basic_manifest, project, Project};
+
+fn workspace() -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["broken", "fine", "dependent", "also-broken"]
+            "#,
+        )
+        .file("broken/Cargo.toml", &basic_manifest("broken", "0.1.0"))
+        .file("broken/src/lib.rs", "pub fn f() { missing(); }")
+        .file(
+            "also-broken/Cargo.toml",
+            &basic_manifest("also-broken", "0.1.0"),
+        )
+        .file("also-broken/src/lib.rs", "pub fn f() { also_missing(); }")
+        .file("fine/Cargo.toml", &basic_manifest("fine", "0.1.0"))
+        .file("fine/src/main.rs", "fn main() {}")
+        .file(
+            "dependent/Cargo.toml",
+            r#"
+                [package]
+                name = "dependent"
+                version = "0.1.0"
+                authors = []
+
+                [dependencies]
+                broken = { path = "../broken" }
+            "#,
+        )
+        .file("dependent/src/lib.rs", "pub fn f() { broken::f(); }")
+        .build()
+}
+
+#[test]
+fn builds_independent_units() {
+    let p = workspace();
+
+    p.cargo("build --keep-going")
+        .with_status(101)
+        .with_stderr_contains("[..]cannot find function `missing`[..]")
+        .with_stderr_contains("[..]cannot find function `also_missing`[..]")
+        .with_stderr_contains("[ERROR] Could not compile `broken`.")
+        .with_stderr_contains("[ERROR] Could not compile `also-broken`.")
+        .with_stderr_contains("[COMPILING] fine v0.1.0 ([..])")
+        .with_stderr_does_not_contain("[COMPILING] dependent [..]")
+        .with_stderr_does_not_contain("[..]waiting for other jobs[..]")
+        .with_stderr_contains("[ERROR] build failed, could not compile `also-broken`, `broken`")
+        .run();
+    assert!(p.bin("fine").is_file());
+}
+
+#[test]
+fn check_keep_going() {
+    let p = workspace();
+
+    p.cargo("check --keep-going -j1")
+        .with_status(101)
+        .with_stderr_contains("[CHECKING] fine v0.1.0 ([..])")
+        .with_stderr_does_not_contain("[CHECKING] dependent [..]")
+        .with_stderr_contains("[ERROR] build failed, could not compile `also-broken`, `broken`")
+        .run();
+}
+
+#[test]
+fn succeeds_without_failures() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("build --keep-going")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[test]
+fn test_requires_no_run() {
+    let p = workspace();
+
+    p.cargo("test --keep-going")
+        .with_status(101)
+        .with_stderr("[ERROR] --keep-going can only be used with --no-run")
+        .run();
+
+    p.cargo("test --keep-going --no-run")
+        .with_status(101)
+        .with_stderr_contains("[COMPILING] fine v0.1.0 ([..])")
+        .with_stderr_contains("[ERROR] build failed, could not compile `also-broken`, `broken`")
+        .run();
+}
@@ -506,3 +506,305 @@ error: the lock file [CWD]/Cargo.lock needs to be updated but --locked was passe
         )
         .run();
 }
+
+fn v2_project() -> crate::support::Project {
+    Package::new("baz", "0.1.0").publish();
+    Package::new("baz", "0.2.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "0.2.0").publish();
+
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+            baz = "0.1.0"
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .build()
+}
+
+fn assert_lockfile(actual: &str, expected: &str) {
+    for (l, r) in expected.lines().zip(actual.lines()) {
+        assert!(lines_match(l, r), "Lines differ:\n{}\n\n{}", l, r);
+    }
+    assert_eq!(actual.lines().count(), expected.lines().count());
+}
+
+#[test]
+fn v2_format() {
+    let p = v2_project();
+    p.cargo("generate-lockfile").run();
+    Package::new("bar", "0.1.1").dep("baz", "0.2.0").publish();
+
+    // Changing the format doesn't update anything.
+    p.cargo("update --lockfile-version 2").with_stderr("").run();
+
+    let expected = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 2
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "[..]"
+dependencies = [
+ "baz 0.2.0",
+]
+
+[[package]]
+name = "baz"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "[..]"
+
+[[package]]
+name = "baz"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "[..]"
+
+[[package]]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar",
+ "baz 0.1.0",
+]
+"#;
+    assert_lockfile(&p.read_lockfile(), expected);
+
+    // The lock file is read back as it was written.
+    p.cargo("build --locked").run();
+    assert_lockfile(&p.read_lockfile(), expected);
+
+    // And keeps its format when updated.
+    p.cargo("update -p bar")
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[UPDATING] bar v0.1.0 -> v0.1.1
+",
+        )
+        .run();
+    assert_lockfile(
+        &p.read_lockfile(),
+        &expected.replace(
+            "name = \"bar\"\nversion = \"0.1.0\"",
+            "name = \"bar\"\nversion = \"0.1.1\"",
+        ),
+    );
+}
+
+#[test]
+fn v2_back_to_v1() {
+    let p = v2_project();
+    p.cargo("generate-lockfile").run();
+    let v1 = p.read_lockfile();
+
+    p.cargo("update --lockfile-version 2").run();
+    assert!(p.read_lockfile().contains("version = 2"));
+    p.cargo("generate-lockfile").run();
+    assert!(p.read_lockfile().contains("version = 2"));
+
+    p.cargo("update --lockfile-version 1").run();
+    assert_eq!(p.read_lockfile(), v1);
+}
+
+#[test]
+fn v2_checksum_mismatch() {
+    let p = v2_project();
+    p.cargo("update --lockfile-version 2").run();
+    let lock = p.read_lockfile();
+    let start = lock.find("checksum = \"").unwrap() + "checksum = \"".len();
+    let mut lock = lock.clone();
+    lock.replace_range(start..start + 64, &"0".repeat(64));
+    p.change_file("Cargo.lock", &lock);
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr_contains("error: checksum for `bar v0.1.0` changed between lock files")
+        .run();
+}
+
+#[test]
+fn v2_missing_checksum() {
+    let p = v2_project();
+    p.cargo("update --lockfile-version 2").run();
+    let lock = p.read_lockfile();
+    let start = lock.find("checksum = \"").unwrap();
+    let end = start + lock[start..].find('\n').unwrap() + 1;
+    let mut lock = lock.clone();
+    lock.replace_range(start..end, "");
+    p.change_file("Cargo.lock", &lock);
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr_contains(
+            "error: checksum for `bar v0.1.0` was not previously calculated, \
+             but a checksum could now be calculated",
+        )
+        .run();
+}
+
+#[test]
+fn unknown_lockfile_version() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "Cargo.lock",
+            r#"
+version = 3
+
+[[package]]
+name = "foo"
+version = "0.0.1"
+"#,
+        )
+        .build();
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse lock file at: [..]Cargo.lock
+
+Caused by:
+  lock file version `3` was found, but this version of Cargo does not \
+understand this lock file, perhaps Cargo needs to be updated?
+",
+        )
+        .run();
+}
+
+fn conflicted_project(lockfile: &str) -> crate::support::Project {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.1").publish();
+    Package::new("baz", "0.1.0").publish();
+
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+            baz = "0.1"
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", lockfile)
+        .build()
+}
+
+// One side of the merge added `baz`, the other updated `bar`.
+const CONFLICTED_LOCKFILE: &str = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+[[package]]
+name = "bar"
+<<<<<<< HEAD
+version = "0.1.0"
+=======
+version = "0.1.1"
+>>>>>>> update-bar
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "baz"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+<<<<<<< HEAD
+ "bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+ "baz 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+||||||| merged common ancestors
+ "bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+=======
+ "bar 0.1.1 (registry+https://github.com/rust-lang/crates.io-index)",
+>>>>>>> update-bar
+]
+"#;
+
+#[test]
+fn merge_conflicts_resolved() {
+    let p = conflicted_project(CONFLICTED_LOCKFILE);
+
+    p.cargo("build")
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[MERGING] conflicting changes to Cargo.lock
+[UPDATING] bar v0.1.0 -> v0.1.1
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[COMPILING] [..]
+[COMPILING] [..]
+[COMPILING] foo v0.0.1 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    let lock = p.read_lockfile();
+    assert!(!lock.contains("<<<<<<<"));
+    assert!(lock.contains("\"bar 0.1.1 (registry+"));
+    assert!(!lock.contains("bar 0.1.0"));
+    assert!(lock.contains("baz 0.1.0"));
+
+    // The clean lock file is used as is.
+    p.cargo("build --locked")
+        .with_stderr("[FINISHED] [..]")
+        .run();
+}
+
+#[test]
+fn merge_conflicts_locked() {
+    let p = conflicted_project(CONFLICTED_LOCKFILE);
+
+    p.cargo("build --locked")
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[MERGING] conflicting changes to Cargo.lock
+[UPDATING] bar v0.1.0 -> v0.1.1
+error: the lock file [CWD]/Cargo.lock needs to be updated but --locked was passed to prevent this
+",
+        )
+        .run();
+    assert!(p.read_lockfile().contains("<<<<<<<"));
+}
+
+#[test]
+fn merge_conflicts_unterminated() {
+    let lockfile = CONFLICTED_LOCKFILE.replace(">>>>>>> update-bar\nsource", "source");
+    let p = conflicted_project(&lockfile);
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse lock file at: [..]Cargo.lock
+
+Caused by:
+  unexpected merge conflict marker on line 20
+",
+        )
+        .run();
+}
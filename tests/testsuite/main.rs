@@ -10,6 +10,7 @@
 mod support;
 
 mod alt_registry;
+mod artifact_dep;
 mod bad_config;
 mod bad_manifest_path;
 mod bench;
@@ -19,6 +20,7 @@ mod build_lib;
 mod build_plan;
 mod build_script;
 mod build_script_env;
+mod cache_messages;
 mod cargo_alias_config;
 mod cargo_command;
 mod cargo_features;
@@ -47,6 +49,7 @@ mod init;
 mod install;
 mod install_upgrade;
 mod jobserver;
+mod keep_going;
 mod list_targets;
 mod local_registry;
 mod lockfile_compat;
@@ -54,14 +57,18 @@ mod login;
 mod member_errors;
 mod metabuild;
 mod metadata;
+mod multitarget;
 mod net_config;
 mod new;
 mod offline;
 mod out_dir;
+mod outdated;
 mod overrides;
 mod package;
 mod patch;
 mod path;
+mod per_package_target;
+mod pipelining;
 mod plugins;
 mod proc_macro;
 mod profile_config;
@@ -85,6 +92,7 @@ mod rustflags;
 mod search;
 mod shell_quoting;
 mod small_fd_limits;
+mod standard_lib;
 mod test;
 mod tool_paths;
 mod update;
@@ -680,6 +680,7 @@ fn metabuild_json_artifact() {
   ],
   "fresh": false,
   "package_id": "foo [..]",
+  "target_triple": "[..]",
   "profile": "{...}",
   "reason": "compiler-artifact",
   "target": {
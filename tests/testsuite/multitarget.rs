@@ -0,0 +1,181 @@
+use crate::support::{basic_manifest, cross_compile, project, rustc_host};
+
+#[test]
+fn multitarget_requires_unstable_flag() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build --target a-unknown-none --target b-unknown-none")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] building for multiple targets is unstable, pass `-Z multitarget` to enable it
+",
+        )
+        .run();
+}
+
+#[test]
+fn multitarget_config_list() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config",
+            &format!(
+                r#"
+                [build]
+                target = ["{}"]
+            "#,
+                rustc_host()
+            ),
+        )
+        .build();
+
+    p.cargo("build").run();
+    assert!(p.target_bin(&rustc_host(), "foo").is_file());
+    assert!(!p.bin("foo").is_file());
+}
+
+#[test]
+fn multitarget_duplicate_targets() {
+    let host = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -v --target")
+        .arg(&host)
+        .arg("--target")
+        .arg(&host)
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[RUNNING] `rustc --crate-name foo [..]--target [..]`
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[test]
+fn artifact_target_triple() {
+    let host = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build --message-format=json --target")
+        .arg(&host)
+        .with_json(&format!(
+            r#"
+            {{
+                "reason": "compiler-artifact",
+                "package_id": "foo 0.0.1 ([..])",
+                "target": "{{...}}",
+                "target_triple": "{}",
+                "profile": "{{...}}",
+                "features": [],
+                "filenames": "{{...}}",
+                "executable": null,
+                "fresh": false
+            }}
+            "#,
+            host
+        ))
+        .run();
+}
+
+#[test]
+fn multitarget_shares_host_units() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let host = rustc_host();
+    let target = cross_compile::alternate();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+        "#,
+        )
+        .file("build.rs", "fn main() {}")
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -v -Z multitarget --target")
+        .arg(&host)
+        .arg("--target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo()
+        .with_stderr_unordered(&format!(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[RUNNING] `rustc --crate-name build_script_build build.rs [..]`
+[RUNNING] `[..]build-script-build`
+[RUNNING] `[..]build-script-build`
+[RUNNING] `rustc --crate-name foo src/main.rs [..]--target {} [..]`
+[RUNNING] `rustc --crate-name foo src/main.rs [..]--target {} [..]`
+[FINISHED] [..]
+",
+            host, target
+        ))
+        .run();
+
+    assert!(p.target_bin(&host, "foo").is_file());
+    assert!(p.target_bin(&target, "foo").is_file());
+}
+
+#[test]
+fn multitarget_runner_per_target() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let host = rustc_host();
+    let target = cross_compile::alternate();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/lib.rs", "#[test] fn t() {}")
+        .file(
+            ".cargo/config",
+            &format!(
+                r#"
+                [target.{}]
+                runner = "nonexistent-runner -r"
+            "#,
+                target
+            ),
+        )
+        .build();
+
+    p.cargo("test -Z multitarget --no-fail-fast --target")
+        .arg(&host)
+        .arg("--target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(&format!(
+            "[RUNNING] `nonexistent-runner -r [..]/target/{}/debug/deps/foo-[..][EXE]`",
+            target
+        ))
+        .with_stderr_contains(&format!(
+            "[RUNNING] `[..]/target/{}/debug/deps/foo-[..][EXE]`",
+            host
+        ))
+        .with_stderr_does_not_contain(&format!(
+            "[RUNNING] `nonexistent-runner -r [..]/target/{}/[..]`",
+            host
+        ))
+        .run();
+}
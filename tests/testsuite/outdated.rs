@@ -0,0 +1,195 @@
+use std::fs;
+
+use crate::support::registry::{registry_path, Package};
+use crate::support::{project, Project};
+
+fn outdated_project() -> Project {
+    Package::new("bar", "0.1.0").dep("deep", "0.1").publish();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("deep", "0.1.0").publish();
+    Package::new("qux", "0.3.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.1"
+                baz = "1.0"
+                qux = "0.3"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("bar", "0.1.1").dep("deep", "0.1").publish();
+    Package::new("bar", "0.2.0").dep("deep", "0.1").publish();
+    Package::new("deep", "0.1.1").publish();
+    fs::remove_file(registry_path().join("3/q/qux")).unwrap();
+    Package::new("qux", "0.3.0").yanked(true).publish();
+    p
+}
+
+#[test]
+fn outdated_human() {
+    let p = outdated_project();
+
+    p.cargo("outdated")
+        .with_stderr("[UPDATING] `[..]` index")
+        .with_stdout(
+            "\
+Name  Locked          Compatible  Latest
+bar   0.1.0           0.1.1       0.2.0
+deep  0.1.0           0.1.1       0.1.1
+qux   0.3.0 (yanked)  -           -
+",
+        )
+        .run();
+
+    p.cargo("outdated --depth 1")
+        .with_stdout(
+            "\
+Name  Locked          Compatible  Latest
+bar   0.1.0           0.1.1       0.2.0
+qux   0.3.0 (yanked)  -           -
+",
+        )
+        .run();
+}
+
+#[test]
+fn outdated_json() {
+    let p = outdated_project();
+
+    p.cargo("outdated --depth 1 --message-format json")
+        .with_json(
+            r#"
+{
+    "reason": "outdated-dependency",
+    "package_id": "bar 0.1.0 (registry+[..])",
+    "name": "bar",
+    "depth": 1,
+    "locked_version": "0.1.0",
+    "yanked": false,
+    "compatible_version": "0.1.1",
+    "latest_version": "0.2.0"
+}
+
+{
+    "reason": "outdated-dependency",
+    "package_id": "qux 0.3.0 (registry+[..])",
+    "name": "qux",
+    "depth": 1,
+    "locked_version": "0.3.0",
+    "yanked": true,
+    "compatible_version": null,
+    "latest_version": null
+}
+"#,
+        )
+        .run();
+}
+
+#[test]
+fn outdated_exit_code() {
+    let p = outdated_project();
+
+    p.cargo("outdated --exit-code --depth 1")
+        .with_status(1)
+        .with_stdout(
+            "\
+Name  Locked          Compatible  Latest
+bar   0.1.0           0.1.1       0.2.0
+qux   0.3.0 (yanked)  -           -
+",
+        )
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.2"
+            baz = "1.0"
+        "#,
+    );
+    p.cargo("update").run();
+
+    p.cargo("outdated --exit-code")
+        .with_stdout("All dependencies are up to date.")
+        .run();
+}
+
+#[test]
+fn outdated_workspace() {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("baz", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.1"
+            "#,
+        )
+        .file("a/src/lib.rs", "")
+        .file(
+            "b/Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                baz = "0.1"
+            "#,
+        )
+        .file("b/src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("bar", "0.1.1").publish();
+    Package::new("baz", "0.1.1").publish();
+
+    p.cargo("outdated -p a")
+        .with_stdout(
+            "\
+Name  Locked  Compatible  Latest
+bar   0.1.0   0.1.1       0.1.1
+",
+        )
+        .run();
+
+    p.cargo("outdated --workspace")
+        .with_stdout(
+            "\
+Name  Locked  Compatible  Latest
+bar   0.1.0   0.1.1       0.1.1
+baz   0.1.0   0.1.1       0.1.1
+",
+        )
+        .run();
+}
@@ -150,9 +150,9 @@ fn package_verbose() {
 [WARNING] manifest has no description[..]
 See https://doc.rust-lang.org/cargo/reference/manifest.html#package-metadata for more info.
 [PACKAGING] foo v0.0.1 ([..])
+[ARCHIVING] .cargo_vcs_info.json
 [ARCHIVING] [..]
 [ARCHIVING] [..]
-[ARCHIVING] .cargo_vcs_info.json
 ",
         )
         .run();
@@ -187,9 +187,9 @@ See https://doc.rust-lang.org/cargo/reference/manifest.html#package-metadata for
 [WARNING] manifest has no description[..]
 See https://doc.rust-lang.org/cargo/reference/manifest.html#package-metadata for more info.
 [PACKAGING] a v0.0.1 ([..])
+[ARCHIVING] .cargo_vcs_info.json
 [ARCHIVING] Cargo.toml
 [ARCHIVING] src/lib.rs
-[ARCHIVING] .cargo_vcs_info.json
 ",
         )
         .run();
@@ -376,6 +376,7 @@ See [..]
 [WARNING] [..] file `some_dir/file_deep_1` WILL be excluded [..]
 See [..]
 [PACKAGING] foo v0.0.1 ([..])
+[ARCHIVING] .cargo_vcs_info.json
 [ARCHIVING] [..]
 [ARCHIVING] [..]
 [ARCHIVING] [..]
@@ -394,7 +395,6 @@ See [..]
 [ARCHIVING] [..]
 [ARCHIVING] [..]
 [ARCHIVING] [..]
-[ARCHIVING] .cargo_vcs_info.json
 ",
         )
         .run();
@@ -457,10 +457,10 @@ fn include() {
 [WARNING] manifest has no description[..]
 See https://doc.rust-lang.org/cargo/reference/manifest.html#package-metadata for more info.
 [PACKAGING] foo v0.0.1 ([..])
+[ARCHIVING] .cargo_vcs_info.json
 [ARCHIVING] [..]
 [ARCHIVING] [..]
 [ARCHIVING] [..]
-[ARCHIVING] .cargo_vcs_info.json
 ",
         )
         .run();
@@ -1154,3 +1154,504 @@ fn package_no_default_features() {
         .with_status(101)
         .run();
 }
+
+fn workspace_with_unpublished_dependency() -> crate::support::Project {
+    // Only an older version of `a` is available on the registry, `b` needs
+    // the one in the workspace.
+    registry::init();
+    Package::new("a", "0.1.0").file("src/lib.rs", "").publish();
+
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["a", "b"]
+        "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+            [project]
+            name = "a"
+            version = "0.2.0"
+            authors = []
+            license = "MIT"
+            description = "a"
+        "#,
+        )
+        .file("a/src/lib.rs", "pub fn new_in_0_2() {}")
+        .file(
+            "b/Cargo.toml",
+            r#"
+            [project]
+            name = "b"
+            version = "0.1.0"
+            authors = []
+            license = "MIT"
+            description = "b"
+
+            [dependencies]
+            a = { path = "../a", version = "0.2.0" }
+        "#,
+        )
+        .file("b/src/lib.rs", "pub fn b() { a::new_in_0_2(); }")
+        .build()
+}
+
+#[test]
+fn package_workspace_verifies_against_packaged_members() {
+    let p = workspace_with_unpublished_dependency();
+
+    p.cargo("package --workspace --no-metadata")
+        .with_stderr(
+            "\
+[PACKAGING] a v0.2.0 ([CWD]/a)
+[PACKAGING] b v0.1.0 ([CWD]/b)
+[VERIFYING] a v0.2.0 ([CWD]/a)
+[COMPILING] a v0.2.0 ([CWD]/target/package/a-0.2.0)
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+[VERIFYING] b v0.1.0 ([CWD]/b)
+[UPDATING] [..]
+[UNPACKING] a v0.2.0 ([..])
+[COMPILING] a v0.2.0
+[COMPILING] b v0.1.0 ([CWD]/target/package/b-0.1.0)
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+",
+        )
+        .run();
+
+    let f = File::open(&p.root().join("target/package/a-0.2.0.crate")).unwrap();
+    validate_crate_contents(
+        f,
+        "a-0.2.0.crate",
+        &["Cargo.toml", "Cargo.toml.orig", "src/lib.rs"],
+        &[],
+    );
+    let f = File::open(&p.root().join("target/package/b-0.1.0.crate")).unwrap();
+    validate_crate_contents(
+        f,
+        "b-0.1.0.crate",
+        &["Cargo.toml", "Cargo.toml.orig", "src/lib.rs"],
+        &[],
+    );
+
+    // Packaging again must not pick up the tarballs unpacked last time.
+    p.change_file("a/src/lib.rs", "pub fn new_in_0_2() {} pub fn newer() {}");
+    p.change_file("b/src/lib.rs", "pub fn b() { a::newer(); }");
+    p.cargo("package --workspace --no-metadata").run();
+}
+
+#[test]
+fn package_single_member_of_workspace_without_overlay() {
+    let p = workspace_with_unpublished_dependency();
+
+    p.cargo("package -p b --no-metadata")
+        .with_status(101)
+        .with_stderr_contains("[..]failed to select a version for the requirement `a = \"^0.2.0\"`")
+        .run();
+}
+
+#[test]
+fn package_workspace_list() {
+    let p = workspace_with_unpublished_dependency();
+
+    p.cargo("package --workspace --list")
+        .with_stdout(
+            "\
+a/Cargo.toml
+a/src/lib.rs
+b/Cargo.toml
+b/src/lib.rs
+",
+        )
+        .run();
+
+    p.cargo("package --workspace --exclude a --list")
+        .with_stdout(
+            "\
+Cargo.toml
+src/lib.rs
+",
+        )
+        .run();
+}
+
+fn crate_entries(path: &Path) -> Vec<(String, u64, u64, u64, u32)> {
+    let f = File::open(path).unwrap();
+    let mut ar = tar::Archive::new(flate2::read::GzDecoder::new(f));
+    ar.entries()
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let header = entry.header();
+            (
+                entry.path().unwrap().display().to_string(),
+                header.mtime().unwrap(),
+                header.uid().unwrap(),
+                header.gid().unwrap(),
+                header.mode().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn reproducible_tarballs() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/main.rs", "mod b; mod a; fn main() {}")
+        .file("src/b.rs", "")
+        .file("src/a.rs", "")
+        .build();
+
+    let crate_path = p.root().join("target/package/foo-0.0.1.crate");
+    p.cargo("package --no-verify --no-metadata").run();
+    let first = std::fs::read(&crate_path).unwrap();
+
+    // Packaging again after touching the sources yields the same tarball.
+    let now = filetime::FileTime::from_unix_time(2_000_000_000, 0);
+    for file in &["Cargo.toml", "src/main.rs", "src/a.rs", "src/b.rs"] {
+        filetime::set_file_times(p.root().join(file), now, now).unwrap();
+    }
+    p.cargo("package --no-verify --no-metadata").run();
+    assert_eq!(first, std::fs::read(&crate_path).unwrap());
+
+    let mut entries = crate_entries(&crate_path);
+    let names: Vec<_> = entries.iter().map(|e| e.0.clone()).collect();
+    assert_eq!(
+        names,
+        [
+            "foo-0.0.1/Cargo.toml",
+            "foo-0.0.1/Cargo.toml.orig",
+            "foo-0.0.1/src/a.rs",
+            "foo-0.0.1/src/b.rs",
+            "foo-0.0.1/src/main.rs",
+        ]
+    );
+    for (name, mtime, uid, gid, mode) in entries.drain(..) {
+        assert_eq!(mtime, 1_153_704_088, "{}", name);
+        assert_eq!((uid, gid), (0, 0), "{}", name);
+        assert_eq!(mode, 0o644, "{}", name);
+    }
+
+    p.cargo("package --no-verify --no-metadata")
+        .env("SOURCE_DATE_EPOCH", "1500000000")
+        .run();
+    for (name, mtime, ..) in crate_entries(&crate_path) {
+        assert_eq!(mtime, 1_500_000_000, "{}", name);
+    }
+
+    p.cargo("package --no-verify --no-metadata --verify-reproducible")
+        .with_stderr(
+            "\
+[PACKAGING] foo v0.0.1 ([CWD])
+",
+        )
+        .run();
+}
+
+#[test]
+fn reproducible_tarballs_bad_source_date_epoch() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("package --no-verify --no-metadata")
+        .env("SOURCE_DATE_EPOCH", "yesterday")
+        .with_status(101)
+        .with_stderr_contains(
+            "[..]SOURCE_DATE_EPOCH must be a non-negative integer, found `yesterday`",
+        )
+        .run();
+}
+
+#[cfg(unix)]
+#[test]
+fn reproducible_tarballs_keep_executable_bit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/main.rs", "fn main() {}")
+        .file("run.sh", "#!/bin/sh")
+        .build();
+    let script = p.root().join("run.sh");
+    let mut perms = std::fs::metadata(&script).unwrap().permissions();
+    perms.set_mode(0o700);
+    std::fs::set_permissions(&script, perms).unwrap();
+
+    p.cargo("package --no-verify --no-metadata").run();
+    let entries = crate_entries(&p.root().join("target/package/foo-0.0.1.crate"));
+    let modes: Vec<_> = entries.iter().map(|e| (e.0.as_str(), e.4)).collect();
+    assert_eq!(
+        modes,
+        [
+            ("foo-0.0.1/Cargo.toml", 0o644),
+            ("foo-0.0.1/Cargo.toml.orig", 0o644),
+            ("foo-0.0.1/run.sh", 0o755),
+            ("foo-0.0.1/src/main.rs", 0o644),
+        ]
+    );
+}
+
+#[test]
+fn list_json() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            include = ["Cargo.toml", "src/**/*.rs", "data.txt"]
+        "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file("data.txt", "hello")
+        .file("notes.txt", "not packaged")
+        .build();
+
+    p.cargo("package --list --message-format json")
+        .with_json(
+            r#"
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "Cargo.toml",
+                "source": "generated",
+                "size": "{...}",
+                "included": true,
+                "rule": "Cargo.toml"
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "Cargo.toml.orig",
+                "source": "walk",
+                "size": "{...}",
+                "included": true,
+                "rule": "Cargo.toml"
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "data.txt",
+                "source": "walk",
+                "size": 5,
+                "included": true,
+                "rule": "data.txt"
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "src/main.rs",
+                "source": "walk",
+                "size": 12,
+                "included": true,
+                "rule": "src/**/*.rs"
+            }
+        "#,
+        )
+        .run();
+
+    p.cargo("package --message-format json")
+        .with_status(1)
+        .with_stderr_contains("[..]--list[..]")
+        .run();
+}
+
+#[test]
+fn list_json_exclude() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            exclude = ["*.txt"]
+        "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file("notes.txt", "not packaged")
+        .build();
+
+    p.cargo("package --list --message-format json")
+        .with_json(
+            r#"
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "Cargo.toml",
+                "source": "generated",
+                "size": "{...}",
+                "included": true,
+                "rule": null
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "Cargo.toml.orig",
+                "source": "walk",
+                "size": "{...}",
+                "included": true,
+                "rule": null
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "notes.txt",
+                "source": "walk",
+                "size": 12,
+                "included": false,
+                "rule": "*.txt"
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "src/main.rs",
+                "source": "walk",
+                "size": 12,
+                "included": true,
+                "rule": null
+            }
+        "#,
+        )
+        .run();
+
+    p.cargo("package --list")
+        .with_stdout(
+            "\
+Cargo.toml
+src/main.rs
+",
+        )
+        .run();
+}
+
+#[test]
+fn list_json_git() {
+    let repo = git::repo(&paths::root().join("foo"))
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    cargo_process("package --list --message-format json")
+        .cwd(repo.root())
+        .with_json(
+            r#"
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": ".cargo_vcs_info.json",
+                "source": "generated",
+                "size": "{...}",
+                "included": true,
+                "rule": null
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "Cargo.toml",
+                "source": "generated",
+                "size": "{...}",
+                "included": true,
+                "rule": null
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "Cargo.toml.orig",
+                "source": "vcs",
+                "size": "{...}",
+                "included": true,
+                "rule": null
+            }
+
+            {
+                "reason": "package-file",
+                "package_id": "foo 0.0.1 (path+file:[..])",
+                "path": "src/main.rs",
+                "source": "vcs",
+                "size": 12,
+                "included": true,
+                "rule": null
+            }
+        "#,
+        )
+        .run();
+}
+
+#[test]
+fn warn_included_but_git_ignored() {
+    let repo = git::repo(&paths::root().join("foo"))
+        .file(
+            "Cargo.toml",
+            r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            include = ["Cargo.toml", "src/*.rs"]
+        "#,
+        )
+        .file(".gitignore", "src/generated.rs\n")
+        .file("src/main.rs", "fn main() {}")
+        .build();
+    File::create(repo.root().join("src/generated.rs")).unwrap();
+
+    cargo_process("package --list --allow-dirty --no-metadata")
+        .cwd(repo.root())
+        .with_stdout(
+            "\
+Cargo.toml
+src/generated.rs
+src/main.rs
+",
+        )
+        .with_stderr(
+            "\
+[WARNING] file `src/generated.rs` is included in the package but ignored by git
+",
+        )
+        .run();
+}
+
+#[test]
+fn warn_large_file() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+    File::create(p.root().join("big.bin"))
+        .unwrap()
+        .set_len(6 * 1024 * 1024)
+        .unwrap();
+
+    p.cargo("package --list --no-metadata")
+        .with_stdout(
+            "\
+Cargo.toml
+big.bin
+src/main.rs
+",
+        )
+        .with_stderr(
+            "\
+[WARNING] file `big.bin` is 6.0 MiB large, consider excluding it from the package
+",
+        )
+        .run();
+}
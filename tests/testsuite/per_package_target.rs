@@ -0,0 +1,261 @@
+use crate::support::{basic_manifest, cross_compile, project, rustc_host};
+
+#[test]
+fn per_package_target_unstable() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            forced-target = "foo-unknown-none"
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  the `forced-target` and `default-target` manifest keys are unstable
+
+Caused by:
+  feature `per-package-target` is required
+
+consider adding `cargo-features = [\"per-package-target\"]` to the manifest
+",
+        )
+        .run();
+}
+
+#[test]
+fn forced_and_default_target_conflict() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["per-package-target"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            forced-target = "foo-unknown-none"
+            default-target = "foo-unknown-none"
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  only one of `forced-target` or `default-target` can be specified
+",
+        )
+        .run();
+}
+
+#[test]
+fn forced_target_per_member() {
+    let host = rustc_host();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["forced", "plain"]
+        "#,
+        )
+        .file(
+            "forced/Cargo.toml",
+            &format!(
+                r#"
+                cargo-features = ["per-package-target"]
+
+                [package]
+                name = "forced"
+                version = "0.0.1"
+                authors = []
+                forced-target = "{}"
+            "#,
+                host
+            ),
+        )
+        .file("forced/src/main.rs", "fn main() {}")
+        .file("plain/Cargo.toml", &basic_manifest("plain", "0.0.1"))
+        .file("plain/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -v")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains(format!(
+            "[RUNNING] `rustc --crate-name forced [..]--target {} [..]`",
+            host
+        ))
+        .with_stderr_line_without(&["[RUNNING] `rustc --crate-name plain"], &["--target"])
+        .run();
+
+    assert!(p.target_bin(&host, "forced").is_file());
+    assert!(!p.bin("forced").is_file());
+    assert!(p.bin("plain").is_file());
+    assert!(!p.target_bin(&host, "plain").is_file());
+}
+
+#[test]
+fn default_target_overridden_by_flag() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let host = rustc_host();
+    let target = cross_compile::alternate();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                cargo-features = ["per-package-target"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+                default-target = "{}"
+            "#,
+                host
+            ),
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build").masquerade_as_nightly_cargo().run();
+    assert!(p.target_bin(&host, "foo").is_file());
+
+    p.cargo("build --target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo()
+        .run();
+    assert!(p.target_bin(&target, "foo").is_file());
+}
+
+#[test]
+fn forced_target_mixed_with_cross() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let target = cross_compile::alternate();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["firmware", "tool"]
+        "#,
+        )
+        .file(
+            "firmware/Cargo.toml",
+            &format!(
+                r#"
+                cargo-features = ["per-package-target"]
+
+                [package]
+                name = "firmware"
+                version = "0.0.1"
+                authors = []
+                build = "build.rs"
+                forced-target = "{}"
+            "#,
+                target
+            ),
+        )
+        .file(
+            "firmware/build.rs",
+            &format!(
+                r#"
+                fn main() {{
+                    assert_eq!(std::env::var("TARGET").unwrap(), "{}");
+                }}
+            "#,
+                target
+            ),
+        )
+        .file(
+            "firmware/src/main.rs",
+            &format!(
+                r#"
+                fn main() {{
+                    assert_eq!(std::env::consts::ARCH, "{}");
+                }}
+            "#,
+                cross_compile::alternate_arch()
+            ),
+        )
+        .file("tool/Cargo.toml", &basic_manifest("tool", "0.0.1"))
+        .file("tool/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build").masquerade_as_nightly_cargo().run();
+    assert!(p.target_bin(&target, "firmware").is_file());
+    assert!(p.bin("tool").is_file());
+    p.process(&p.target_bin(&target, "firmware")).run();
+}
+
+#[test]
+fn forced_target_uses_its_runner() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let target = cross_compile::alternate();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                cargo-features = ["per-package-target"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+                forced-target = "{}"
+            "#,
+                target
+            ),
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config",
+            &format!(
+                r#"
+                [target.{}]
+                runner = "nonexistent-runner -r"
+            "#,
+                target
+            ),
+        )
+        .build();
+
+    p.cargo("run")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(&format!(
+            "[RUNNING] `nonexistent-runner -r target/{}/debug/foo[EXE]`",
+            target
+        ))
+        .run();
+}
@@ -0,0 +1,124 @@
+use crate::support::{basic_manifest, project, Project};
+
+fn pipelined_project() -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#,
+        )
+        .file(".cargo/config", "[build]\npipelining = true")
+        .file("src/lib.rs", "pub fn foo() { bar::bar(); }")
+        .file("src/main.rs", "fn main() { foo::foo(); }")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .build()
+}
+
+#[test]
+fn libraries_start_from_metadata() {
+    let p = pipelined_project();
+
+    p.cargo("build -v")
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name bar [..]--error-format=json --json=artifacts \
+             [..]--emit=dep-info,metadata,link [..]`",
+        )
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name foo src/lib.rs [..]--extern bar=[..]libbar-[..].rmeta[..]`",
+        )
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name foo src/main.rs [..]--extern bar=[..]libbar-[..].rlib[..]`",
+        )
+        .run();
+    p.process(&p.bin("foo")).run();
+
+    // Both the metadata and the library are tracked, so nothing is rebuilt.
+    p.cargo("build -v")
+        .with_stderr(
+            "\
+[FRESH] bar v0.0.1 ([..])
+[FRESH] foo v0.0.1 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[test]
+fn not_enabled_by_default() {
+    let p = pipelined_project();
+    p.change_file(".cargo/config", "");
+
+    p.cargo("build -v")
+        .with_stderr_does_not_contain("[..]--json=artifacts[..]")
+        .with_stderr_does_not_contain("[..].rmeta[..]")
+        .run();
+}
+
+#[test]
+fn check_is_not_pipelined() {
+    let p = pipelined_project();
+
+    p.cargo("check -v")
+        .with_stderr_does_not_contain("[..]--json=artifacts[..]")
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name bar [..]--emit=dep-info,metadata [..]`",
+        )
+        .run();
+}
+
+#[test]
+fn warnings_are_rendered() {
+    let p = pipelined_project();
+    p.change_file("bar/src/lib.rs", "pub fn bar() { let x = 1; }");
+
+    p.cargo("build")
+        .with_stderr_contains("[WARNING] unused variable: `x`")
+        .with_stderr_contains("[..]--> bar/src/lib.rs:1:20")
+        .with_stderr_does_not_contain("[..]\"rendered\"[..]")
+        .with_stderr_does_not_contain("[..]\"artifact\"[..]")
+        .run();
+}
+
+#[test]
+fn short_message_format() {
+    let p = pipelined_project();
+    p.change_file("bar/src/lib.rs", "pub fn bar() { let x = 1; }");
+
+    p.cargo("build --message-format=short")
+        .with_stderr_contains("bar/src/lib.rs:1:20: [WARNING] unused variable: `x`[..]")
+        .run();
+}
+
+#[test]
+fn json_message_format() {
+    let p = pipelined_project();
+    p.change_file("bar/src/lib.rs", "pub fn bar() { let x = 1; }");
+
+    p.cargo("build --message-format=json")
+        .with_stdout_contains(
+            r#"{"reason":"compiler-message",[..]"message":"unused variable: `x`"[..]"#,
+        )
+        .with_stdout_does_not_contain(r#"[..]"artifact":"[..]"#)
+        .run();
+}
+
+#[test]
+fn errors_abort_the_build() {
+    let p = pipelined_project();
+    p.change_file("bar/src/lib.rs", "pub fn bar() { missing(); }");
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr_contains("error[E0425]: cannot find function `missing` in this scope")
+        .with_stderr_does_not_contain("[COMPILING] foo [..]")
+        .run();
+}
@@ -1,4 +1,4 @@
-use crate::support::registry::Package;
+use crate::support::registry::{registry_url, Dependency, Package};
 use crate::support::{is_nightly, project};
 
 #[test]
@@ -202,3 +202,171 @@ Caused by:
         )
         .run()
 }
+
+#[test]
+fn pub_dep_version_conflict() {
+    Package::new("shared", "0.1.0").publish();
+    Package::new("shared", "0.2.0").publish();
+    Package::new("middle", "0.1.0")
+        .add_dep(Dependency::new("shared", "0.1.0").public(true))
+        .file("src/lib.rs", "")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["public-dependency"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            middle = "0.1.0"
+            shared = "0.2.0"
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+the package `foo` can only see one version of each crate through its public dependencies, [..]",
+        )
+        .run()
+}
+
+#[test]
+fn pub_dep_versions_unify() {
+    Package::new("shared", "0.1.0").publish();
+    Package::new("shared", "0.1.1").publish();
+    Package::new("middle", "0.1.0")
+        .add_dep(Dependency::new("shared", "=0.1.0").public(true))
+        .file("src/lib.rs", "")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["public-dependency"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            middle = "0.1.0"
+            shared = "0.1.0"
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("generate-lockfile")
+        .masquerade_as_nightly_cargo()
+        .run();
+    p.cargo("pkgid shared")
+        .masquerade_as_nightly_cargo()
+        .with_stdout("[..]#shared:0.1.0")
+        .run();
+}
+
+#[test]
+fn package_warns_exported_private_dependency() {
+    if !is_nightly() {
+        return;
+    }
+    Package::new("priv_dep", "0.1.0")
+        .file("src/lib.rs", "pub struct FromPriv;")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["public-dependency"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            priv_dep = "0.1.0"
+        "#,
+        )
+        .file(
+            "src/lib.rs",
+            "
+            extern crate priv_dep;
+            pub fn use_priv(_: priv_dep::FromPriv) {}
+        ",
+        )
+        .build();
+
+    p.cargo("package")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains(
+            "[..]type `priv_dep::FromPriv` from private dependency 'priv_dep' in public interface",
+        )
+        .run()
+}
+
+#[test]
+fn publish_warns_exported_private_dependency() {
+    if !is_nightly() {
+        return;
+    }
+    Package::new("priv_dep", "0.1.0")
+        .file("src/lib.rs", "pub struct FromPriv;")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["public-dependency"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            priv_dep = "0.1.0"
+        "#,
+        )
+        .file(
+            "src/lib.rs",
+            "
+            extern crate priv_dep;
+            pub fn use_priv(_: priv_dep::FromPriv) {}
+        ",
+        )
+        .build();
+
+    p.cargo("publish --dry-run --index")
+        .arg(registry_url().to_string())
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains(
+            "[..]type `priv_dep::FromPriv` from private dependency 'priv_dep' in public interface",
+        )
+        .with_stderr_contains("[UPLOADING] foo v0.0.1 ([CWD])")
+        .run();
+
+    // The warning comes from building the packaged crate, which is skipped.
+    p.cargo("publish --dry-run --no-verify --index")
+        .arg(registry_url().to_string())
+        .masquerade_as_nightly_cargo()
+        .with_stderr_does_not_contain("[..]private dependency[..]")
+        .run();
+}
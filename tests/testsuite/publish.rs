@@ -1013,3 +1013,232 @@ fn publish_checks_for_token_before_verify() {
         .with_stderr_contains("[VERIFYING] foo v0.0.1 ([CWD])")
         .run();
 }
+
+fn workspace_with_dependent_members() -> crate::support::Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["a", "b"]
+        "#,
+        )
+        .file(
+            "b/Cargo.toml",
+            r#"
+            [project]
+            name = "b"
+            version = "0.1.0"
+            authors = []
+            license = "MIT"
+            description = "b"
+
+            [dependencies]
+            a = { path = "../a", version = "0.1.0" }
+        "#,
+        )
+        .file("b/src/lib.rs", "pub fn b() { a::a(); }")
+        .file(
+            "a/Cargo.toml",
+            r#"
+            [project]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+            license = "MIT"
+            description = "a"
+        "#,
+        )
+        .file("a/src/lib.rs", "pub fn a() {}")
+        .build()
+}
+
+#[test]
+fn publish_workspace_in_dependency_order() {
+    registry::init();
+
+    let p = workspace_with_dependent_members();
+
+    p.cargo("publish --workspace --no-verify --index")
+        .arg(registry_url().to_string())
+        .with_stderr(
+            "\
+[UPDATING] [..]
+[WARNING] manifest has no documentation, [..]
+See [..]
+[PACKAGING] a v0.1.0 ([CWD]/a)
+[WARNING] manifest has no documentation, [..]
+See [..]
+[PACKAGING] b v0.1.0 ([CWD]/b)
+[UPLOADING] a v0.1.0 ([CWD]/a)
+[UPLOADING] b v0.1.0 ([CWD]/b)
+",
+        )
+        .run();
+
+    // The upload API only keeps the last request around.
+    publish::validate_upload(
+        r#"
+        {
+          "authors": [],
+          "badges": {},
+          "categories": [],
+          "deps": [
+            {
+              "default_features": true,
+              "features": [],
+              "kind": "normal",
+              "name": "a",
+              "optional": false,
+              "registry": "https://github.com/rust-lang/crates.io-index",
+              "target": null,
+              "version_req": "^0.1.0"
+            }
+          ],
+          "description": "b",
+          "documentation": null,
+          "features": {},
+          "homepage": null,
+          "keywords": [],
+          "license": "MIT",
+          "license_file": null,
+          "links": null,
+          "name": "b",
+          "readme": null,
+          "readme_file": null,
+          "repository": null,
+          "vers": "0.1.0"
+          }
+        "#,
+        "b-0.1.0.crate",
+        &["Cargo.toml", "Cargo.toml.orig", "src/lib.rs"],
+    );
+}
+
+#[test]
+fn publish_workspace_verifies_against_unpublished_members() {
+    registry::init();
+
+    let p = workspace_with_dependent_members();
+
+    p.cargo("publish --workspace --dry-run --index")
+        .arg(registry_url().to_string())
+        .with_stderr(
+            "\
+[UPDATING] [..]
+[WARNING] manifest has no documentation, [..]
+See [..]
+[PACKAGING] a v0.1.0 ([CWD]/a)
+[WARNING] manifest has no documentation, [..]
+See [..]
+[PACKAGING] b v0.1.0 ([CWD]/b)
+[VERIFYING] a v0.1.0 ([CWD]/a)
+[COMPILING] a v0.1.0 ([CWD]/target/package/a-0.1.0)
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+[VERIFYING] b v0.1.0 ([CWD]/b)
+[UNPACKING] a v0.1.0 ([..])
+[COMPILING] a v0.1.0
+[COMPILING] b v0.1.0 ([CWD]/target/package/b-0.1.0)
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+[UPLOADING] a v0.1.0 ([CWD]/a)
+[WARNING] aborting upload due to dry run
+[UPLOADING] b v0.1.0 ([CWD]/b)
+[WARNING] aborting upload due to dry run
+",
+        )
+        .run();
+
+    // The unpublished member is unpacked next to the temporary registry,
+    // the shared source cache is left alone.
+    assert!(p
+        .root()
+        .join("target/package/tmp-registry/src/a-0.1.0/Cargo.toml")
+        .is_file());
+    assert!(!paths::home().join(".cargo/registry/src").exists());
+}
+
+#[test]
+fn publish_exclude_requires_workspace() {
+    registry::init();
+
+    let p = workspace_with_dependent_members();
+
+    p.cargo("publish --exclude a")
+        .with_status(101)
+        .with_stderr("[ERROR] --exclude can only be used together with --workspace")
+        .run();
+}
+
+fn workspace_with_unpublished_member() -> crate::support::Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["a", "xtask"]
+        "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+            [project]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+            license = "MIT"
+            description = "a"
+        "#,
+        )
+        .file("a/src/lib.rs", "")
+        .file(
+            "xtask/Cargo.toml",
+            r#"
+            [project]
+            name = "xtask"
+            version = "0.1.0"
+            authors = []
+            publish = false
+        "#,
+        )
+        .file("xtask/src/main.rs", "fn main() {}")
+        .build()
+}
+
+#[test]
+fn publish_workspace_skips_unpublishable_members() {
+    registry::init();
+
+    let p = workspace_with_unpublished_member();
+
+    p.cargo("publish --workspace --no-verify --index")
+        .arg(registry_url().to_string())
+        .with_stderr(
+            "\
+[SKIPPING] xtask v0.1.0 ([CWD]/xtask) (not allowed to be published to `crates-io`)
+[UPDATING] [..]
+[WARNING] manifest has no documentation, [..]
+See [..]
+[PACKAGING] a v0.1.0 ([CWD]/a)
+[UPLOADING] a v0.1.0 ([CWD]/a)
+",
+        )
+        .run();
+}
+
+#[test]
+fn publish_unpublishable_member_explicitly() {
+    registry::init();
+
+    let p = workspace_with_unpublished_member();
+
+    p.cargo("publish -p xtask --no-verify --index")
+        .arg(registry_url().to_string())
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `xtask` cannot be published.
+The registry `crates-io` is not listed in the `publish` value in Cargo.toml.
+",
+        )
+        .run();
+}
@@ -559,6 +559,47 @@ fn test_resolving_minimum_version_with_transitive_deps() {
     assert!(!res.contains(&("util", "1.0.0").to_pkgid()));
 }
 
+#[test]
+fn test_resolving_direct_minimum_version() {
+    enable_nightly_features(); // -Z direct-minimal-versions
+                               // Only the dependencies of the root are resolved to their minimum
+                               // versions, "util" is a dependency of "foo" so its maximum version
+                               // is selected.
+    let reg = registry(vec![
+        pkg!(("util", "1.2.2")),
+        pkg!(("util", "1.0.0")),
+        pkg!(("util", "1.1.1")),
+        pkg!(("foo", "1.0.0") => [dep_req("util", "1.0.0")]),
+        pkg!(("foo", "1.1.0") => [dep_req("util", "1.0.0")]),
+    ]);
+
+    let mut config = Config::default().unwrap();
+    config
+        .configure(
+            1,
+            None,
+            &None,
+            false,
+            false,
+            &None,
+            &["direct-minimal-versions".to_string()],
+        )
+        .unwrap();
+
+    let res = resolve_with_config(
+        pkg_id("root"),
+        vec![dep_req("foo", "1.0.0")],
+        &reg,
+        Some(&config),
+    )
+    .unwrap();
+
+    assert_same(
+        &res,
+        &names(&[("root", "1.0.0"), ("foo", "1.0.0"), ("util", "1.2.2")]),
+    );
+}
+
 // Ensure that the "-Z minimal-versions" CLI option works and the minimal
 // version of a dependency ends up in the lock file.
 #[test]
@@ -591,6 +632,57 @@ fn minimal_version_cli() {
     assert!(lock.contains("dep 1.0.0"));
 }
 
+// Ensure that the "-Z direct-minimal-versions" CLI option works, and only
+// the dependencies of the workspace members get their minimal versions.
+#[test]
+fn direct_minimal_version_cli() {
+    Package::new("dep", "1.0.0").publish();
+    Package::new("dep", "1.1.0").publish();
+    Package::new("indirect", "1.0.0").publish();
+    Package::new("indirect", "1.1.0").publish();
+    Package::new("direct", "1.0.0")
+        .dep("indirect", "1.0")
+        .publish();
+    Package::new("direct", "1.1.0")
+        .dep("indirect", "1.0")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+
+            [dependencies]
+            dep = "1.0"
+            direct = "1.0"
+        "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("generate-lockfile -Zdirect-minimal-versions")
+        .masquerade_as_nightly_cargo()
+        .run();
+
+    let lock = p.read_lockfile();
+
+    assert!(lock.contains("dep 1.0.0"));
+    assert!(lock.contains("direct 1.0.0"));
+    assert!(lock.contains("indirect 1.1.0"));
+
+    p.cargo("generate-lockfile -Zminimal-versions -Zdirect-minimal-versions")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] `-Z minimal-versions` and `-Z direct-minimal-versions` can't be used together",
+        )
+        .run();
+}
+
 #[test]
 fn resolving_incompat_versions() {
     let reg = registry(vec![
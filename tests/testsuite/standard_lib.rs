@@ -0,0 +1,353 @@
+use crate::support::registry::{Dependency, Package};
+use crate::support::{is_nightly, paths, project, rustc_host, Project};
+
+/// Creates a tiny stand-in for the `rust-src` component, laid out like the
+/// standard library's own workspace. Every crate re-exports the real one from
+/// the sysroot, so the result can be linked against like the real thing.
+fn setup() -> Project {
+    // A crates.io package which, like the real `compiler_builtins` and
+    // friends, depends back on `core` through a shim package.
+    Package::new("registry-dep-using-core", "1.0.0")
+        .file(
+            "src/lib.rs",
+            "
+                #![no_std]
+
+                pub fn custom_api() {}
+            ",
+        )
+        .add_dep(Dependency::new("rustc-std-workspace-core", "*").optional(true))
+        .feature("mockbuild", &["rustc-std-workspace-core"])
+        .publish();
+
+    project()
+        .at("sysroot-src")
+        .file("src/libcore/Cargo.toml", &lib_manifest("core", "", ""))
+        .file(
+            "src/libcore/lib.rs",
+            r#"
+                #![feature(staged_api)]
+                #![stable(since = "1.0.0", feature = "core")]
+                #![no_std]
+
+                #[stable(since = "1.0.0", feature = "core")]
+                pub use core::*;
+
+                #[stable(since = "1.0.0", feature = "core")]
+                pub fn custom_api() {}
+            "#,
+        )
+        .file(
+            "src/liballoc/Cargo.toml",
+            &lib_manifest("alloc", "core = { path = \"../libcore\" }", ""),
+        )
+        .file(
+            "src/liballoc/lib.rs",
+            r#"
+                #![feature(staged_api)]
+                #![stable(since = "1.0.0", feature = "alloc")]
+                #![no_std]
+
+                extern crate alloc as real_alloc;
+
+                #[stable(since = "1.0.0", feature = "alloc")]
+                pub use real_alloc::*;
+            "#,
+        )
+        .file(
+            "src/libstd/Cargo.toml",
+            &lib_manifest(
+                "std",
+                r#"
+                    core = { path = "../libcore" }
+                    alloc = { path = "../liballoc" }
+                    panic_unwind = { path = "../libpanic_unwind" }
+                    compiler_builtins = { path = "../libcompiler_builtins" }
+                    registry-dep-using-core = { version = "1.0", features = ["mockbuild"] }
+                "#,
+                r#"
+                    panic-unwind = []
+                    backtrace = []
+                "#,
+            ),
+        )
+        .file(
+            "src/libstd/lib.rs",
+            r#"
+                #![feature(staged_api)]
+                #![stable(since = "1.0.0", feature = "std")]
+
+                #[stable(since = "1.0.0", feature = "std")]
+                pub use std::*;
+
+                #[stable(since = "1.0.0", feature = "std")]
+                pub fn custom_api() {
+                    registry_dep_using_core::custom_api();
+                }
+            "#,
+        )
+        .file(
+            "src/libpanic_unwind/Cargo.toml",
+            &lib_manifest("panic_unwind", "", ""),
+        )
+        .file(
+            "src/libpanic_unwind/lib.rs",
+            r#"
+                #![feature(panic_unwind, panic_runtime)]
+                #![panic_runtime]
+                #![no_std]
+
+                extern crate panic_unwind;
+            "#,
+        )
+        .file(
+            "src/libcompiler_builtins/Cargo.toml",
+            &lib_manifest("compiler_builtins", "", ""),
+        )
+        .file("src/libcompiler_builtins/lib.rs", "#![no_std]")
+        .file(
+            "src/libtest/Cargo.toml",
+            &lib_manifest(
+                "test",
+                "std = { path = \"../libstd\" }",
+                r#"
+                    panic-unwind = ["std/panic-unwind"]
+                    backtrace = ["std/backtrace"]
+                "#,
+            ),
+        )
+        .file(
+            "src/libtest/lib.rs",
+            r#"
+                #![feature(staged_api)]
+                #![feature(test)]
+                #![stable(since = "1.0.0", feature = "test")]
+
+                extern crate test;
+
+                #[stable(since = "1.0.0", feature = "test")]
+                pub use test::*;
+            "#,
+        )
+        .file(
+            "src/tools/rustc-std-workspace-core/Cargo.toml",
+            &shim_manifest(
+                "rustc-std-workspace-core",
+                "core = { path = \"../../libcore\" }",
+            ),
+        )
+        .file(
+            "src/tools/rustc-std-workspace-core/lib.rs",
+            "#![no_std] pub use core::*;",
+        )
+        .file(
+            "src/tools/rustc-std-workspace-alloc/Cargo.toml",
+            &shim_manifest(
+                "rustc-std-workspace-alloc",
+                "alloc = { path = \"../../liballoc\" }",
+            ),
+        )
+        .file(
+            "src/tools/rustc-std-workspace-alloc/lib.rs",
+            "#![no_std] pub use alloc::*;",
+        )
+        .file(
+            "src/tools/rustc-std-workspace-std/Cargo.toml",
+            &shim_manifest(
+                "rustc-std-workspace-std",
+                "std = { path = \"../../libstd\" }",
+            ),
+        )
+        .file(
+            "src/tools/rustc-std-workspace-std/lib.rs",
+            "pub use std::*;",
+        )
+        .build()
+}
+
+fn lib_manifest(name: &str, deps: &str, features: &str) -> String {
+    format!(
+        r#"
+            [package]
+            name = "{}"
+            version = "0.1.0"
+            authors = []
+            edition = "2018"
+
+            [lib]
+            path = "lib.rs"
+
+            [dependencies]
+            {}
+
+            [features]
+            {}
+        "#,
+        name, deps, features
+    )
+}
+
+fn shim_manifest(name: &str, dep: &str) -> String {
+    format!(
+        r#"
+            [package]
+            name = "{}"
+            version = "1.9.0"
+            authors = []
+            edition = "2018"
+
+            [lib]
+            path = "lib.rs"
+
+            [dependencies]
+            {}
+        "#,
+        name, dep
+    )
+}
+
+fn src_root() -> std::path::PathBuf {
+    paths::root().join("sysroot-src")
+}
+
+#[test]
+fn requires_target() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("build -Zbuild-std")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] -Z build-std requires --target")
+        .run();
+}
+
+#[test]
+fn missing_rust_src() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("build -Zbuild-std --target")
+        .arg(rustc_host())
+        .masquerade_as_nightly_cargo()
+        .env("__CARGO_TESTS_ONLY_SRC_ROOT", paths::root().join("missing"))
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] \"[..]/missing/src/libstd/Cargo.toml\" does not exist, unable to build with \
+the standard library, try:
+        rustup component add rust-src
+",
+        )
+        .run();
+}
+
+#[test]
+fn basic() {
+    if !is_nightly() {
+        // Requires `-Z unstable-options` to link against the built crates.
+        return;
+    }
+    setup();
+
+    let p = project()
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    std::custom_api();
+                    foo::f();
+                }
+
+                #[test]
+                fn smoke_bin_unit() {
+                    std::custom_api();
+                    foo::f();
+                }
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                extern crate alloc;
+
+                pub fn f() {
+                    core::custom_api();
+                    std::custom_api();
+                    let _ = alloc::vec::Vec::<u8>::new();
+                }
+
+                #[test]
+                fn smoke_lib_unit() {
+                    f();
+                }
+            "#,
+        )
+        .file(
+            "tests/smoke.rs",
+            r#"
+                #[test]
+                fn smoke_integration() {
+                    std::custom_api();
+                    foo::f();
+                }
+            "#,
+        )
+        .build();
+
+    let target = rustc_host();
+    p.cargo("build -v -Zbuild-std --target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo()
+        .env("__CARGO_TESTS_ONLY_SRC_ROOT", src_root())
+        .with_stderr_contains(
+            "[RUNNING] `rustc [..]--crate-name core [..]-Z force-unstable-if-unmarked[..]`",
+        )
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]--extern 'noprelude:std=[..]`")
+        .run();
+    p.cargo("run -Zbuild-std --target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo()
+        .env("__CARGO_TESTS_ONLY_SRC_ROOT", src_root())
+        .run();
+    p.cargo("test -Zbuild-std --target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo()
+        .env("__CARGO_TESTS_ONLY_SRC_ROOT", src_root())
+        .with_stdout_contains("test smoke_lib_unit ... ok")
+        .with_stdout_contains("test smoke_bin_unit ... ok")
+        .with_stdout_contains("test smoke_integration ... ok")
+        .run();
+}
+
+#[test]
+fn host_units_use_sysroot() {
+    if !is_nightly() {
+        // Requires `-Z unstable-options` to link against the built crates.
+        return;
+    }
+    setup();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                authors = []
+                build = "build.rs"
+            "#,
+        )
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "pub fn f() { std::custom_api(); }")
+        .build();
+
+    p.cargo("build -v -Zbuild-std --target")
+        .arg(rustc_host())
+        .masquerade_as_nightly_cargo()
+        .env("__CARGO_TESTS_ONLY_SRC_ROOT", src_root())
+        .with_stderr_line_without(
+            &["[RUNNING] `rustc --crate-name build_script_build "],
+            &["noprelude"],
+        )
+        .run();
+}
@@ -149,6 +149,7 @@ pub struct Dependency {
     registry: Option<String>,
     package: Option<String>,
     optional: bool,
+    public: bool,
 }
 
 pub fn init() {
@@ -376,6 +377,7 @@ impl Package {
                     "kind": dep.kind,
                     "registry": registry_url,
                     "package": dep.package,
+                    "public": dep.public,
                 })
             })
             .collect::<Vec<_>>();
@@ -445,7 +447,11 @@ impl Package {
     }
 
     fn make_archive(&self) {
-        let mut manifest = format!(
+        let mut manifest = String::new();
+        if self.deps.iter().any(|dep| dep.public) {
+            manifest.push_str("cargo-features = [\"public-dependency\"]\n");
+        }
+        manifest.push_str(&format!(
             r#"
             [package]
             name = "{}"
@@ -453,7 +459,7 @@ impl Package {
             authors = []
         "#,
             self.name, self.vers
-        );
+        ));
         for dep in self.deps.iter() {
             let target = match dep.target {
                 None => String::new(),
@@ -475,6 +481,9 @@ impl Package {
                 assert_eq!(registry, "alternative");
                 manifest.push_str(&format!("registry-index = \"{}\"", alt_registry_url()));
             }
+            if dep.public {
+                manifest.push_str("\npublic = true\n");
+            }
         }
 
         let dst = self.archive_dst();
@@ -541,6 +550,7 @@ impl Dependency {
             features: Vec::new(),
             package: None,
             optional: false,
+            public: false,
             registry: None,
         }
     }
@@ -586,4 +596,11 @@ impl Dependency {
         self.optional = optional;
         self
     }
+
+    /// Changes this to a public dependency, which also adds the
+    /// `public-dependency` feature to the manifest.
+    pub fn public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
 }
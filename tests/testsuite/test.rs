@@ -6,7 +6,7 @@ use cargo;
 use crate::support::paths::CargoPathExt;
 use crate::support::registry::Package;
 use crate::support::{basic_bin_manifest, basic_lib_manifest, basic_manifest, cargo_exe, project};
-use crate::support::{rustc_host, sleep_ms};
+use crate::support::{is_nightly, rustc_host, sleep_ms};
 
 #[test]
 fn cargo_test_simple() {
@@ -3349,6 +3349,7 @@ fn json_artifact_includes_test_flag() {
             r#"
     {
         "reason":"compiler-artifact",
+        "target_triple": "[..]",
         "profile": {
             "debug_assertions": true,
             "debuginfo": 2,
@@ -3369,6 +3370,23 @@ fn json_artifact_includes_test_flag() {
         "filenames":["[..]/foo-[..]"],
         "fresh": false
     }
+
+    {
+        "reason":"test-binary-started",
+        "package_id":"foo 0.0.1 ([..])",
+        "target":"{...}",
+        "executable":"[..]/foo-[..]"
+    }
+
+    {
+        "reason":"test-binary-finished",
+        "package_id":"foo 0.0.1 ([..])",
+        "target":"{...}",
+        "executable":"[..]/foo-[..]",
+        "success":true,
+        "exit_code":null,
+        "duration":"{...}"
+    }
 "#,
         )
         .run();
@@ -3390,6 +3408,7 @@ fn json_artifact_includes_executable_for_library_tests() {
                 "filenames": "{...}",
                 "fresh": false,
                 "package_id": "foo 0.0.1 ([..])",
+                "target_triple": "[..]",
                 "profile": "{...}",
                 "reason": "compiler-artifact",
                 "target": {
@@ -3423,6 +3442,7 @@ fn json_artifact_includes_executable_for_integration_tests() {
                 "filenames": "{...}",
                 "fresh": false,
                 "package_id": "foo 0.0.1 ([..])",
+                "target_triple": "[..]",
                 "profile": "{...}",
                 "reason": "compiler-artifact",
                 "target": {
@@ -3660,3 +3680,567 @@ fn test_dep_with_dev() {
         )
         .run();
 }
+
+fn parallel_binaries_project() -> crate::support::Project {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+            #[test]
+            fn fails_in_lib() { panic!("lib failure"); }
+
+            #[test]
+            fn passes_in_lib() {}
+        "#,
+        )
+        .file(
+            "tests/t1.rs",
+            r#"
+            #[test]
+            fn fails_in_t1() { panic!("t1 failure"); }
+        "#,
+        )
+        .file("tests/t2.rs", "#[test] fn passes_in_t2() {}")
+        .build()
+}
+
+#[test]
+fn parallel_binaries_summarizes_failures() {
+    let p = parallel_binaries_project();
+
+    p.cargo("test --parallel-binaries --no-fail-fast -j2")
+        .with_status(101)
+        .with_stderr_contains("[RUNNING] target/debug/deps/foo-[..][EXE]")
+        .with_stderr_contains("[RUNNING] target/debug/deps/t1-[..][EXE]")
+        .with_stderr_contains("[RUNNING] target/debug/deps/t2-[..][EXE]")
+        .with_stderr_contains("[ERROR] test failed.")
+        .with_stdout_contains("test passes_in_t2 ... ok")
+        .with_stdout_contains(
+            "\
+test binaries: 1 passed; 2 failed
+
+failures across all test binaries:
+    foo (lib): fails_in_lib
+    foo (integration-test \"t1\"): fails_in_t1
+",
+        )
+        .run();
+}
+
+#[test]
+fn parallel_binaries_buffers_output() {
+    let p = parallel_binaries_project();
+
+    // Each binary's output is printed in one piece.
+    p.cargo("test --parallel-binaries --no-fail-fast -j3")
+        .with_status(101)
+        .with_stdout_contains(
+            "\
+running 1 test
+test passes_in_t2 ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out[..]
+",
+        )
+        .run();
+}
+
+#[test]
+fn parallel_binaries_fail_fast() {
+    let p = parallel_binaries_project();
+
+    // The library's tests are run first, and nothing else after them.
+    p.cargo("test --parallel-binaries -j1")
+        .with_status(101)
+        .with_stderr_contains("[RUNNING] target/debug/deps/foo-[..][EXE]")
+        .with_stderr_does_not_contain("[RUNNING] target/debug/deps/t1-[..]")
+        .with_stderr_contains("[ERROR] test failed, to rerun pass '--lib'")
+        .with_stdout_contains("    foo (lib): fails_in_lib")
+        .with_stdout_contains("test binaries: 0 passed; 1 failed; 2 not run")
+        .run();
+}
+
+#[test]
+fn parallel_binaries_pass() {
+    let p = project()
+        .file("src/lib.rs", "#[test] fn in_lib() {}")
+        .file("tests/t1.rs", "#[test] fn in_t1() {}")
+        .file("tests/t2.rs", "#[test] fn in_t2() {}")
+        .build();
+
+    p.cargo("test --parallel-binaries")
+        .with_stdout_contains("test binaries: 3 passed")
+        .run();
+}
+
+#[test]
+fn parallel_binaries_reports_crashes() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[test]]
+            name = "crash"
+            harness = false
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file("tests/crash.rs", "fn main() { std::process::exit(3); }")
+        .build();
+
+    p.cargo("test --parallel-binaries")
+        .with_status(3)
+        .with_stdout_contains(
+            "    foo (integration-test \"crash\"): process didn't exit successfully: [..]crash-[..]",
+        )
+        .run();
+}
+
+#[test]
+fn json_test_binary_messages() {
+    let p = project()
+        .file("src/lib.rs", "#[test] fn in_lib() {}")
+        .file("tests/t1.rs", "#[test] fn in_t1() { panic!() }")
+        .build();
+
+    p.cargo("test --message-format=json --no-fail-fast")
+        .with_status(101)
+        .with_json_contains_unordered(
+            r#"
+            {
+                "reason": "test-binary-started",
+                "package_id": "foo 0.0.1 ([..])",
+                "target": "{...}",
+                "executable": "[..]/foo/target/debug/deps/foo-[..][EXE]"
+            }
+
+            {
+                "reason": "test-binary-finished",
+                "package_id": "foo 0.0.1 ([..])",
+                "target": "{...}",
+                "executable": "[..]/foo/target/debug/deps/foo-[..][EXE]",
+                "success": true,
+                "exit_code": null,
+                "duration": "{...}"
+            }
+
+            {
+                "reason": "test-binary-finished",
+                "package_id": "foo 0.0.1 ([..])",
+                "target": "{...}",
+                "executable": "[..]/foo/target/debug/deps/t1-[..][EXE]",
+                "success": false,
+                "exit_code": 101,
+                "duration": "{...}"
+            }
+"#,
+        )
+        .run();
+}
+
+#[test]
+fn json_libtest_messages() {
+    if !is_nightly() {
+        // Libtest only has JSON output on nightly.
+        return;
+    }
+    let p = project()
+        .file("src/lib.rs", "#[test] fn in_lib() {}")
+        .build();
+
+    p.cargo("test --message-format=json")
+        .with_json_contains_unordered(
+            r#"
+            {
+                "reason": "test-message",
+                "package_id": "foo 0.0.1 ([..])",
+                "target": "{...}",
+                "message": {
+                    "type": "test",
+                    "event": "ok",
+                    "name": "in_lib"
+                }
+            }
+"#,
+        )
+        .run();
+}
+
+#[test]
+fn junit_report() {
+    let p = project()
+        .file(
+            "src/lib.rs",
+            r#"
+            #[test] fn passes() {}
+            #[test] fn fails() { println!("some output"); panic!() }
+            #[test] #[ignore] fn ignored() {}
+        "#,
+        )
+        .file("tests/t1.rs", "#[test] fn in_t1() {}")
+        .build();
+
+    p.cargo("test --junit report.xml --no-fail-fast")
+        .with_status(101)
+        .with_stdout_contains("test fails ... FAILED")
+        .run();
+
+    let report = p.read_file("report.xml");
+    for expected in &[
+        "<testsuites name=\"cargo test\" tests=\"4\" failures=\"1\" skipped=\"1\" time=\"[..]\">",
+        "<testsuite name=\"foo (lib)\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"[..]\">",
+        "<testcase name=\"passes\" classname=\"foo (lib)\"/>",
+        "<testcase name=\"ignored\" classname=\"foo (lib)\">",
+        "<skipped/>",
+        "<testcase name=\"fails\" classname=\"foo (lib)\">",
+        "<failure message=\"test failed\">some output",
+        "<testsuite name=\"foo (integration-test &quot;t1&quot;)\" tests=\"1\" failures=\"0\" skipped=\"0\" time=\"[..]\">",
+        "<testcase name=\"in_t1\" classname=\"foo (integration-test &quot;t1&quot;)\"/>",
+    ] {
+        assert!(
+            report
+                .lines()
+                .any(|line| crate::support::lines_match(expected, line.trim())),
+            "`{}` not found in:\n{}",
+            expected,
+            report
+        );
+    }
+}
+
+fn partitions_project() -> crate::support::Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            autotests = true
+
+            [[test]]
+            name = "no_harness"
+            harness = false
+        "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+            #[test] fn a() {}
+            #[test] fn b() {}
+            #[test] fn c() {}
+            #[test] fn d() {}
+            #[test] fn e() {}
+            #[test] fn f() {}
+        "#,
+        )
+        .file("tests/t1.rs", "#[test] fn t1_a() {} #[test] fn t1_b() {}")
+        .file("tests/no_harness.rs", "fn main() {}")
+        .build()
+}
+
+/// The tests in each partition, from the output of `--list-partitions`.
+fn list_partitions(p: &crate::support::Project, partition: &str) -> Vec<Vec<String>> {
+    let output = p
+        .cargo(&format!("test --list-partitions --partition {}", partition))
+        .exec_with_output()
+        .unwrap();
+    let mut partitions = Vec::new();
+    for line in String::from_utf8(output.stdout).unwrap().lines() {
+        if line.starts_with("partition ") {
+            partitions.push(Vec::new());
+        } else {
+            partitions.last_mut().unwrap().push(line.trim().to_string());
+        }
+    }
+    partitions
+}
+
+#[test]
+fn partition_runs_share_of_tests() {
+    let p = partitions_project();
+    let partitions = list_partitions(&p, "hash:1/3");
+    assert_eq!(partitions.len(), 3);
+    let mut all = partitions.concat();
+    all.sort();
+    assert_eq!(
+        all,
+        [
+            "foo (doc-tests)",
+            "foo (integration-test \"no_harness\")",
+            "foo (integration-test \"t1\"): t1_a",
+            "foo (integration-test \"t1\"): t1_b",
+            "foo (lib): a",
+            "foo (lib): b",
+            "foo (lib): c",
+            "foo (lib): d",
+            "foo (lib): e",
+            "foo (lib): f",
+        ]
+    );
+
+    // Each test is run in the partition it was listed in, and only there.
+    for (i, tests) in partitions.iter().enumerate() {
+        let output = p
+            .cargo(&format!("test --partition hash:{}/3", i + 1))
+            .exec_with_output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        for test in &all {
+            let ran = match test.find("): ") {
+                Some(i) => stdout.contains(&format!("test {} ... ok", &test[i + 3..])),
+                None if test.ends_with("(doc-tests)") => stderr.contains("Doc-tests foo"),
+                None => stderr.contains("no_harness-"),
+            };
+            assert_eq!(ran, tests.contains(test), "{} in {:?}", test, tests);
+        }
+    }
+}
+
+#[test]
+fn partition_count_evens_out() {
+    let p = partitions_project();
+    let partitions = list_partitions(&p, "count:1/2");
+    let lib_tests = |tests: &Vec<String>| tests.iter().filter(|t| t.contains("(lib)")).count();
+    assert_eq!(lib_tests(&partitions[0]), 3);
+    assert_eq!(lib_tests(&partitions[1]), 3);
+}
+
+#[test]
+fn partition_stable_when_adding_tests() {
+    let p = partitions_project();
+    for partition in &["hash:1/4", "count:1/4"] {
+        let before = list_partitions(&p, partition);
+        p.change_file("tests/t2.rs", "#[test] fn t2_a() {} #[test] fn t2_b() {}");
+        let after = list_partitions(&p, partition);
+        for (before, after) in before.iter().zip(&after) {
+            let after = after
+                .iter()
+                .filter(|t| !t.contains("\"t2\""))
+                .collect::<Vec<_>>();
+            assert_eq!(before.iter().collect::<Vec<_>>(), after);
+        }
+        std::fs::remove_file(p.root().join("tests/t2.rs")).unwrap();
+    }
+}
+
+#[test]
+fn partition_applies_filters() {
+    let p = partitions_project();
+
+    p.cargo("test --lib --partition hash:1/1 a -- --test-threads 1")
+        .with_stdout_contains("test a ... ok")
+        .with_stdout_does_not_contain("test b ... ok")
+        .run();
+
+    p.cargo("test --lib --partition hash:1/1 a -- --exact")
+        .with_stdout_contains("test a ... ok")
+        .with_stdout_contains(
+            "test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 5 filtered out[..]",
+        )
+        .run();
+
+    p.cargo("test --lib --partition hash:1/1 -- --skip a")
+        .with_stdout_contains(
+            "test result: ok. 5 passed; 0 failed; 0 ignored; 0 measured; 1 filtered out[..]",
+        )
+        .run();
+}
+
+#[test]
+fn partition_invalid() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("test --partition hash:3/2")
+        .with_status(101)
+        .with_stderr("[ERROR] invalid partition `hash:3/2`, N must be between 1 and M")
+        .run();
+
+    p.cargo("test --partition slice:1/2")
+        .with_status(101)
+        .with_stderr("[ERROR] invalid partition `slice:1/2`, expected `count:N/M` or `hash:N/M`")
+        .run();
+}
+
+#[test]
+fn test_timeout_kills_hung_test() {
+    let p = project()
+        .file(
+            "src/lib.rs",
+            r#"
+            #[test]
+            fn hangs() {
+                std::thread::sleep(std::time::Duration::from_secs(60));
+            }
+        "#,
+        )
+        .build();
+
+    p.cargo("test --test-timeout 1")
+        .with_status(101)
+        .with_stderr_contains("[ERROR] process timed out after 1 seconds: `[..]foo-[..]`[..]")
+        .run();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_timeout_kills_spawned_processes() {
+    // The spawned process would keep the output of the test open, and cargo
+    // waiting for it, if it weren't killed too.
+    let p = project()
+        .file(
+            "src/lib.rs",
+            r#"
+            #[test]
+            fn hangs() {
+                std::process::Command::new("sleep").arg("60").spawn().unwrap();
+                std::thread::sleep(std::time::Duration::from_secs(60));
+            }
+        "#,
+        )
+        .build();
+
+    p.cargo("build --tests").run();
+    let start = std::time::Instant::now();
+    p.cargo("test --test-timeout 1 --message-format=json")
+        .with_status(101)
+        .with_stderr_contains("[..]process timed out after 1 seconds[..]")
+        .with_json_contains_unordered(
+            r#"
+            {
+                "reason": "test-binary-finished",
+                "package_id": "foo 0.0.1 ([..])",
+                "target": "{...}",
+                "executable": "[..]",
+                "success": false,
+                "exit_code": null,
+                "duration": "{...}"
+            }
+"#,
+        )
+        .run();
+    assert!(start.elapsed() < std::time::Duration::from_secs(30));
+}
+
+#[test]
+fn test_timeout_parallel_binaries() {
+    let p = project()
+        .file("src/lib.rs", "#[test] fn in_lib() {}")
+        .file(
+            "tests/hangs.rs",
+            r#"
+            #[test]
+            fn hangs() {
+                std::thread::sleep(std::time::Duration::from_secs(60));
+            }
+        "#,
+        )
+        .build();
+
+    p.cargo("test --test-timeout 1 --parallel-binaries")
+        .with_status(101)
+        .with_stdout_contains("test binaries: 1 passed; 1 failed")
+        .with_stdout_contains(
+            "    foo (integration-test \"hangs\"): process timed out after 1 seconds: [..]",
+        )
+        .run();
+}
+
+#[test]
+fn test_timeout_per_target() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["test-timeout"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[test]]
+            name = "hangs"
+            timeout = 1
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "tests/hangs.rs",
+            r#"
+            #[test]
+            fn hangs() {
+                std::thread::sleep(std::time::Duration::from_secs(60));
+            }
+        "#,
+        )
+        .build();
+
+    p.cargo("test")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] process timed out after 1 seconds: `[..]hangs-[..]`[..]")
+        .run();
+
+    // The command line takes precedence.
+    p.cargo("test --test-timeout 2")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] process timed out after 2 seconds: `[..]hangs-[..]`[..]")
+        .run();
+}
+
+#[test]
+fn test_timeout_per_target_unstable() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [lib]
+            timeout = 1
+        "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("test")
+        .with_status(101)
+        .with_stderr_contains("  the `timeout` key is unstable")
+        .run();
+}
+
+#[test]
+fn report_slow() {
+    let p = project()
+        .file(
+            "src/lib.rs",
+            r#"
+            #[test]
+            fn slow() {
+                std::thread::sleep(std::time::Duration::from_millis(1500));
+            }
+        "#,
+        )
+        .file("tests/fast.rs", "#[test] fn fast() {}")
+        .build();
+
+    p.cargo("test --report-slow 1")
+        .with_stderr_contains(
+            "[WARNING] 1 test binary ran for longer than 1 seconds:\n    foo (lib): [..]s",
+        )
+        .with_stderr_does_not_contain("[..]integration-test[..]")
+        .run();
+}
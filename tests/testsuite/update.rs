@@ -395,6 +395,326 @@ fn update_precise() {
         .run();
 }
 
+#[test]
+fn update_json() {
+    Package::new("log", "0.1.0").publish();
+    Package::new("serde", "0.1.0").dep("log", "0.1").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "bar"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                serde = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("generate-lockfile").run();
+    let lockfile = p.read_lockfile();
+
+    Package::new("itoa", "0.1.0").publish();
+    Package::new("serde", "0.1.1").dep("itoa", "0.1").publish();
+
+    p.cargo("update --dry-run --message-format json")
+        .with_json(
+            r#"
+                {
+                    "reason": "lockfile-change",
+                    "name": "itoa",
+                    "change": "added",
+                    "old_version": null,
+                    "new_version": "0.1.0",
+                    "old_source": null,
+                    "new_source": "registry+https://github.com/rust-lang/crates.io-index"
+                }
+
+                {
+                    "reason": "lockfile-change",
+                    "name": "log",
+                    "change": "removed",
+                    "old_version": "0.1.0",
+                    "new_version": null,
+                    "old_source": "registry+https://github.com/rust-lang/crates.io-index",
+                    "new_source": null
+                }
+
+                {
+                    "reason": "lockfile-change",
+                    "name": "serde",
+                    "change": "upgraded",
+                    "old_version": "0.1.0",
+                    "new_version": "0.1.1",
+                    "old_source": "registry+https://github.com/rust-lang/crates.io-index",
+                    "new_source": "registry+https://github.com/rust-lang/crates.io-index"
+                }
+            "#,
+        )
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[ADDING] itoa v0.1.0
+[REMOVING] log v0.1.0
+[UPDATING] serde v0.1.0 -> v0.1.1
+[WARNING] not updating lockfile due to dry run
+",
+        )
+        .run();
+    assert_eq!(p.read_lockfile(), lockfile);
+
+    p.cargo("update -p serde --precise 0.1.0 --message-format json")
+        .with_stdout("")
+        .run();
+    p.cargo("update -p serde --message-format json")
+        .with_json(
+            r#"
+                {
+                    "reason": "lockfile-change",
+                    "name": "itoa",
+                    "change": "added",
+                    "old_version": null,
+                    "new_version": "0.1.0",
+                    "old_source": null,
+                    "new_source": "{...}"
+                }
+
+                {
+                    "reason": "lockfile-change",
+                    "name": "log",
+                    "change": "removed",
+                    "old_version": "0.1.0",
+                    "new_version": null,
+                    "old_source": "{...}",
+                    "new_source": null
+                }
+
+                {
+                    "reason": "lockfile-change",
+                    "name": "serde",
+                    "change": "upgraded",
+                    "old_version": "0.1.0",
+                    "new_version": "0.1.1",
+                    "old_source": "{...}",
+                    "new_source": "{...}"
+                }
+            "#,
+        )
+        .run();
+    p.cargo("update -p serde --precise 0.1.0 --message-format json")
+        .with_json(
+            r#"
+                {
+                    "reason": "lockfile-change",
+                    "name": "itoa",
+                    "change": "removed",
+                    "old_version": "0.1.0",
+                    "new_version": null,
+                    "old_source": "{...}",
+                    "new_source": null
+                }
+
+                {
+                    "reason": "lockfile-change",
+                    "name": "log",
+                    "change": "added",
+                    "old_version": null,
+                    "new_version": "0.1.0",
+                    "old_source": null,
+                    "new_source": "{...}"
+                }
+
+                {
+                    "reason": "lockfile-change",
+                    "name": "serde",
+                    "change": "downgraded",
+                    "old_version": "0.1.1",
+                    "new_version": "0.1.0",
+                    "old_source": "{...}",
+                    "new_source": "{...}"
+                }
+            "#,
+        )
+        .run();
+}
+
+#[test]
+fn update_json_source_changed() {
+    Package::new("serde", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "bar"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                serde = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("serde/Cargo.toml", &basic_manifest("serde", "0.1.0"))
+        .file("serde/src/lib.rs", "")
+        .build();
+
+    p.cargo("generate-lockfile").run();
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            serde = { path = "serde" }
+        "#,
+    );
+
+    p.cargo("update --message-format json")
+        .with_json(
+            r#"
+                {
+                    "reason": "lockfile-change",
+                    "name": "serde",
+                    "change": "source-changed",
+                    "old_version": "0.1.0",
+                    "new_version": "0.1.0",
+                    "old_source": "registry+https://github.com/rust-lang/crates.io-index",
+                    "new_source": null
+                }
+            "#,
+        )
+        .with_stderr(
+            "\
+[ADDING] serde v0.1.0 ([CWD]/serde)
+[REMOVING] serde v0.1.0
+",
+        )
+        .run();
+}
+
+fn breaking_project() -> crate::support::Project {
+    Package::new("itoa", "0.1.0").publish();
+    Package::new("log", "1.0.0").publish();
+    Package::new("serde", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                serde = "0.1" # not the newest
+                itoa = '0.1'
+
+                [dev-dependencies.log]
+                version = "^1.0"
+                default-features = false
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("itoa", "0.1.1").publish();
+    Package::new("log", "2.1.0").publish();
+    Package::new("serde", "0.2.0").publish();
+    Package::new("serde", "0.3.0-alpha.1").publish();
+    p
+}
+
+#[test]
+fn update_breaking() {
+    let p = breaking_project();
+
+    p.cargo("update --breaking")
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[UPGRADING] serde 0.1 -> 0.2 in foo
+[UPGRADING] log ^1.0 -> ^2.1 in foo
+[UPDATING] log v1.0.0 -> v2.1.0
+[UPDATING] serde v0.1.0 -> v0.2.0
+",
+        )
+        .run();
+
+    assert_eq!(
+        p.read_file("Cargo.toml"),
+        r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                serde = "0.2" # not the newest
+                itoa = '0.1'
+
+                [dev-dependencies.log]
+                version = "^2.1"
+                default-features = false
+            "#
+    );
+    let lockfile = p.read_lockfile();
+    assert!(lockfile.contains("itoa 0.1.0"));
+    assert!(lockfile.contains("log 2.1.0"));
+    assert!(lockfile.contains("serde 0.2.0"));
+
+    // Everything is up to date now.
+    p.cargo("update --breaking")
+        .with_stderr("[UPDATING] `[..]` index")
+        .run();
+}
+
+#[test]
+fn update_breaking_spec() {
+    let p = breaking_project();
+    let manifest = p.read_file("Cargo.toml");
+
+    p.cargo("update --breaking -p log --dry-run")
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[UPGRADING] log ^1.0 -> ^2.1 in foo
+[WARNING] not updating manifests or lockfile due to dry run
+",
+        )
+        .run();
+    assert_eq!(p.read_file("Cargo.toml"), manifest);
+
+    p.cargo("update --breaking -p log")
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[UPGRADING] log ^1.0 -> ^2.1 in foo
+[UPDATING] log v1.0.0 -> v2.1.0
+",
+        )
+        .run();
+    assert!(p.read_file("Cargo.toml").contains("serde = \"0.1\""));
+
+    p.cargo("update --breaking -p bar")
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] package ID specification `bar` did not match any dependencies to upgrade",
+        )
+        .run();
+}
+
 #[test]
 fn preserve_top_comment() {
     let p = project().file("src/lib.rs", "").build();
@@ -474,3 +794,102 @@ fn dry_run_update() {
     let new_lockfile = p.read_file("Cargo.lock");
     assert_eq!(old_lockfile, new_lockfile)
 }
+
+#[test]
+fn update_explain() {
+    Package::new("bar", "0.1.1").publish();
+    Package::new("baz", "0.1.0").dep("bar", "0.1.1").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.1"
+                baz = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("bar", "0.1.2").publish();
+
+    p.cargo("update -p baz --explain bar")
+        .with_stdout(
+            "\
+`bar v0.1.1` is required by:
+  `baz v0.1.0`, with `bar = \"^0.1.1\"`
+      ... which is depended on by `foo v0.0.1 ([..])`
+  `foo v0.0.1 ([..])`, with `bar = \"^0.1\"`
+It is locked to this version in `Cargo.lock`, `cargo update -p bar:0.1.1` would update it.
+",
+        )
+        .run();
+
+    p.cargo("update --explain bar")
+        .with_stdout(
+            "\
+`bar v0.1.2` is required by:
+  `baz v0.1.0`, with `bar = \"^0.1.1\"`
+      ... which is depended on by `foo v0.0.1 ([..])`
+  `foo v0.0.1 ([..])`, with `bar = \"^0.1\"`
+It is the newest version matching these requirements.
+",
+        )
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[UPDATING] bar v0.1.1 -> v0.1.2
+",
+        )
+        .run();
+
+    p.cargo("update --explain foo")
+        .with_stdout("`foo v0.0.1 ([..])` is a member of the workspace.")
+        .run();
+}
+
+#[test]
+fn update_explain_conflict() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("bar", "1.1.0").publish();
+    Package::new("baz", "0.1.0").dep("bar", "=1.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "=1.0.0"
+                baz = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("update --explain bar")
+        .with_status(101)
+        .with_stdout(
+            "\
+(1) `foo v0.0.1 ([..])` is being resolved.
+(2) Because of (1), `baz v0.1.0` is selected, as `foo v0.0.1 ([..])` depends on `baz = \"^0.1\"`.
+(3) Because of (2), `baz v0.1.0` depends on `bar = \"= 1.1.0\"`, which matches `bar v1.1.0`.
+(4) Because of (1), `bar v1.0.0` is selected, as `foo v0.0.1 ([..])` depends on `bar = \"= 1.0.0\"`.
+(5) Because of (4), `bar v1.1.0` cannot be selected, as only one semver compatible version of `bar` can be selected.
+(6) Because of (3) and (5), no version of `bar` can be selected for `baz v0.1.0`, so resolution failed.
+",
+        )
+        .with_stderr_contains("[ERROR] failed to select a version for `bar`.")
+        .run();
+}